@@ -0,0 +1,346 @@
+//Static checks and lookahead sets of a lowered grammar.
+//
+//The sets are computed on the desugared grammar, so quantifiers and groups
+//are already plain rules, and they are only kept for the rules written in
+//the grammar. Terminals are the literals, lexical rules and character
+//classes the productions use. Predicates match the empty string and add
+//nothing to a FIRST set.
+//
+//Diagnostics:
+//  errors:   references to undefined productions or lexical rules, and
+//            productions that derive no finite input
+//  warnings: rules not reachable from a start or skip declaration, a
+//            grammar without start declarations is a library and has none
+use super::grammar::*;
+use super::frontend_error::*;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fmt::Result as FormatResult;
+
+//A terminal of a FIRST or FOLLOW set
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Lookahead {
+    Symbol(Symbol),
+    //end of the input, follows the entry points
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub error: FrontendError,
+    pub severity: Severity,
+    //the rule that is undefined, unreachable or unproductive
+    pub name: String,
+    //the rule the finding is in, empty for skip declarations, and its
+    //position
+    pub rule: String,
+    pub line: usize,
+    pub offset: usize,
+}
+
+pub struct Analysis {
+    nullable: HashSet<String>,
+    first: HashMap<String, HashSet<Lookahead>>,
+    follow: HashMap<String, HashSet<Lookahead>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Grammar {
+    pub fn analyze(&self) -> Analysis {
+        let desugared = self.desugar();
+        let mut analysis = Analysis {
+            nullable: HashSet::new(),
+            first: HashMap::new(),
+            follow: HashMap::new(),
+            diagnostics: Vec::new(),
+        };
+        analysis.compute_nullable(&desugared);
+        analysis.compute_first(&desugared);
+        analysis.compute_follow(&desugared, &self.entry_points());
+        analysis.check_references(self);
+        analysis.check_productive(&desugared);
+        analysis.check_reachable(self);
+        analysis.first.retain(|name, _| self.production(name).is_some());
+        analysis.follow.retain(|name, _| self.production(name).is_some());
+        analysis.diagnostics.sort_by_key(|d| (d.line, d.offset));
+        analysis
+    }
+}
+
+impl Analysis {
+    pub fn nullable(&self, production: &str) -> bool {
+        self.nullable.contains(production)
+    }
+
+    //Sorted by their text, empty for unknown productions
+    pub fn first(&self, production: &str) -> Vec<&Lookahead> {
+        sorted(self.first.get(production))
+    }
+
+    pub fn follow(&self, production: &str) -> Vec<&Lookahead> {
+        sorted(self.follow.get(production))
+    }
+
+    //Ordered by position
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    fn compute_nullable(&mut self, grammar: &Grammar) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for prod in &grammar.productions {
+                if self.nullable.contains(&prod.name) {
+                    continue;
+                }
+                let nullable = prod.nullable || prod.alternatives.iter().any(|alternative| {
+                    alternative.items.iter().all(|item| self.item_nullable(item))
+                });
+                if nullable {
+                    self.nullable.insert(prod.name.clone());
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    fn item_nullable(&self, item: &Item) -> bool {
+        item.nullable() || match item.symbol {
+            Symbol::Epsilon | Symbol::And(_) | Symbol::Not(_) => true,
+            Symbol::Name(ref name) => self.nullable.contains(name),
+            _ => false,
+        }
+    }
+
+    fn compute_first(&mut self, grammar: &Grammar) {
+        for prod in &grammar.productions {
+            self.first.insert(prod.name.clone(), HashSet::new());
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for prod in &grammar.productions {
+                let mut first = HashSet::new();
+                for alternative in &prod.alternatives {
+                    first.extend(self.first_of(&alternative.items).0);
+                }
+                let known = self.first.get_mut(&prod.name).expect("inserted above");
+                let count = known.len();
+                known.extend(first);
+                changed |= known.len() != count;
+            }
+        }
+    }
+
+    //FIRST set of a sequence of items and whether all of them are nullable
+    fn first_of(&self, items: &[Item]) -> (HashSet<Lookahead>, bool) {
+        let mut first = HashSet::new();
+        for item in items {
+            match item.symbol {
+                Symbol::Literal(_) | Symbol::Lexical(_) | Symbol::Class(_) => {
+                    first.insert(Lookahead::Symbol(item.symbol.clone()));
+                }
+                Symbol::Name(ref name) => if let Some(set) = self.first.get(name) {
+                    first.extend(set.iter().cloned());
+                },
+                _ => {}
+            }
+            if !self.item_nullable(item) {
+                return (first, false);
+            }
+        }
+        (first, true)
+    }
+
+    fn compute_follow(&mut self, grammar: &Grammar, entry_points: &[&str]) {
+        for prod in &grammar.productions {
+            self.follow.insert(prod.name.clone(), HashSet::new());
+        }
+        for name in entry_points {
+            if let Some(follow) = self.follow.get_mut(*name) {
+                follow.insert(Lookahead::End);
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for prod in &grammar.productions {
+                for alternative in &prod.alternatives {
+                    for (i, item) in alternative.items.iter().enumerate() {
+                        let name = match item.symbol {
+                            Symbol::Name(ref name) if self.follow.contains_key(name) => name,
+                            _ => continue,
+                        };
+                        let (mut follow, nullable) = self.first_of(&alternative.items[i + 1..]);
+                        if nullable {
+                            follow.extend(self.follow[&prod.name].iter().cloned());
+                        }
+                        let known = self.follow.get_mut(name).expect("checked above");
+                        let count = known.len();
+                        known.extend(follow);
+                        changed |= known.len() != count;
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_references(&mut self, grammar: &Grammar) {
+        let rules = grammar.productions.iter().chain(grammar.lexical_rules.iter());
+        let skip = grammar.skip.iter().map(|skip| (&skip.alternatives, ("", 0, 0)));
+        let bodies = rules
+            .map(|r| (&r.alternatives, (r.name.as_str(), r.line, r.offset)))
+            .chain(skip);
+        for start in &grammar.starts {
+            if grammar.production(&start.name).is_none() {
+                let error = FrontendError::UnknownProduction;
+                self.report(error, Severity::Error, &start.name, ("", start.line, start.offset));
+            }
+        }
+        for (alternatives, owner) in bodies {
+            let mut reported = HashSet::new();
+            let mut pending = Vec::new();
+            push_symbols(&mut pending, alternatives);
+            while let Some(symbol) = pending.pop() {
+                let (error, name) = match *symbol {
+                    Symbol::Name(ref name) if grammar.production(name).is_none() => {
+                        (FrontendError::UnknownProduction, name)
+                    }
+                    Symbol::Lexical(ref name) if !defined_lexical(grammar, name) => {
+                        (FrontendError::UnknownLexicalRule, name)
+                    }
+                    Symbol::Group(ref alternatives) => {
+                        push_symbols(&mut pending, alternatives);
+                        continue;
+                    }
+                    Symbol::And(ref symbol) | Symbol::Not(ref symbol) => {
+                        pending.push(symbol);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if reported.insert(symbol) {
+                    self.report(error, Severity::Error, name, owner);
+                }
+            }
+        }
+    }
+
+    //A production is productive when some alternative only uses
+    //terminals and productive productions
+    fn check_productive(&mut self, desugared: &Grammar) {
+        let mut productive: HashSet<&str> = HashSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for prod in &desugared.productions {
+                if productive.contains(prod.name.as_str()) {
+                    continue;
+                }
+                let derives = prod.nullable || prod.alternatives.iter().any(|alternative| {
+                    alternative.items.iter().all(|item| match item.symbol {
+                        //undefined names are reported by check_references
+                        Symbol::Name(ref name) => {
+                            productive.contains(name.as_str()) || desugared.production(name).is_none()
+                        }
+                        _ => true,
+                    })
+                });
+                if derives {
+                    productive.insert(&prod.name);
+                    changed = true;
+                }
+            }
+        }
+        for prod in &desugared.productions {
+            if prod.helper.is_none() && !productive.contains(prod.name.as_str()) {
+                let error = FrontendError::UnproductiveRule;
+                self.report(error, Severity::Error, &prod.name, owner(prod));
+            }
+        }
+    }
+
+    fn check_reachable(&mut self, grammar: &Grammar) {
+        if grammar.starts.is_empty() {
+            return;
+        }
+        let reachable = grammar.reachable();
+        for prod in &grammar.productions {
+            if !reachable.contains(&Symbol::Name(prod.name.clone())) {
+                let error = FrontendError::UnreachableRule;
+                self.report(error, Severity::Warning, &prod.name, owner(prod));
+            }
+        }
+        for rule in &grammar.lexical_rules {
+            if !reachable.contains(&Symbol::Lexical(rule.name.clone())) {
+                let error = FrontendError::UnreachableRule;
+                self.report(error, Severity::Warning, &rule.name, owner(rule));
+            }
+        }
+    }
+
+    //`owner` is the name and position of the rule the finding is in
+    fn report(
+        &mut self,
+        error: FrontendError,
+        severity: Severity,
+        name: &str,
+        owner: (&str, usize, usize),
+    ) {
+        let (rule, line, offset) = owner;
+        self.diagnostics.push(Diagnostic {
+            error,
+            severity,
+            name: name.to_string(),
+            rule: rule.to_string(),
+            line,
+            offset,
+        });
+    }
+}
+
+fn owner(rule: &Production) -> (&str, usize, usize) {
+    (&rule.name, rule.line, rule.offset)
+}
+
+//Lexical rules of the grammar and the ones the scanner synthesizes
+fn defined_lexical(grammar: &Grammar, name: &str) -> bool {
+    grammar.lexical_rule(name).is_some()
+        || grammar.layout && (name == INDENT || name == DEDENT || name == NEWLINE)
+}
+
+fn sorted(set: Option<&HashSet<Lookahead>>) -> Vec<&Lookahead> {
+    let mut sorted: Vec<&Lookahead> = set.map(|set| set.iter().collect()).unwrap_or_default();
+    sorted.sort_by_key(|lookahead| lookahead.to_string());
+    sorted
+}
+
+impl Display for Lookahead {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            Lookahead::Symbol(ref symbol) => write!(f, "{}", symbol),
+            Lookahead::End => write!(f, "EOI"),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
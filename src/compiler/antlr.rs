@@ -0,0 +1,982 @@
+//ANTLR4 grammars, written by a backend, see backend.rs, and read by
+//`gideon import`, see import.rs.
+//
+//  grammar Expr;
+//  expr
+//      : t=term ('+' term)*
+//      ;
+//  NUMBER : [0-9]+ ;
+//  SKIP : (' ' | '\t') -> skip ;
+//
+//ANTLR has groups, quantifiers and labels, so rules keep their shape. Parser
+//rules start lowercase and lexer rules uppercase, names are changed to fit
+//and to avoid the keywords of ANTLR. The grammar is named after its first
+//entry point, ANTLR wants the file named the same.
+//
+//Lexical rules the productions refer to are tokens, the others fragments,
+//the skip declarations one SKIP rule. A nullable production gets an empty
+//alternative, `X* % S` becomes `(X (S X)*)?`. Classes in productions become
+//lexer rules CLASS1, CLASS2 and so on, after the others.
+//
+//Lookaheads, actions, result types and lexer modes have no equivalent that
+//works in a combined grammar, notes ahead of the rules say what is left out.
+//
+//import reads combined, parser and lexer grammars. Lexer rules, fragments
+//included, become lexical rules, rules in a `mode` section are in that
+//mode, and skip or channel commands make a skip declaration use the rule.
+//Rules ending with EOF are the entry points, EOF itself is dropped. A
+//non-greedy `X*?` followed by Y becomes `(!Y X)*`. Actions, predicates,
+//options, arguments and labels of alternatives are left out with a warning,
+//'.' and '~' in parser rules match any token and are errors.
+use super::char_class::{self, CharClass, ClassItem};
+use super::frontend_error::FrontendError;
+use super::grammar::*;
+use super::import::*;
+
+use std::collections::HashMap;
+
+//Names ANTLR does not allow for rules
+const KEYWORDS: &[&str] = &[
+    "import", "fragment", "lexer", "parser", "grammar", "returns", "locals", "throws", "catch", "finally",
+    "mode", "options", "tokens", "channels", "EOF",
+];
+
+pub fn export(grammar: &Grammar) -> String {
+    let mut exporter = Exporter {
+        names: HashMap::new(),
+        taken: Vec::new(),
+        classes: Vec::new(),
+        notes: Vec::new(),
+    };
+    for prod in &grammar.productions {
+        let name = exporter.unique(parser_name(&prod.name));
+        exporter.names.insert((false, prod.name.clone()), name);
+    }
+    for rule in &grammar.lexical_rules {
+        let name = exporter.unique(lexer_name(&rule.name));
+        exporter.names.insert((true, rule.name.clone()), name);
+    }
+    let starts = grammar.entry_points();
+    let title = starts
+        .first()
+        .cloned()
+        .or_else(|| grammar.productions.first().map(|prod| prod.name.as_str()))
+        .unwrap_or("Grammar");
+    let mut rules = String::new();
+    //the start first, ANTLR tools take the first rule for it
+    let mut productions: Vec<&Production> = grammar.productions.iter().collect();
+    if let Some(i) = productions.iter().position(|prod| Some(&prod.name.as_str()) == starts.first()) {
+        let prod = productions.remove(i);
+        productions.insert(0, prod);
+    }
+    for prod in productions {
+        let name = exporter.name(false, &prod.name);
+        let mut alternatives: Vec<String> = prod
+            .alternatives
+            .iter()
+            .map(|alternative| exporter.sequence(alternative, false))
+            .collect();
+        if prod.nullable && !alternatives.iter().any(String::is_empty) {
+            alternatives.push(String::new());
+        }
+        if prod.result_type.is_some() {
+            exporter.note("actions and result types are left out");
+        }
+        rules.push_str(&format!("\n{}\n", name));
+        for (i, alternative) in alternatives.iter().enumerate() {
+            let separator = if i == 0 { ':' } else { '|' };
+            if alternative.is_empty() {
+                rules.push_str(&format!("    {}\n", separator));
+            } else {
+                rules.push_str(&format!("    {} {}\n", separator, alternative));
+            }
+        }
+        rules.push_str("    ;\n");
+    }
+    //lexical rules the productions do not refer to are fragments
+    let tokens: Vec<&str> = grammar
+        .productions
+        .iter()
+        .flat_map(|prod| lexical_references(&prod.alternatives))
+        .collect();
+    if !grammar.lexical_rules.is_empty() {
+        rules.push('\n');
+    }
+    for rule in &grammar.lexical_rules {
+        let name = exporter.name(true, &rule.name);
+        let fragment = if tokens.contains(&rule.name.as_str()) { "" } else { "fragment " };
+        if !rule.modes.is_empty() || rule.action.is_some() {
+            exporter.note("lexer modes are left out, ANTLR allows them only in lexer grammars");
+        }
+        if rule.result_type.is_some() {
+            exporter.note("actions and result types are left out");
+        }
+        let alternatives = exporter.alternatives(&rule.alternatives, true);
+        rules.push_str(&format!("{}{} : {} ;\n", fragment, name, alternatives));
+    }
+    if !grammar.skip.is_empty() {
+        let name = exporter.unique("SKIP".to_string());
+        let alternatives: Vec<Alternative> = grammar
+            .skip
+            .iter()
+            .flat_map(|skip| skip.alternatives.iter().cloned())
+            .collect();
+        if grammar.skip.iter().any(|skip| !skip.modes.is_empty()) {
+            exporter.note("lexer modes are left out, ANTLR allows them only in lexer grammars");
+        }
+        let alternatives = exporter.alternatives(&alternatives, true);
+        rules.push_str(&format!("{} : ({}) -> skip ;\n", name, alternatives));
+    }
+    for (i, class) in exporter.classes.clone().iter().enumerate() {
+        rules.push_str(&format!("CLASS{} : {} ;\n", i + 1, class));
+    }
+    if grammar.layout {
+        exporter.note(&format!("{}, {} and {} need a lexer that tracks the indentation", INDENT, DEDENT, NEWLINE));
+    }
+    if starts.len() > 1 {
+        exporter.note(&format!("the entry points are {}", starts.join(", ")));
+    }
+    if !starts.is_empty() {
+        exporter.note("add EOF to the entry points to match whole inputs");
+    }
+    let mut out = String::new();
+    for note in &exporter.notes {
+        out.push_str(&format!("// {}\n", note));
+    }
+    if !exporter.notes.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&format!("grammar {};\n", lexer_name(title)));
+    out.push_str(&rules);
+    out
+}
+
+//The lexical rules `alternatives` refer to
+fn lexical_references(alternatives: &[Alternative]) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut stack = Vec::new();
+    push_symbols(&mut stack, alternatives);
+    while let Some(symbol) = stack.pop() {
+        match *symbol {
+            Symbol::Lexical(ref name) => out.push(name.as_str()),
+            Symbol::Group(ref alternatives) => push_symbols(&mut stack, alternatives),
+            Symbol::And(ref symbol) | Symbol::Not(ref symbol) => stack.push(symbol),
+            _ => {}
+        }
+    }
+    out
+}
+
+//Parser rules start lowercase
+fn parser_name(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) => c.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+//Lexer rules start uppercase
+fn lexer_name(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+struct Exporter {
+    //rule names by whether the rule is lexical and its Gideon name
+    names: HashMap<(bool, String), String>,
+    taken: Vec<String>,
+    //lexer sets of the classes in productions, CLASS1 is the first
+    classes: Vec<String>,
+    notes: Vec<String>,
+}
+
+impl Exporter {
+    fn note(&mut self, note: &str) {
+        if !self.notes.iter().any(|n| n == note) {
+            self.notes.push(note.to_string());
+        }
+    }
+
+    //`name` with a '_' for every rule or keyword that has it already
+    fn unique(&mut self, name: String) -> String {
+        let mut name = name;
+        while self.taken.contains(&name) || KEYWORDS.contains(&name.as_str()) {
+            name.push('_');
+        }
+        self.taken.push(name.clone());
+        name
+    }
+
+    fn name(&self, lexical: bool, name: &str) -> String {
+        match self.names.get(&(lexical, name.to_string())) {
+            Some(name) => name.clone(),
+            //virtual rules of layout mode
+            None => lexer_name(name),
+        }
+    }
+
+    fn alternatives(&mut self, alternatives: &[Alternative], lexical: bool) -> String {
+        let alternatives: Vec<String> = alternatives.iter().map(|a| self.sequence(a, lexical)).collect();
+        alternatives.join(" | ")
+    }
+
+    fn sequence(&mut self, alternative: &Alternative, lexical: bool) -> String {
+        if alternative.action.is_some() {
+            self.note("actions and result types are left out");
+        }
+        let items: Vec<String> = alternative
+            .items
+            .iter()
+            .filter_map(|item| self.item(item, lexical))
+            .collect();
+        items.join(" ")
+    }
+
+    //None for items matching only the empty string
+    fn item(&mut self, item: &Item, lexical: bool) -> Option<String> {
+        if item.symbol == Symbol::Epsilon {
+            return None;
+        }
+        let symbol = self.symbol(&item.symbol, lexical);
+        //labels collect every match of a repeated item
+        let labeled = match item.label {
+            Some(ref label) if item.quantifier.repeated() || item.separator.is_some() => {
+                format!("{}+={}", label, symbol)
+            }
+            Some(ref label) => format!("{}={}", label, symbol),
+            None => symbol.clone(),
+        };
+        let separator = match item.separator {
+            Some(ref separator) => separator,
+            None => {
+                return Some(match item.quantifier {
+                    Quantifier::One => labeled,
+                    Quantifier::Optional => format!("{}?", labeled),
+                    Quantifier::Star => format!("{}*", labeled),
+                    Quantifier::Plus => format!("{}+", labeled),
+                })
+            }
+        };
+        let separator_symbol = self.symbol(&separator.symbol, lexical);
+        let mut list = format!("{} ({} {})*", labeled, separator_symbol, labeled);
+        if separator.trailing {
+            list = format!("{} {}?", list, separator_symbol);
+        }
+        Some(match item.quantifier {
+            Quantifier::Plus => list,
+            _ => format!("({})?", list),
+        })
+    }
+
+    fn symbol(&mut self, symbol: &Symbol, lexical: bool) -> String {
+        match *symbol {
+            Symbol::Literal(ref value) => literal(value),
+            Symbol::Lexical(ref name) => self.name(true, name),
+            Symbol::Name(ref name) => self.name(false, name),
+            Symbol::Class(ref class) => {
+                let set = self.class(class);
+                if lexical {
+                    return set;
+                }
+                //sets are lexer only
+                let n = match self.classes.iter().position(|c| *c == set) {
+                    Some(i) => i + 1,
+                    None => {
+                        self.classes.push(set);
+                        self.classes.len()
+                    }
+                };
+                format!("CLASS{}", n)
+            }
+            Symbol::Epsilon => String::new(),
+            Symbol::Group(ref alternatives) => format!("({})", self.alternatives(alternatives, lexical)),
+            Symbol::And(_) | Symbol::Not(_) => {
+                self.note("lookaheads have no equivalent and are kept as comments");
+                format!("/* {} */", symbol.to_string().replace("*/", "* /"))
+            }
+        }
+    }
+
+    fn class(&mut self, class: &CharClass) -> String {
+        let mut out = String::from(if class.negated { "~[" } else { "[" });
+        for item in &class.items {
+            match *item {
+                ClassItem::Char(c) => out.push_str(&set_char(c)),
+                ClassItem::Range(from, to) => out.push_str(&format!("{}-{}", set_char(from), set_char(to))),
+                ClassItem::Property(ref name, negated) => {
+                    let p = if negated { 'P' } else { 'p' };
+                    match (name.as_str(), negated) {
+                        ("ASCII", false) => out.push_str("\\u0000-\\u007F"),
+                        ("Any", false) => out.push_str("\\u0000-\\u{10FFFF}"),
+                        ("Alphanumeric", false) => out.push_str("\\p{Alphabetic}\\p{N}"),
+                        ("ASCII", true) | ("Any", true) | ("Alphanumeric", true) => {
+                            self.note("negated ASCII, Any and Alphanumeric properties have no equivalent in sets");
+                            out.push_str(&format!("\\{}{{{}}}", p, name));
+                        }
+                        ("Whitespace", _) => out.push_str(&format!("\\{}{{White_Space}}", p)),
+                        ("Numeric", _) => out.push_str(&format!("\\{}{{N}}", p)),
+                        _ => {
+                            //categories by their short name
+                            let found = char_class::CATEGORIES.iter().find(|&&(_, long)| long == name);
+                            let name = found.map_or(name.as_str(), |&(short, _)| short);
+                            out.push_str(&format!("\\{}{{{}}}", p, name))
+                        }
+                    }
+                }
+            }
+        }
+        out.push(']');
+        out
+    }
+}
+
+fn literal(value: &str) -> String {
+    let mut out = String::from("'");
+    for c in value.chars() {
+        out.push_str(&match c {
+            '\'' => "\\'".to_string(),
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            c if c < ' ' || c == '\u{7f}' => format!("\\u{:04X}", c as u32),
+            c => c.to_string(),
+        });
+    }
+    out.push('\'');
+    out
+}
+
+//A character in a lexer set
+fn set_char(c: char) -> String {
+    match c {
+        ']' | '\\' | '-' | '^' => format!("\\{}", c),
+        '\n' => "\\n".to_string(),
+        '\r' => "\\r".to_string(),
+        '\t' => "\\t".to_string(),
+        c if c < ' ' || c == '\u{7f}' => format!("\\u{:04X}", c as u32),
+        c => c.to_string(),
+    }
+}
+
+const COMMENTS: &[(&str, &str)] = &[("//", "\n"), ("/*", "*/")];
+
+//An element with whether its quantifier is non-greedy and its position
+type Element = (Item, bool, (usize, usize));
+
+pub fn import(source: &str) -> ImportResult<Imported> {
+    let mut importer = Importer {
+        cursor: Cursor::new(source, COMMENTS),
+        warnings: Vec::new(),
+        mode: None,
+        end: false,
+        lexer: false,
+        commands: Vec::new(),
+    };
+    let mut grammar = Grammar::default();
+    let mut lexical = Vec::new();
+    importer.cursor.skip_space();
+    if importer.keyword("lexer") || importer.keyword("parser") || importer.at_keyword("grammar") {
+        importer.cursor.skip_space();
+        if !importer.keyword("grammar") {
+            return Err(importer.cursor.error(FrontendError::ExpectedName));
+        }
+        importer.identifier()?;
+        importer.cursor.expect(";", FrontendError::ExpectedEndl)?;
+    }
+    loop {
+        importer.cursor.skip_space();
+        if importer.cursor.at_end() {
+            break;
+        }
+        if importer.prequel()? {
+            continue;
+        }
+        if importer.keyword("mode") {
+            importer.mode = Some(importer.identifier()?);
+            importer.cursor.expect(";", FrontendError::ExpectedEndl)?;
+            continue;
+        }
+        let mut rule = importer.rule()?;
+        if rule.name.starts_with(char::is_uppercase) {
+            lexical.push(rule.name.clone());
+            importer.apply_commands(&mut rule, &mut grammar.skip);
+        }
+        if importer.end {
+            grammar.starts.push(EntryPoint {
+                name: rule.name.clone(),
+                line: rule.line,
+                offset: rule.offset,
+            });
+        }
+        grammar.productions.push(rule);
+    }
+    let lexical: Vec<&str> = lexical.iter().map(String::as_str).collect();
+    Ok(Imported {
+        grammar: finish(grammar, &lexical),
+        warnings: importer.warnings,
+    })
+}
+
+struct Importer {
+    cursor: Cursor,
+    warnings: Vec<ImportError>,
+    //the mode of the lexer rules being read, None for the default mode
+    mode: Option<String>,
+    //the rule being read uses EOF
+    end: bool,
+    //the rule being read is a lexer rule
+    lexer: bool,
+    //lexer commands of the rule being read, with their argument and position
+    commands: Vec<(String, Option<String>, (usize, usize))>,
+}
+
+impl Importer {
+    fn warn(&mut self, what: FrontendError, position: (usize, usize)) {
+        self.warnings.push(ImportError::new(what, position.0, position.1));
+    }
+
+    fn identifier(&mut self) -> ImportResult<String> {
+        self.cursor.skip_space();
+        match self.cursor.peek() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return Err(self.cursor.error(FrontendError::ExpectedName)),
+        }
+        Ok(self.cursor.take_while(|c| c.is_alphanumeric() || c == '_'))
+    }
+
+    fn at_keyword(&mut self, word: &str) -> bool {
+        self.cursor.skip_space();
+        let next = self.cursor.peek_at(word.chars().count());
+        self.cursor.at(word) && !next.is_some_and(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        self.at_keyword(word) && self.cursor.eat(word)
+    }
+
+    //Skips a block between `open` and `close` holding code or options,
+    //nested blocks and strings included
+    fn block(&mut self, open: char, close: char) -> ImportResult<()> {
+        self.cursor.skip_space();
+        let (line, offset) = self.cursor.position();
+        if self.cursor.peek() != Some(open) {
+            let what = if open == '{' {
+                FrontendError::ExpectedOpenCurlyBrace
+            } else {
+                FrontendError::ExpectedCode
+            };
+            return Err(self.cursor.error(what));
+        }
+        let mut depth = 0;
+        while let Some(c) = self.cursor.bump() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if c == '"' || c == '\'' {
+                while let Some(d) = self.cursor.bump() {
+                    if d == '\\' {
+                        self.cursor.bump();
+                    } else if d == c || d == '\n' {
+                        break;
+                    }
+                }
+            }
+        }
+        Err(ImportError::new(FrontendError::UnterminatedCode, line, offset))
+    }
+
+    //Options, token and channel declarations, imports and named actions
+    //ahead of the rules, false when none follows
+    fn prequel(&mut self) -> ImportResult<bool> {
+        let position = self.cursor.position();
+        if self.keyword("options") {
+            self.block('{', '}')?;
+            self.warn(FrontendError::DroppedOptions, position);
+        } else if self.keyword("tokens") {
+            self.block('{', '}')?;
+            self.warn(FrontendError::DroppedTokenDeclaration, position);
+        } else if self.keyword("channels") {
+            //only used by channel commands, which warn themselves
+            self.block('{', '}')?;
+        } else if self.keyword("import") {
+            while self.cursor.peek().is_some_and(|c| c != ';') {
+                self.cursor.bump();
+            }
+            self.cursor.expect(";", FrontendError::ExpectedEndl)?;
+            self.warn(FrontendError::DroppedImport, position);
+        } else if self.cursor.eat("@") {
+            self.identifier()?;
+            if self.cursor.eat("::") {
+                self.identifier()?;
+            }
+            self.block('{', '}')?;
+            self.warn(FrontendError::DroppedAction, position);
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn rule(&mut self) -> ImportResult<Production> {
+        self.cursor.skip_space();
+        self.keyword("fragment");
+        self.cursor.skip_space();
+        let (line, offset) = self.cursor.position();
+        let name = self.identifier()?;
+        self.lexer = name.starts_with(char::is_uppercase);
+        loop {
+            self.cursor.skip_space();
+            let position = self.cursor.position();
+            if self.cursor.peek() == Some('[') || self.keyword("returns") || self.keyword("locals") {
+                self.block('[', ']')?;
+            } else if self.keyword("throws") {
+                loop {
+                    self.identifier()?;
+                    self.cursor.skip_space();
+                    if !self.cursor.eat(",") {
+                        break;
+                    }
+                }
+            } else if self.keyword("options") {
+                self.block('{', '}')?;
+                self.warn(FrontendError::DroppedOptions, position);
+                continue;
+            } else if self.cursor.eat("@") {
+                self.identifier()?;
+                self.block('{', '}')?;
+            } else {
+                break;
+            }
+            self.warn(FrontendError::DroppedAction, position);
+        }
+        self.cursor.expect(":", FrontendError::ExpectedColon)?;
+        self.end = false;
+        self.commands.clear();
+        let alternatives = self.alternatives()?;
+        self.cursor.expect(";", FrontendError::ExpectedEndl)?;
+        loop {
+            let position = self.cursor.position();
+            if self.keyword("catch") {
+                self.block('[', ']')?;
+                self.block('{', '}')?;
+            } else if self.keyword("finally") {
+                self.block('{', '}')?;
+            } else {
+                break;
+            }
+            self.warn(FrontendError::DroppedAction, position);
+        }
+        let mut rule = rule(&name, alternatives, line, offset);
+        if self.lexer {
+            rule.modes = self.mode.iter().cloned().collect();
+        }
+        Ok(rule)
+    }
+
+    fn alternatives(&mut self) -> ImportResult<Vec<Alternative>> {
+        let mut alternatives = vec![self.alternative()?];
+        loop {
+            self.cursor.skip_space();
+            if !self.cursor.eat("|") {
+                return Ok(alternatives);
+            }
+            alternatives.push(self.alternative()?);
+        }
+    }
+
+    fn alternative(&mut self) -> ImportResult<Alternative> {
+        let mut items = Vec::new();
+        loop {
+            self.cursor.skip_space();
+            let position = self.cursor.position();
+            match self.cursor.peek() {
+                None | Some(';') | Some('|') | Some(')') => break,
+                Some('<') => {
+                    self.block('<', '>')?;
+                    self.warn(FrontendError::DroppedOptions, position);
+                }
+                Some('#') => {
+                    self.cursor.bump();
+                    self.identifier()?;
+                    self.warn(FrontendError::DroppedAlternativeLabel, position);
+                }
+                Some('{') => {
+                    self.block('{', '}')?;
+                    if self.cursor.eat("?") {
+                        self.warn(FrontendError::DroppedPredicate, position);
+                    } else {
+                        self.warn(FrontendError::DroppedAction, position);
+                    }
+                }
+                Some('-') if self.cursor.at("->") => {
+                    self.cursor.eat("->");
+                    self.lexer_commands()?;
+                }
+                _ => {
+                    if let Some(element) = self.element()? {
+                        items.push(element);
+                    }
+                }
+            }
+        }
+        Ok(Alternative {
+            items: self.lazy(items),
+            action: None,
+        })
+    }
+
+    //A non-greedy item stops where the items after it match: `X*? Y`
+    //becomes `(!Y X)* Y`
+    fn lazy(&mut self, items: Vec<Element>) -> Vec<Item> {
+        let mut out: Vec<Item> = Vec::new();
+        for (item, lazy, position) in items.into_iter().rev() {
+            if !lazy {
+                out.insert(0, item);
+                continue;
+            }
+            if out.is_empty() {
+                self.warn(FrontendError::NonGreedyAtEnd, position);
+                out.insert(0, item);
+                continue;
+            }
+            let rest = Box::new(symbol(sequence(out.clone())));
+            let quantifier = item.quantifier;
+            let mut single = item;
+            single.quantifier = Quantifier::One;
+            let guarded = sequence(vec![Item::new(Symbol::Not(rest)), single.clone()]);
+            if quantifier == Quantifier::Plus {
+                out.insert(0, quantified(guarded, Quantifier::Star));
+                out.insert(0, single);
+            } else {
+                out.insert(0, quantified(guarded, quantifier));
+            }
+        }
+        out
+    }
+
+    //An element with its quantifier, None for EOF
+    fn element(&mut self) -> ImportResult<Option<Element>> {
+        let position = self.cursor.position();
+        let label = self.label();
+        let mut item = self.atom()?;
+        let quantifier = match self.cursor.peek() {
+            Some('?') => Quantifier::Optional,
+            Some('*') => Quantifier::Star,
+            Some('+') => Quantifier::Plus,
+            _ => Quantifier::One,
+        };
+        if quantifier != Quantifier::One {
+            self.cursor.bump();
+        }
+        let lazy = quantifier != Quantifier::One && self.cursor.eat("?");
+        if self.cursor.peek() == Some('<') {
+            let position = self.cursor.position();
+            self.block('<', '>')?;
+            self.warn(FrontendError::DroppedOptions, position);
+        }
+        if item.symbol == Symbol::Epsilon {
+            //EOF
+            self.cursor.skip_space();
+            let last = matches!(self.cursor.peek(), None | Some(';') | Some('|') | Some(')'));
+            if quantifier != Quantifier::One || !last {
+                self.warn(FrontendError::DroppedEndOfInput, position);
+            }
+            return Ok(None);
+        }
+        item = quantified(item, quantifier);
+        if label.is_some() {
+            item = sequence(vec![item]);
+            item.label = label;
+        }
+        Ok(Some((item, lazy, position)))
+    }
+
+    //`x=` or `x+=` ahead of an element
+    fn label(&mut self) -> Option<String> {
+        let saved = self.cursor.save();
+        if let Ok(label) = self.identifier() {
+            self.cursor.skip_space();
+            if self.cursor.eat("+=") || (self.cursor.peek_at(1) != Some('>') && self.cursor.eat("=")) {
+                return Some(label);
+            }
+        }
+        self.cursor.restore(saved);
+        None
+    }
+
+    fn atom(&mut self) -> ImportResult<Item> {
+        self.cursor.skip_space();
+        let (line, offset) = self.cursor.position();
+        let unsupported = ImportError::new(FrontendError::UnsupportedTokenSet, line, offset);
+        match self.cursor.peek() {
+            Some('(') => {
+                self.cursor.bump();
+                let alternatives = self.alternatives()?;
+                self.cursor.expect(")", FrontendError::ExpectedCloseParen)?;
+                Ok(choice(alternatives))
+            }
+            Some('\'') => {
+                let from = self.string()?;
+                self.cursor.skip_space();
+                if !self.cursor.eat("..") {
+                    return Ok(Item::new(Symbol::Literal(from)));
+                }
+                self.cursor.skip_space();
+                let to = self.string()?;
+                let (mut from, mut to) = (from.chars(), to.chars());
+                let class = match (from.next(), from.next(), to.next(), to.next()) {
+                    (Some(from), None, Some(to), None) => CharClass::range(from, to),
+                    _ => Err(FrontendError::InvalidRange),
+                };
+                match class {
+                    Ok(class) => Ok(Item::new(Symbol::Class(class))),
+                    Err(error) => Err(ImportError::new(error, line, offset)),
+                }
+            }
+            Some('[') => Ok(Item::new(Symbol::Class(self.set()?))),
+            Some('~') if self.lexer => {
+                self.cursor.bump();
+                let item = self.atom()?;
+                match class_of(&item) {
+                    Some(mut class) => {
+                        class.negated = true;
+                        Ok(Item::new(Symbol::Class(class)))
+                    }
+                    None => Err(ImportError::new(FrontendError::InvalidCharClass, line, offset)),
+                }
+            }
+            Some('.') if self.lexer => {
+                self.cursor.bump();
+                Ok(Item::new(Symbol::Class(CharClass {
+                    negated: false,
+                    items: vec![ClassItem::Property("Any".to_string(), false)],
+                })))
+            }
+            Some('~') | Some('.') => Err(unsupported),
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.identifier()?;
+                if self.cursor.peek() == Some('[') {
+                    let position = self.cursor.position();
+                    self.block('[', ']')?;
+                    self.warn(FrontendError::DroppedAction, position);
+                }
+                if name == "EOF" {
+                    self.end = true;
+                    return Ok(Item::new(Symbol::Epsilon));
+                }
+                //tokens also when declared elsewhere
+                if name.starts_with(char::is_uppercase) {
+                    return Ok(Item::new(Symbol::Lexical(name)));
+                }
+                Ok(Item::new(Symbol::Name(name)))
+            }
+            _ => Err(self.cursor.error(FrontendError::ExpectedPart)),
+        }
+    }
+
+    //A literal between apostrophes, with its escapes resolved
+    fn string(&mut self) -> ImportResult<String> {
+        self.cursor.bump();
+        let mut out = String::new();
+        loop {
+            let position = self.cursor.position();
+            match self.cursor.bump() {
+                Some('\'') => return Ok(out),
+                Some('\\') => out.push(self.escape(position)?),
+                Some(c) => out.push(c),
+                None => return Err(self.cursor.error(FrontendError::EOI)),
+            }
+        }
+    }
+
+    //The character of an escape sequence, after its backslash
+    fn escape(&mut self, position: (usize, usize)) -> ImportResult<char> {
+        let c = match self.cursor.bump() {
+            Some('n') => Some('\n'),
+            Some('r') => Some('\r'),
+            Some('t') => Some('\t'),
+            Some('b') => Some('\u{8}'),
+            Some('f') => Some('\u{c}'),
+            Some('u') => {
+                let hex = if self.cursor.eat("{") {
+                    let hex = self.cursor.take_while(|c| c.is_ascii_hexdigit());
+                    self.cursor.eat("}");
+                    hex
+                } else {
+                    (0..4).filter_map(|_| self.cursor.bump()).collect()
+                };
+                u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32)
+            }
+            c => c,
+        };
+        c.ok_or_else(|| ImportError::new(FrontendError::ExpectedEscapeSequence, position.0, position.1))
+    }
+
+    //A lexer set like [a-z_\p{Alpha}]
+    fn set(&mut self) -> ImportResult<CharClass> {
+        let (line, offset) = self.cursor.position();
+        let unclosed = ImportError::new(FrontendError::ExpectedCloseBracket, line, offset);
+        self.cursor.bump();
+        let mut class = CharClass::default();
+        loop {
+            let position = self.cursor.position();
+            let from = match self.cursor.bump() {
+                Some(']') => break,
+                Some('\\') if self.cursor.at("p{") || self.cursor.at("P{") => {
+                    let negated = self.cursor.bump() == Some('P');
+                    self.cursor.bump();
+                    let name = self.cursor.take_while(|c| c != '}' && c != ']');
+                    if !self.cursor.eat("}") {
+                        return Err(ImportError::new(FrontendError::ExpectedProperty, position.0, position.1));
+                    }
+                    match property(&name) {
+                        Some(name) => class.items.push(ClassItem::Property(name.to_string(), negated)),
+                        None => {
+                            let what = FrontendError::UnknownCharacterProperty;
+                            return Err(ImportError::new(what, position.0, position.1));
+                        }
+                    }
+                    continue;
+                }
+                Some('\\') => self.escape(position)?,
+                Some(c) => c,
+                None => return Err(unclosed),
+            };
+            if self.cursor.peek() != Some('-') || self.cursor.peek_at(1) == Some(']') {
+                class.items.push(ClassItem::Char(from));
+                continue;
+            }
+            self.cursor.bump();
+            let to = match self.cursor.bump() {
+                Some('\\') => self.escape(position)?,
+                Some(c) => c,
+                None => return Err(unclosed),
+            };
+            if from > to {
+                return Err(ImportError::new(FrontendError::InvalidRange, position.0, position.1));
+            }
+            class.items.push(ClassItem::Range(from, to));
+        }
+        if class.items.is_empty() {
+            return Err(ImportError::new(FrontendError::InvalidCharClass, line, offset));
+        }
+        Ok(class)
+    }
+
+    //Lexer commands after '->'
+    fn lexer_commands(&mut self) -> ImportResult<()> {
+        loop {
+            self.cursor.skip_space();
+            let position = self.cursor.position();
+            let command = self.identifier()?;
+            self.cursor.skip_space();
+            let argument = if self.cursor.eat("(") {
+                self.cursor.skip_space();
+                let argument = self.cursor.take_while(|c| c.is_alphanumeric() || c == '_');
+                if argument.is_empty() {
+                    return Err(self.cursor.error(FrontendError::ExpectedName));
+                }
+                self.cursor.expect(")", FrontendError::ExpectedCloseParen)?;
+                Some(argument)
+            } else {
+                None
+            };
+            self.commands.push((command, argument, position));
+            self.cursor.skip_space();
+            if !self.cursor.eat(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    //The lexer commands of `rule`: mode changes become its action, skip and
+    //channel a skip declaration in its mode
+    fn apply_commands(&mut self, rule: &mut Production, skip: &mut Vec<Skip>) {
+        let mut skipped = false;
+        let commands = ::std::mem::take(&mut self.commands);
+        for (command, argument, position) in commands {
+            match (command.as_str(), argument) {
+                ("skip", None) => skipped = true,
+                ("channel", Some(_)) => {
+                    skipped = true;
+                    self.warn(FrontendError::HiddenChannel, position);
+                }
+                ("pushMode", Some(mode)) => rule.action = Some(ModeAction::Push(mode)),
+                ("mode", Some(mode)) => {
+                    self.warn(FrontendError::ModeSwitch, position);
+                    rule.action = Some(ModeAction::Push(mode));
+                }
+                ("popMode", None) => rule.action = Some(ModeAction::Pop),
+                _ => self.warn(FrontendError::DroppedLexerCommand, position),
+            }
+        }
+        if !skipped {
+            return;
+        }
+        let alternative = Alternative {
+            items: vec![Item::new(Symbol::Name(rule.name.clone()))],
+            action: None,
+        };
+        match skip.iter_mut().find(|skip| skip.modes == rule.modes) {
+            Some(skip) => skip.alternatives.push(alternative),
+            None => skip.push(Skip {
+                modes: rule.modes.clone(),
+                alternatives: vec![alternative],
+            }),
+        }
+    }
+}
+
+//The characters `item` matches as a class, None when it matches more
+fn class_of(item: &Item) -> Option<CharClass> {
+    if item.quantifier != Quantifier::One || item.separator.is_some() {
+        return None;
+    }
+    match item.symbol {
+        Symbol::Literal(ref value) => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(CharClass {
+                    negated: false,
+                    items: vec![ClassItem::Char(c)],
+                }),
+                _ => None,
+            }
+        }
+        Symbol::Class(ref class) if !class.negated => Some(class.clone()),
+        Symbol::Group(ref alternatives) => {
+            let mut out = CharClass::default();
+            for alternative in alternatives {
+                match alternative.items.len() {
+                    1 => out.items.extend(class_of(&alternative.items[0])?.items),
+                    _ => return None,
+                }
+            }
+            Some(out)
+        }
+        _ => None,
+    }
+}
+
+//The Gideon name of a Unicode property of ANTLR
+fn property(name: &str) -> Option<&'static str> {
+    let name = match name {
+        "Alpha" => "Alphabetic",
+        "Lower" => "Lowercase",
+        "Upper" => "Uppercase",
+        "WSpace" | "Space" => "White_Space",
+        "Digit" => "Nd",
+        "Cntrl" => "Cc",
+        name => name,
+    };
+    char_class::property_name(name)
+}
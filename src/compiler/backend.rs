@@ -0,0 +1,112 @@
+//Output formats of `gideon generate`.
+//
+//Backends work on a loaded grammar, which holds the rules of the grammars
+//it uses or extends, so the output stands alone: it has no use, extends or
+//override statements.
+//
+//  gideon:      the grammar in canonical Gideon syntax
+//  bnf:         the same with groups and quantifiers rewritten, see desugar.rs
+//  w3c-ebnf:    EBNF of the XML specification, see notation.rs
+//  iso-ebnf:    ISO/IEC 14977 EBNF
+//  abnf:        RFC 5234 ABNF
+//  grammophone: plain BNF for Grammophone, see grammophone.rs
+//  bison:       a Bison input file without actions, see bison.rs
+//  antlr:       an ANTLR4 combined grammar, see antlr.rs
+//
+//Actions, labels and result types only matter to parsers generated by
+//Gideon, the other formats leave them out with a note, in a comment of the
+//output or, for formats without comments, from Backend::left_out.
+use super::antlr;
+use super::bison;
+use super::grammar::*;
+use super::grammophone;
+use super::notation::{self, Notation};
+
+pub const LEFT_OUT: &str = "actions, labels and result types are left out";
+pub const MODES_LEFT_OUT: &str = "lexer modes are left out";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Gideon,
+    Bnf,
+    W3cEbnf,
+    IsoEbnf,
+    Abnf,
+    Grammophone,
+    Bison,
+    Antlr,
+}
+
+pub const BACKENDS: &[Backend] = &[
+    Backend::Gideon,
+    Backend::Bnf,
+    Backend::W3cEbnf,
+    Backend::IsoEbnf,
+    Backend::Abnf,
+    Backend::Grammophone,
+    Backend::Bison,
+    Backend::Antlr,
+];
+
+impl Backend {
+    pub fn from_name(name: &str) -> Option<Backend> {
+        BACKENDS.iter().cloned().find(|backend| backend.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Gideon => "gideon",
+            Backend::Bnf => "bnf",
+            Backend::W3cEbnf => "w3c-ebnf",
+            Backend::IsoEbnf => "iso-ebnf",
+            Backend::Abnf => "abnf",
+            Backend::Grammophone => "grammophone",
+            Backend::Bison => "bison",
+            Backend::Antlr => "antlr",
+        }
+    }
+
+    pub fn generate(self, grammar: &Grammar) -> String {
+        let grammar = Grammar {
+            uses: Vec::new(),
+            extends: Vec::new(),
+            overrides: Vec::new(),
+            ..grammar.clone()
+        };
+        match self {
+            Backend::Gideon => grammar.to_string(),
+            Backend::Bnf => grammar.desugar().to_string(),
+            Backend::W3cEbnf => notation::export(&grammar, Notation::W3c),
+            Backend::IsoEbnf => notation::export(&grammar, Notation::Iso),
+            Backend::Abnf => notation::export(&grammar, Notation::Abnf),
+            Backend::Grammophone => grammophone::export(&grammar),
+            Backend::Bison => bison::export(&grammar),
+            Backend::Antlr => antlr::export(&grammar),
+        }
+    }
+
+    //Notes on what the output of a format without comments leaves out
+    pub fn left_out(self, grammar: &Grammar) -> Vec<&'static str> {
+        match self {
+            //lexical rules are left out as a whole
+            Backend::Grammophone if grammar.productions.iter().any(has_actions) => vec![LEFT_OUT],
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn has_actions(rule: &Production) -> bool {
+    fn in_alternatives(alternatives: &[Alternative]) -> bool {
+        alternatives.iter().any(|alternative| {
+            alternative.action.is_some()
+                || alternative.items.iter().any(|item| {
+                    item.label.is_some()
+                        || match item.symbol {
+                            Symbol::Group(ref alternatives) => in_alternatives(alternatives),
+                            _ => false,
+                        }
+                })
+        })
+    }
+    rule.result_type.is_some() || in_alternatives(&rule.alternatives)
+}
@@ -0,0 +1,210 @@
+//Bison input files, see backend.rs.
+//
+//  %token TOK_NUMBER
+//  %token TOK_ARROW "->"
+//  %start Expr
+//  %%
+//  Expr
+//      : Expr '+' TOK_NUMBER
+//      | %empty
+//      ;
+//
+//The productions are desugared, see desugar.rs, with helpers of the same
+//alternatives shared. Lexical rules the productions refer to are tokens,
+//their Gideon definitions are listed in a comment for writing the scanner.
+//Literals of a single ASCII character are character tokens, the others get
+//a token named as in grammophone.rs with the literal as its alias. Token
+//names start with TOK_, the generated parser defines them as C macros,
+//which would clash with NULL, TRUE or EOF. A nullable production gets an
+//%empty alternative.
+//
+//Bison has one start symbol and no lookahead, classes in productions become
+//tokens TOK_CLASS1, TOK_CLASS2 and so on. Actions, labels, result types and lexer
+//modes are left out, notes ahead of the declarations say what changed.
+use super::backend;
+use super::grammar::*;
+use super::grammophone;
+
+use std::collections::HashMap;
+
+//Ahead of every token name, see above
+const TOKEN_PREFIX: &str = "TOK_";
+
+pub fn export(grammar: &Grammar) -> String {
+    let bnf = grammar.desugar().share_helpers();
+    let mut exporter = Exporter {
+        taken: bnf.productions.iter().map(|prod| prod.name.clone()).collect(),
+        tokens: HashMap::new(),
+        declared: Vec::new(),
+        classes: 0,
+        notes: Vec::new(),
+    };
+    let mut rules = String::new();
+    for prod in &bnf.productions {
+        rules.push_str(&format!("\n{}\n", prod.name));
+        let mut alternatives: Vec<String> = prod
+            .alternatives
+            .iter()
+            .map(|alternative| exporter.sequence(alternative))
+            .collect();
+        if prod.nullable && !alternatives.iter().any(|a| a == "%empty") {
+            alternatives.push("%empty".to_string());
+        }
+        for (i, alternative) in alternatives.iter().enumerate() {
+            rules.push_str(&format!("    {} {}\n", if i == 0 { ':' } else { '|' }, alternative));
+        }
+        rules.push_str("    ;\n");
+        if prod.result_type.is_some() {
+            exporter.note(backend::LEFT_OUT);
+        }
+    }
+    let starts = grammar.entry_points();
+    if starts.len() > 1 {
+        exporter.note(&format!("Bison has one start symbol, {} are entry points too", starts[1..].join(", ")));
+    }
+    let modes = grammar.lexical_rules.iter().any(|rule| !rule.modes.is_empty() || rule.action.is_some());
+    if modes || grammar.skip.iter().any(|skip| !skip.modes.is_empty()) {
+        exporter.note(backend::MODES_LEFT_OUT);
+    }
+    if grammar.layout {
+        let p = TOKEN_PREFIX;
+        let tokens = format!("{}{}, {}{} and {}{}", p, INDENT, p, DEDENT, p, NEWLINE);
+        exporter.note(&format!("the scanner makes {} from the indentation", tokens));
+    }
+    let mut out = String::new();
+    if !exporter.notes.is_empty() {
+        out.push_str("/*\n");
+        for note in &exporter.notes {
+            out.push_str(&format!(" * {}\n", note));
+        }
+        out.push_str(" */\n\n");
+    }
+    //the lexical rules as written, the scanner is not generated
+    if !grammar.lexical_rules.is_empty() || !grammar.skip.is_empty() {
+        out.push_str("/* tokens of the scanner:\n");
+        for rule in &grammar.lexical_rules {
+            let name = match exporter.tokens.get(&(true, rule.name.clone())) {
+                Some(token) => token.clone(),
+                None => format!("{{{}}}", rule.name),
+            };
+            out.push_str(&format!(" *   {}: {}\n", name, alternatives(&rule.alternatives)));
+        }
+        for skip in &grammar.skip {
+            out.push_str(&format!(" *   skipped: {}\n", alternatives(&skip.alternatives)));
+        }
+        out.push_str(" */\n\n");
+    }
+    for (token, alias) in &exporter.declared {
+        match *alias {
+            Some(ref alias) => out.push_str(&format!("%token {} {}\n", token, alias)),
+            None => out.push_str(&format!("%token {}\n", token)),
+        }
+    }
+    if let Some(start) = starts.first() {
+        out.push_str(&format!("%start {}\n", start));
+    }
+    out.push_str("\n%%\n");
+    out.push_str(&rules);
+    out
+}
+
+fn alternatives(alternatives: &[Alternative]) -> String {
+    let alternatives: Vec<String> = alternatives.iter().map(Alternative::to_string).collect();
+    alternatives.join(" | ").replace("*/", "* /")
+}
+
+struct Exporter {
+    //names of the nonterminals and tokens
+    taken: Vec<String>,
+    //tokens by whether they are lexical rules or literals and their name or
+    //value, classes are literals of their Gideon text
+    tokens: HashMap<(bool, String), String>,
+    //the %token declarations with their aliases
+    declared: Vec<(String, Option<String>)>,
+    //the CLASS tokens declared
+    classes: usize,
+    notes: Vec<String>,
+}
+
+impl Exporter {
+    fn note(&mut self, note: &str) {
+        if !self.notes.iter().any(|n| n == note) {
+            self.notes.push(note.to_string());
+        }
+    }
+
+    fn sequence(&mut self, alternative: &Alternative) -> String {
+        if alternative.action.is_some() {
+            self.note(backend::LEFT_OUT);
+        }
+        let symbols: Vec<String> = alternative.items.iter().filter_map(|item| self.item(item)).collect();
+        if symbols.is_empty() {
+            "%empty".to_string()
+        } else {
+            symbols.join(" ")
+        }
+    }
+
+    //None for items matching only the empty string
+    fn item(&mut self, item: &Item) -> Option<String> {
+        if item.label.is_some() {
+            self.note(backend::LEFT_OUT);
+        }
+        match item.symbol {
+            Symbol::Name(ref name) => Some(name.clone()),
+            Symbol::Lexical(ref name) => Some(self.token(true, name, name.to_uppercase(), None)),
+            Symbol::Literal(ref value) => Some(self.literal(value)),
+            Symbol::Class(ref class) => {
+                self.note("classes in productions are tokens TOK_CLASS1, TOK_CLASS2 and so on");
+                let class = format!("class {}", class);
+                if !self.tokens.contains_key(&(false, class.clone())) {
+                    self.classes += 1;
+                }
+                let name = format!("CLASS{}", self.classes);
+                Some(self.token(false, &class, name, None))
+            }
+            Symbol::And(_) | Symbol::Not(_) => {
+                self.note("lookaheads have no equivalent and are left out");
+                None
+            }
+            Symbol::Epsilon | Symbol::Group(_) => None,
+        }
+    }
+
+    fn literal(&mut self, value: &str) -> String {
+        let mut chars = value.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            match c {
+                '\'' => return "'\\''".to_string(),
+                '\\' => return "'\\\\'".to_string(),
+                '\n' => return "'\\n'".to_string(),
+                '\t' => return "'\\t'".to_string(),
+                ' '..='~' => return format!("'{}'", c),
+                _ => {}
+            }
+        }
+        let alias = format!("\"{}\"", escape(value));
+        let name = grammophone::literal_name(value).to_uppercase();
+        self.token(false, value, name, Some(alias.clone()));
+        alias
+    }
+
+    //The token for a lexical rule or literal, declared the first time
+    fn token(&mut self, lexical: bool, key: &str, name: String, alias: Option<String>) -> String {
+        let key = (lexical, key.to_string());
+        if let Some(token) = self.tokens.get(&key) {
+            return alias.unwrap_or_else(|| token.clone());
+        }
+        let name = format!("{}{}", TOKEN_PREFIX, name);
+        let mut token = name.clone();
+        let mut n = 1;
+        while self.taken.contains(&token) {
+            token = if n == 1 { format!("{}_TOKEN", name) } else { format!("{}_TOKEN{}", name, n) };
+            n += 1;
+        }
+        self.taken.push(token.clone());
+        self.tokens.insert(key, token.clone());
+        self.declared.push((token.clone(), alias.clone()));
+        alias.unwrap_or(token)
+    }
+}
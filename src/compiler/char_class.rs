@@ -0,0 +1,303 @@
+//Character classes used by grammar parts
+//
+//  [a-zA-Z_]       characters and ranges
+//  [^"\\]          negated class
+//  [\u{0}-\u{1F}]  characters by their hexadecimal code point
+//  \p{Alphabetic}  Unicode property, \P{...} negates it
+//  "0".."9"        range between two single character literals
+//
+//Binary properties are decided with the standard library, general categories
+//such as Lu or Punctuation with the table of unicode.rs. A one letter category
+//matches all categories starting with it, LC matches Lu, Ll and Lt.
+use super::frontend_error::*;
+use super::unicode;
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::fmt::Result as FormatResult;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClassItem {
+    Char(char),
+    Range(char, char),
+    Property(String, bool),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CharClass {
+    pub negated: bool,
+    pub items: Vec<ClassItem>,
+}
+
+pub const PROPERTIES: &[&str] = &[
+    "Alphabetic",
+    "Alphanumeric",
+    "Lowercase",
+    "Uppercase",
+    "White_Space",
+    "Whitespace",
+    "Numeric",
+    "ASCII",
+    "Any",
+];
+
+//General categories by short and long name
+pub const CATEGORIES: &[(&str, &str)] = &[
+    ("L", "Letter"),
+    ("LC", "Cased_Letter"),
+    ("Lu", "Uppercase_Letter"),
+    ("Ll", "Lowercase_Letter"),
+    ("Lt", "Titlecase_Letter"),
+    ("Lm", "Modifier_Letter"),
+    ("Lo", "Other_Letter"),
+    ("M", "Mark"),
+    ("Mn", "Nonspacing_Mark"),
+    ("Mc", "Spacing_Mark"),
+    ("Me", "Enclosing_Mark"),
+    ("N", "Number"),
+    ("Nd", "Decimal_Number"),
+    ("Nl", "Letter_Number"),
+    ("No", "Other_Number"),
+    ("P", "Punctuation"),
+    ("Pc", "Connector_Punctuation"),
+    ("Pd", "Dash_Punctuation"),
+    ("Ps", "Open_Punctuation"),
+    ("Pe", "Close_Punctuation"),
+    ("Pi", "Initial_Punctuation"),
+    ("Pf", "Final_Punctuation"),
+    ("Po", "Other_Punctuation"),
+    ("S", "Symbol"),
+    ("Sm", "Math_Symbol"),
+    ("Sc", "Currency_Symbol"),
+    ("Sk", "Modifier_Symbol"),
+    ("So", "Other_Symbol"),
+    ("Z", "Separator"),
+    ("Zs", "Space_Separator"),
+    ("Zl", "Line_Separator"),
+    ("Zp", "Paragraph_Separator"),
+    ("C", "Other"),
+    ("Cc", "Control"),
+    ("Cf", "Format"),
+    ("Cs", "Surrogate"),
+    ("Co", "Private_Use"),
+    ("Cn", "Unassigned"),
+];
+
+//The name as written in PROPERTIES or CATEGORIES, None for an unknown one
+pub fn property_name(name: &str) -> Option<&'static str> {
+    let property = PROPERTIES.iter().find(|property| **property == name).cloned();
+    let category = || {
+        let found = CATEGORIES.iter().find(|&&(short, long)| short == name || long == name);
+        found.map(|&(short, long)| if short == name { short } else { long })
+    };
+    property.or_else(category)
+}
+
+//The two letter general category of `c`
+pub fn general_category(c: char) -> &'static str {
+    let c = c as u32;
+    let found = unicode::CATEGORIES.binary_search_by(|&(from, to, _)| {
+        if to < c {
+            Ordering::Less
+        } else if from > c {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    });
+    match found {
+        Ok(index) => unicode::CATEGORIES[index].2,
+        Err(_) => "Cn",
+    }
+}
+
+//None for an unknown property name
+pub fn property_matches(name: &str, c: char) -> Option<bool> {
+    let out = match name {
+        "Alphabetic" => c.is_alphabetic(),
+        "Alphanumeric" => c.is_alphanumeric(),
+        "Lowercase" => c.is_lowercase(),
+        "Uppercase" => c.is_uppercase(),
+        "White_Space" | "Whitespace" => c.is_whitespace(),
+        "Numeric" => c.is_numeric(),
+        "ASCII" => c.is_ascii(),
+        "Any" => true,
+        _ => {
+            let found = CATEGORIES.iter().find(|&&(short, long)| short == name || long == name);
+            let short = found?.0;
+            let category = general_category(c);
+            match short {
+                "LC" => category == "Lu" || category == "Ll" || category == "Lt",
+                _ if short.len() == 1 => category.starts_with(short),
+                _ => category == short,
+            }
+        }
+    };
+    Some(out)
+}
+
+impl CharClass {
+    pub fn range(from: char, to: char) -> Result<CharClass, FrontendError> {
+        if from > to {
+            return Err(FrontendError::InvalidRange);
+        }
+        Ok(CharClass {
+            negated: false,
+            items: vec![ClassItem::Range(from, to)],
+        })
+    }
+
+    //`\p{Name}` or `\P{Name}` as written in the grammar
+    pub fn property(value: &[char]) -> Result<CharClass, FrontendError> {
+        let mut chars = value.iter().cloned().peekable();
+        let item = parse_escape(&mut chars)?;
+        if chars.next().is_some() {
+            return Err(FrontendError::InvalidCharClass);
+        }
+        Ok(CharClass {
+            negated: false,
+            items: vec![item],
+        })
+    }
+
+    //Contents of a bracket class, without the brackets
+    pub fn parse(value: &[char]) -> Result<CharClass, FrontendError> {
+        let mut chars = value.iter().cloned().peekable();
+        let mut class = CharClass::default();
+        if chars.peek() == Some(&'^') {
+            chars.next();
+            class.negated = true;
+        }
+        while chars.peek().is_some() {
+            let item = match chars.next() {
+                Some('\\') => parse_escape(&mut chars)?,
+                Some(c) => ClassItem::Char(c),
+                None => break,
+            };
+            let from = match item {
+                ClassItem::Char(from) => from,
+                _ => {
+                    class.items.push(item);
+                    continue;
+                }
+            };
+            let mut ahead = chars.clone();
+            if ahead.next() == Some('-') && ahead.peek().is_some() {
+                chars.next();
+                let to = match chars.next() {
+                    Some('\\') => match parse_escape(&mut chars)? {
+                        ClassItem::Char(to) => to,
+                        _ => return Err(FrontendError::InvalidRange),
+                    },
+                    Some(to) => to,
+                    None => return Err(FrontendError::InvalidRange),
+                };
+                if from > to {
+                    return Err(FrontendError::InvalidRange);
+                }
+                class.items.push(ClassItem::Range(from, to));
+            } else {
+                class.items.push(item);
+            }
+        }
+        if class.items.is_empty() {
+            return Err(FrontendError::InvalidCharClass);
+        }
+        Ok(class)
+    }
+
+    pub fn matches(&self, c: char) -> bool {
+        let found = self.items.iter().any(|item| match *item {
+            ClassItem::Char(x) => x == c,
+            ClassItem::Range(from, to) => from <= c && c <= to,
+            ClassItem::Property(ref name, negated) => {
+                property_matches(name, c).unwrap_or(false) != negated
+            }
+        });
+        found != self.negated
+    }
+}
+
+//Escape sequence after a backslash
+fn parse_escape<I>(chars: &mut ::std::iter::Peekable<I>) -> Result<ClassItem, FrontendError>
+where
+    I: Iterator<Item = char>,
+{
+    match chars.next() {
+        Some(kind @ 'p') | Some(kind @ 'P') => {
+            if chars.next() != Some('{') {
+                return Err(FrontendError::ExpectedProperty);
+            }
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) => name.push(c),
+                    None => return Err(FrontendError::ExpectedProperty),
+                }
+            }
+            if property_name(&name).is_none() {
+                return Err(FrontendError::UnknownCharacterProperty);
+            }
+            Ok(ClassItem::Property(name, kind == 'P'))
+        }
+        Some('u') if chars.peek() == Some(&'{') => {
+            chars.next();
+            let mut hex = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) if c.is_ascii_hexdigit() && hex.len() < 6 => hex.push(c),
+                    _ => return Err(FrontendError::InvalidCharClass),
+                }
+            }
+            let code = u32::from_str_radix(&hex, 16).map_err(|_| FrontendError::InvalidCharClass)?;
+            ::std::char::from_u32(code).map(ClassItem::Char).ok_or(FrontendError::InvalidCharClass)
+        }
+        Some('n') => Ok(ClassItem::Char('\n')),
+        Some('t') => Ok(ClassItem::Char('\t')),
+        Some('r') => Ok(ClassItem::Char('\r')),
+        Some(c) => Ok(ClassItem::Char(c)),
+        None => Err(FrontendError::InvalidCharClass),
+    }
+}
+
+fn write_char(f: &mut Formatter, c: char) -> FormatResult {
+    match c {
+        ']' | '\\' | '^' | '-' | '[' => write!(f, "\\{}", c),
+        '\n' => write!(f, "\\n"),
+        '\t' => write!(f, "\\t"),
+        '\r' => write!(f, "\\r"),
+        _ if c.is_control() => write!(f, "\\u{{{:X}}}", c as u32),
+        _ => write!(f, "{}", c),
+    }
+}
+
+impl Display for ClassItem {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            ClassItem::Char(c) => write_char(f, c),
+            ClassItem::Range(from, to) => {
+                write_char(f, from)?;
+                write!(f, "-")?;
+                write_char(f, to)
+            }
+            ClassItem::Property(ref name, negated) => {
+                write!(f, "\\{}{{{}}}", if negated { 'P' } else { 'p' }, name)
+            }
+        }
+    }
+}
+
+impl Display for CharClass {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        write!(f, "[")?;
+        if self.negated {
+            write!(f, "^")?;
+        }
+        for item in &self.items {
+            write!(f, "{}", item)?;
+        }
+        write!(f, "]")
+    }
+}
@@ -0,0 +1,304 @@
+//Rewrites EBNF groups and quantifiers to plain BNF for the backends that
+//need it. Every group or quantified part becomes a helper rule named after
+//the rule that uses it:
+//
+//  A -> B ( C | D ) ;   A -> B AGroup1 ;    AGroup1 -> C | D ;
+//  A -> B? ;            A -> AOpt1 ;        AOpt1? -> B ;
+//  A -> B* ;            A -> AList1 ;       AList1? -> B AList1 ;
+//  A -> B+ ;            A -> AList1 ;       AList1 -> B AList1 | B ;
+//
+//Separated lists use the recursive form of a head and a tail, see
+//Desugar::separated. A group under a predicate becomes a group helper, the
+//predicate itself has no BNF form and stays.
+//
+//Helpers of productions are productions, helpers of lexical rules and skip
+//declarations are lexical rules, so they stay on their layer.
+use super::grammar::*;
+
+use std::collections::{HashMap, HashSet};
+
+struct Desugar {
+    names: HashSet<String>,
+    productions: Vec<Production>,
+    lexical_rules: Vec<Production>,
+}
+
+impl Grammar {
+    pub fn desugar(&self) -> Grammar {
+        let mut desugar = Desugar {
+            names: self.productions
+                .iter()
+                .chain(self.lexical_rules.iter())
+                .map(|p| p.name.clone())
+                .collect(),
+            productions: Vec::new(),
+            lexical_rules: Vec::new(),
+        };
+        let mut grammar = Grammar {
+            uses: self.uses.clone(),
+            extends: self.extends.clone(),
+            overrides: self.overrides.clone(),
+            starts: self.starts.clone(),
+            productions: Vec::new(),
+            lexical_rules: Vec::new(),
+            skip: Vec::new(),
+            layout: self.layout,
+        };
+        for prod in &self.productions {
+            let alternatives = desugar.alternatives(prod, false, &prod.alternatives);
+            grammar.productions.push(Production {
+                alternatives,
+                ..prod.clone()
+            });
+            grammar.productions.append(&mut desugar.productions);
+        }
+        for rule in &self.lexical_rules {
+            let alternatives = desugar.alternatives(rule, true, &rule.alternatives);
+            grammar.lexical_rules.push(Production {
+                alternatives,
+                ..rule.clone()
+            });
+            grammar.lexical_rules.append(&mut desugar.lexical_rules);
+        }
+        for skip in &self.skip {
+            let owner = Production {
+                name: "Skip".to_string(),
+                nullable: false,
+                result_type: None,
+                alternatives: Vec::new(),
+                modes: Vec::new(),
+                action: None,
+                helper: None,
+                line: 0,
+                offset: 0,
+            };
+            grammar.skip.push(Skip {
+                modes: skip.modes.clone(),
+                alternatives: desugar.alternatives(&owner, true, &skip.alternatives),
+            });
+            grammar.lexical_rules.append(&mut desugar.lexical_rules);
+        }
+        grammar
+    }
+
+    //Replaces helpers with the same alternatives as an earlier helper by it,
+    //desugar adds one for every group or quantified part
+    pub fn share_helpers(mut self) -> Grammar {
+        let mut shared: HashMap<String, String> = HashMap::new();
+        let mut kept: Vec<Production> = Vec::new();
+        for prod in self.productions.drain(..) {
+            let same = kept.iter().find(|other| {
+                prod.helper.is_some()
+                    && other.helper == prod.helper
+                    && other.nullable == prod.nullable
+                    && other.alternatives == prod.alternatives
+            });
+            match same {
+                Some(other) => {
+                    shared.insert(prod.name.clone(), other.name.clone());
+                }
+                None => kept.push(prod),
+            }
+        }
+        for prod in &mut kept {
+            for alternative in &mut prod.alternatives {
+                for item in &mut alternative.items {
+                    if let Symbol::Name(ref mut name) = item.symbol {
+                        if let Some(other) = shared.get(name) {
+                            *name = other.clone();
+                        }
+                    }
+                }
+            }
+        }
+        self.productions = kept;
+        self
+    }
+
+    //True when no group, quantifier or predicate is left
+    pub fn is_bnf(&self) -> bool {
+        let plain = |alternatives: &[Alternative]| {
+            alternatives.iter().all(|alternative| {
+                alternative.items.iter().all(|item| match item.symbol {
+                    Symbol::Group(_) | Symbol::And(_) | Symbol::Not(_) => false,
+                    _ => item.quantifier == Quantifier::One && item.separator.is_none(),
+                })
+            })
+        };
+        self.productions.iter().all(|p| plain(&p.alternatives))
+            && self.lexical_rules.iter().all(|p| plain(&p.alternatives))
+            && self.skip.iter().all(|s| plain(&s.alternatives))
+    }
+}
+
+impl Desugar {
+    fn alternatives(
+        &mut self,
+        owner: &Production,
+        lexical: bool,
+        alternatives: &[Alternative],
+    ) -> Vec<Alternative> {
+        alternatives
+            .iter()
+            .map(|alternative| Alternative {
+                items: alternative
+                    .items
+                    .iter()
+                    .map(|item| Item {
+                        label: item.label.clone(),
+                        ..self.item(owner, lexical, item)
+                    })
+                    .collect(),
+                action: alternative.action.clone(),
+            })
+            .collect()
+    }
+
+    fn item(&mut self, owner: &Production, lexical: bool, item: &Item) -> Item {
+        //what one repetition of the item matches
+        let predicate = self.predicate(owner, lexical, &item.symbol);
+        let body = match predicate.as_ref().unwrap_or(&item.symbol) {
+            Symbol::Group(alternatives) => self.alternatives(owner, lexical, alternatives),
+            symbol if item.quantifier == Quantifier::One => return Item::new(symbol.clone()),
+            symbol => vec![
+                Alternative {
+                    items: vec![Item::new(symbol.clone())],
+                    action: None,
+                },
+            ],
+        };
+        if let Some(ref separator) = item.separator {
+            return self.separated(owner, lexical, item.quantifier, body, separator);
+        }
+        let (kind, helper, nullable) = match item.quantifier {
+            Quantifier::One => ("Group", Helper::Group, false),
+            Quantifier::Optional => ("Opt", Helper::Optional, true),
+            Quantifier::Star => ("List", Helper::List, true),
+            Quantifier::Plus => ("List", Helper::List, false),
+        };
+        let name = self.fresh(&owner.name, kind);
+        let alternatives = if item.quantifier.repeated() {
+            let mut alternatives = append(&body, reference(lexical, &name));
+            if item.quantifier == Quantifier::Plus {
+                alternatives.extend(body);
+            }
+            alternatives
+        } else {
+            body
+        };
+        self.add(owner, lexical, &name, helper, nullable, alternatives);
+        reference(lexical, &name)
+    }
+
+    //The predicate with its operand desugared, None for other symbols
+    fn predicate(&mut self, owner: &Production, lexical: bool, symbol: &Symbol) -> Option<Symbol> {
+        match *symbol {
+            Symbol::And(ref operand) => {
+                let operand = self.item(owner, lexical, &Item::new((**operand).clone()));
+                Some(Symbol::And(Box::new(operand.symbol)))
+            }
+            Symbol::Not(ref operand) => {
+                let operand = self.item(owner, lexical, &Item::new((**operand).clone()));
+                Some(Symbol::Not(Box::new(operand.symbol)))
+            }
+            _ => None,
+        }
+    }
+
+    //  A -> B* % C ;    A -> AList1 ;    AList1? -> B AList2 ;    AList2? -> C B AList2 ;
+    //  A -> B+ %% C ;   A -> AList1 ;    AList1 -> B AList2 ;     AList2? -> C AList3 ;
+    //                                    AList3? -> B AList2 ;
+    fn separated(
+        &mut self,
+        owner: &Production,
+        lexical: bool,
+        quantifier: Quantifier,
+        body: Vec<Alternative>,
+        separator: &Separator,
+    ) -> Item {
+        let separator_item = self.item(owner, lexical, &Item::new(separator.symbol.clone()));
+        let list = self.fresh(&owner.name, "List");
+        let tail = self.fresh(&owner.name, "List");
+        let tail_item = reference(lexical, &tail);
+        let nullable = quantifier == Quantifier::Star;
+        self.add(owner, lexical, &list, Helper::List, nullable, append(&body, tail_item.clone()));
+        if separator.trailing {
+            let rest = self.fresh(&owner.name, "List");
+            let alternatives = vec![
+                Alternative {
+                    items: vec![separator_item, reference(lexical, &rest)],
+                    action: None,
+                },
+            ];
+            self.add(owner, lexical, &tail, Helper::Tail, true, alternatives);
+            self.add(owner, lexical, &rest, Helper::Tail, true, append(&body, tail_item));
+        } else {
+            let alternatives = append(&body, tail_item)
+                .into_iter()
+                .map(|mut alternative| {
+                    alternative.items.insert(0, separator_item.clone());
+                    alternative
+                })
+                .collect();
+            self.add(owner, lexical, &tail, Helper::Tail, true, alternatives);
+        }
+        reference(lexical, &list)
+    }
+
+    fn add(
+        &mut self,
+        owner: &Production,
+        lexical: bool,
+        name: &str,
+        helper: Helper,
+        nullable: bool,
+        alternatives: Vec<Alternative>,
+    ) {
+        let production = Production {
+            name: name.to_string(),
+            nullable,
+            result_type: None,
+            alternatives,
+            modes: Vec::new(),
+            action: None,
+            helper: Some(helper),
+            line: owner.line,
+            offset: owner.offset,
+        };
+        if lexical {
+            self.lexical_rules.push(production);
+        } else {
+            self.productions.push(production);
+        }
+    }
+
+    fn fresh(&mut self, owner: &str, kind: &str) -> String {
+        let mut index = 1;
+        loop {
+            let name = format!("{}{}{}", owner, kind, index);
+            if self.names.insert(name.clone()) {
+                return name;
+            }
+            index += 1;
+        }
+    }
+}
+
+fn reference(lexical: bool, name: &str) -> Item {
+    if lexical {
+        Item::new(Symbol::Lexical(name.to_string()))
+    } else {
+        Item::new(Symbol::Name(name.to_string()))
+    }
+}
+
+//Every alternative of `body` followed by `item`
+fn append(body: &[Alternative], item: Item) -> Vec<Alternative> {
+    body.iter()
+        .cloned()
+        .map(|mut alternative| {
+            alternative.items.push(item.clone());
+            alternative
+        })
+        .collect()
+}
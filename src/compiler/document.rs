@@ -0,0 +1,248 @@
+//A grammar file open in an editor, kept lexed and parsed across edits.
+//
+//Relexing restarts at the end of the last ';' token ahead of the edit. The
+//lexer keeps no state between tokens and a ';' is always a token of its
+//own, so from there it produces what lexing the whole file would. It stops
+//at the first ';' past the edit that the old tokens also end at, the tokens
+//after it are kept and moved.
+//
+//Reparsing works on the declarations at the top of the lossless tree. The
+//declarations ahead of the edit are kept up to the last complete one, one
+//that ends with its ';' and has no errors, and parsing restarts after it.
+//The region reparsed ends with the first complete declaration past the
+//edit, the parser never looks beyond the ';' ending it, so the declarations
+//after it are kept as they are. Once the parser stopped at input it could
+//not use, the rest of the file is reparsed, as the parser may now get
+//further.
+//
+//Positions are character indices into the text.
+use super::frontend_error::FrontendError;
+use super::lexer::{Lexer, Trivia};
+use super::parser::Parser;
+use super::syntax::{self, GreenElement, GreenNode, SyntaxKind, SyntaxNode};
+
+use std::rc::Rc;
+
+//A token of the lexer, the text the lexer failed on is one Error token
+//that ends at the next ';' from where it failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LexedToken {
+    pub kind: SyntaxKind,
+    pub start: usize,
+    pub end: usize,
+    pub error: Option<FrontendError>,
+}
+
+pub struct Document {
+    text: Vec<char>,
+    tokens: Vec<LexedToken>,
+    tree: Rc<GreenNode>,
+}
+
+impl Document {
+    pub fn new(source: &str) -> Document {
+        let text: Vec<char> = source.chars().collect();
+        let mut tokens = Vec::new();
+        lex(&text, 0, &mut tokens, |_| false);
+        let tree = Rc::new(parse_region(&text, 0));
+        Document { text, tokens, tree }
+    }
+
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    pub fn chars(&self) -> &[char] {
+        &self.text
+    }
+
+    pub fn tokens(&self) -> &[LexedToken] {
+        &self.tokens
+    }
+
+    pub fn tree(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.tree.clone())
+    }
+
+    //Replaces `start..end` with `text`
+    pub fn edit(&mut self, start: usize, end: usize, text: &str) {
+        let end = end.min(self.text.len());
+        let start = start.min(end);
+        let replacement: Vec<char> = text.chars().collect();
+        let new_end = start + replacement.len();
+        self.text.splice(start..end, replacement);
+        self.relex(start, end, new_end);
+        self.reparse(start, end, new_end);
+    }
+
+    fn relex(&mut self, start: usize, old_end: usize, new_end: usize) {
+        let safe = self.tokens
+            .iter()
+            .rposition(|token| token.kind == SyntaxKind::Endl && token.end <= start);
+        let from = safe.map(|i| self.tokens[i].end).unwrap_or(0);
+        let old = self.tokens.split_off(safe.map(|i| i + 1).unwrap_or(0));
+        //where the old tokens are in sync with the new ones again
+        let mut resume = None;
+        lex(&self.text, from, &mut self.tokens, |token| {
+            if token.kind != SyntaxKind::Endl || token.end < new_end {
+                return false;
+            }
+            let end = token.end - new_end + old_end;
+            resume = old.iter().position(|old| old.kind == SyntaxKind::Endl && old.end == end);
+            resume.is_some()
+        });
+        if let Some(resume) = resume {
+            self.tokens.extend(old[resume + 1..].iter().map(|token| LexedToken {
+                start: token.start + new_end - old_end,
+                end: token.end + new_end - old_end,
+                ..*token
+            }));
+        }
+    }
+
+    fn reparse(&mut self, start: usize, old_end: usize, new_end: usize) {
+        let children = self.tree.children();
+        let mut ends = Vec::with_capacity(children.len());
+        let mut offset = 0;
+        for child in children {
+            offset += child.width();
+            ends.push(offset);
+        }
+        let mut first = ends.iter().take_while(|end| **end <= start).count();
+        while first > 0 && !complete(&children[first - 1]) {
+            first -= 1;
+        }
+        let from = if first > 0 { ends[first - 1] } else { 0 };
+        let stopped = children[first..].iter().any(|child| child.kind() == SyntaxKind::Error);
+        let mut last = if stopped {
+            children.len()
+        } else {
+            let region = children[first..]
+                .iter()
+                .zip(&ends[first..])
+                .position(|(child, end)| *end >= old_end && complete(child));
+            region.map(|i| first + i + 1).unwrap_or(children.len())
+        };
+        let reparsed = loop {
+            let to = if last == children.len() {
+                self.text.len()
+            } else {
+                ends[last - 1] + new_end - old_end
+            };
+            let region = parse_region(&self.text[from..to], from);
+            if to == self.text.len() {
+                break region;
+            }
+            let parsed = region.children();
+            if parsed.iter().any(|child| child.kind() == SyntaxKind::Error) {
+                last = children.len();
+            } else if parsed.last().map(complete).unwrap_or(false) {
+                break region;
+            } else {
+                last += 1;
+            }
+        };
+        let mut merged = children[..first].to_vec();
+        merged.extend(reparsed.children().iter().cloned());
+        merged.extend(children[last..].iter().cloned());
+        self.tree = Rc::new(GreenNode::new(SyntaxKind::Root, merged));
+    }
+}
+
+//A declaration ending with its ';' without errors, the parser is at the
+//start of a declaration after it
+fn complete(element: &GreenElement) -> bool {
+    fn last_token(node: &GreenNode, last: &mut Option<SyntaxKind>) -> bool {
+        for child in node.children() {
+            match *child {
+                GreenElement::Node(ref child) => if !last_token(child, last) {
+                    return false;
+                },
+                GreenElement::Token(ref token) => match token.kind() {
+                    SyntaxKind::Error => return false,
+                    kind if !kind.is_trivia() => *last = Some(kind),
+                    _ => {}
+                },
+            }
+        }
+        true
+    }
+
+    let mut last = None;
+    match *element {
+        GreenElement::Node(ref node) => last_token(node, &mut last) && last == Some(SyntaxKind::Endl),
+        GreenElement::Token(_) => false,
+    }
+}
+
+//The top level of the tree for `region`, starting at index `from` of the
+//text. Past the first declaration the parser ends quietly at input it cannot
+//use, so the error it reports for such a region is dropped.
+fn parse_region(region: &[char], from: usize) -> GreenNode {
+    let parser = Parser::with_trivia(region, Trivia::Attach);
+    let tree = parser.parse();
+    let root = syntax::build(region, &tree);
+    if from == 0 || tree.is_ok() {
+        return root;
+    }
+    let kept = root
+        .children()
+        .iter()
+        .filter(|child| child.kind() != SyntaxKind::Error || child.width() > 0)
+        .cloned()
+        .collect();
+    GreenNode::new(SyntaxKind::Root, kept)
+}
+
+//Lexes `text` from index `from` into `out` until its end or until `stop`
+//accepts a token
+fn lex<F>(text: &[char], from: usize, out: &mut Vec<LexedToken>, mut stop: F)
+where
+    F: FnMut(&LexedToken) -> bool,
+{
+    let mut from = from;
+    while from < text.len() {
+        let lexer = Lexer::new(&text[from..]);
+        loop {
+            let token = match lexer.next() {
+                Ok(token) => {
+                    let data = token.data();
+                    LexedToken {
+                        kind: SyntaxKind::from(token),
+                        start: from + data.start(),
+                        end: from + data.end(),
+                        error: None,
+                    }
+                }
+                Err(FrontendError::EOI) => return,
+                Err(what) => {
+                    //a ';' always starts a token, lexing restarts at the
+                    //first one the lexer did not look at before failing
+                    let start = from + lexer.token_start();
+                    let failed = from + lexer.position();
+                    let end = text[failed..]
+                        .iter()
+                        .position(|c| *c == ';')
+                        .map(|i| failed + i)
+                        .unwrap_or(text.len());
+                    let token = LexedToken {
+                        kind: SyntaxKind::Error,
+                        start,
+                        end,
+                        error: Some(what),
+                    };
+                    out.push(token);
+                    if stop(&token) {
+                        return;
+                    }
+                    from = end;
+                    break;
+                }
+            };
+            out.push(token);
+            if stop(&token) {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//Canonical printing of grammar source, rebuilt from the concrete syntax tree.
+//
+//The tree has to be parsed with Trivia::Attach, comments and blank lines
+//are taken from the trivia of the tokens:
+//
+//  - every declaration starts a line, runs of blank lines between
+//    declarations become one and comments keep their place
+//  - tokens are separated by one space, except around brackets, path
+//    separators, ranges, quantifiers, predicates and labels
+//  - a union keeps the line breaks written before or after its top level
+//    '|', continuation lines start with the '|' below the tip of the '->',
+//    like
+//
+//      Modifier -> "in" {NAME} ModeList
+//                | "push" {NAME}
+//                | "pop" ;
+//
+//    other line breaks inside a declaration are dropped
+//  - comments inside a declaration end their line, the declaration
+//    continues on the next one
+//  - literals are double quoted unless they contain an unescaped double
+//    quote, epsilon is written None
+use super::syntax_tree::*;
+use super::token::Token;
+use super::visitor::*;
+
+//Continuation lines of a declaration without an arrow
+const INDENT: usize = 4;
+
+struct Printer {
+    out: String,
+    line: String,
+    //the line holds indentation only
+    fresh: bool,
+    //no space before the next token
+    glue: bool,
+    //the next token starts a declaration
+    declaration: bool,
+    //unions entered, 1 for the alternatives of a production or skip
+    depth: usize,
+    //column of the '|' on continuation lines
+    align: Option<usize>,
+    //where the top level '|' ending the line starts, it moves to the next
+    //line when the source breaks after it
+    last_or: Option<usize>,
+}
+
+pub fn format(grammar: &Grammar, trailing_trivia: &[char]) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        line: String::new(),
+        fresh: true,
+        glue: false,
+        declaration: true,
+        depth: 0,
+        align: None,
+        last_or: None,
+    };
+    printer.visit_grammar(grammar);
+    printer.leading(trailing_trivia, true);
+    printer.out
+}
+
+impl<'a> Visitor<'a> for Printer {
+    fn visit_union(&mut self, union: &Union<'a>) {
+        self.depth += 1;
+        if self.depth == 1 && self.align.is_none() {
+            //skip declarations, the '|' goes two columns left of the union
+            self.align = Some(width(&self.line) - 1);
+        }
+        walk_union(self, union);
+        self.depth -= 1;
+    }
+
+    fn visit_part(&mut self, part: &Part<'a>) {
+        match *part {
+            Part::Labeled(Ok(name), Ok(colon), ref labeled) => {
+                self.visit_token(name);
+                self.visit_token(colon);
+                self.glue = true;
+                if let Ok(ref labeled) = **labeled {
+                    self.visit_part(labeled);
+                }
+            }
+            _ => walk_part(self, part),
+        }
+    }
+
+    fn visit_token(&mut self, token: Token<'a>) {
+        let data = token.data();
+        let or = match token {
+            Token::Or(_) => self.depth == 1 && self.align.is_some(),
+            _ => false,
+        };
+        if self.declaration {
+            self.leading(data.trivia(), false);
+            self.declaration = false;
+        } else {
+            self.inner(data.trivia(), or);
+        }
+        match token {
+            //the '|' goes below the tip of the arrow
+            Token::Arrow(data) | Token::AppendArrow(data) if self.depth == 0 => {
+                self.align = Some(width(&self.line) + data.value().len());
+            }
+            _ => {}
+        }
+        let start = self.line.len();
+        if !self.fresh && !self.glue && !glued_left(&token) {
+            self.line.push(' ');
+        }
+        self.line.push_str(&text(&token));
+        self.last_or = if or && !self.fresh { Some(start) } else { None };
+        self.fresh = false;
+        self.glue = glued_right(&token);
+        if let Token::Endl(_) = token {
+            self.end_line(0);
+            self.declaration = true;
+            self.align = None;
+        }
+    }
+}
+
+impl Printer {
+    //Comments and blank lines ahead of a declaration or the end of input
+    fn leading(&mut self, trivia: &[char], end: bool) {
+        let start = self.out.is_empty();
+        let (trailing, lines) = split(trivia, start);
+        if let Some(comment) = trailing {
+            self.out.pop();
+            self.out.push(' ');
+            self.out.push_str(&comment);
+            self.out.push('\n');
+        }
+        let mut blank = false;
+        for line in lines {
+            match line {
+                Some(comment) => {
+                    if blank && !self.out.is_empty() {
+                        self.out.push('\n');
+                    }
+                    self.out.push_str(&comment);
+                    self.out.push('\n');
+                    blank = false;
+                }
+                None => blank = true,
+            }
+        }
+        if blank && !end && !self.out.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    //Comments and line breaks ahead of a token inside a declaration
+    fn inner(&mut self, trivia: &[char], or: bool) {
+        let (trailing, lines) = split(trivia, false);
+        let comments: Vec<String> = lines.into_iter().flatten().collect();
+        let indent = match self.align {
+            Some(align) if or => align,
+            Some(align) => align + 2,
+            None => INDENT,
+        };
+        if trailing.is_some() || !comments.is_empty() {
+            if let Some(comment) = trailing {
+                self.line.push(' ');
+                self.line.push_str(&comment);
+            }
+            for comment in comments {
+                self.end_line(indent);
+                self.line.push_str(&comment);
+            }
+            self.end_line(indent);
+        } else if trivia.contains(&'\n') {
+            if let (Some(start), Some(align)) = (self.last_or, self.align) {
+                self.line.truncate(start);
+                self.end_line(align);
+                self.line.push('|');
+                self.fresh = false;
+            } else if or {
+                self.end_line(indent);
+            }
+        }
+    }
+
+    fn end_line(&mut self, indent: usize) {
+        if !self.fresh || !self.line.trim().is_empty() {
+            self.out.push_str(self.line.trim_end());
+            self.out.push('\n');
+        }
+        self.line = " ".repeat(indent);
+        self.fresh = true;
+        self.glue = false;
+        self.last_or = None;
+    }
+}
+
+//The comment ending the line of the previous token, then every following
+//line as its comment or None when blank. At the start of the input there
+//is no previous token.
+fn split(trivia: &[char], start: bool) -> (Option<String>, Vec<Option<String>>) {
+    let text: String = trivia.iter().collect();
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    //the indentation of the token itself, or a comment ending the input
+    let last = lines.pop().filter(|last| last.contains('#'));
+    let trailing = if start || lines.is_empty() {
+        None
+    } else {
+        comment(lines.remove(0))
+    };
+    let mut out: Vec<Option<String>> = lines.into_iter().map(comment).collect();
+    out.extend(last.map(comment));
+    (trailing, out)
+}
+
+fn comment(line: &str) -> Option<String> {
+    line.find('#').map(|start| line[start..].trim_end().to_string())
+}
+
+fn width(line: &str) -> usize {
+    line.chars().count()
+}
+
+fn glued_left(token: &Token) -> bool {
+    matches!(
+        *token,
+        Token::CloseParen(_)
+            | Token::OpenAngle(_)
+            | Token::CloseAngle(_)
+            | Token::CloseBrace(_)
+            | Token::Comma(_)
+            | Token::Colon(_)
+            | Token::PathSeperator(_)
+            | Token::Range(_)
+            | Token::QMark(_)
+            | Token::Star(_)
+            | Token::Plus(_)
+    )
+}
+
+fn glued_right(token: &Token) -> bool {
+    matches!(
+        *token,
+        Token::OpenParen(_)
+            | Token::OpenAngle(_)
+            | Token::OpenBrace(_)
+            | Token::PathSeperator(_)
+            | Token::Range(_)
+            | Token::Amp(_)
+            | Token::Bang(_)
+    )
+}
+
+//Source text of the token, with the delimiters the lexer strips
+fn text(token: &Token) -> String {
+    let body = token.data().text();
+    match *token {
+        Token::Literal(_) if unescaped(&body, '"') => format!("'{}'", body),
+        Token::Literal(_) => format!("\"{}\"", body),
+        Token::Class(_) => format!("[{}]", body),
+        Token::Code(_) => format!("{{{}}}", body),
+        Token::Epsilon(_) => "None".to_string(),
+        _ => body,
+    }
+}
+
+fn unescaped(body: &str, quote: char) -> bool {
+    let mut escaped = false;
+    for c in body.chars() {
+        if c == quote && !escaped {
+            return true;
+        }
+        escaped = !escaped && c == '\\';
+    }
+    false
+}
@@ -2,11 +2,13 @@ use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::fmt::Result as FormatResult;
 
-#[derive(Debug, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FrontendError {
     //general
     EOI,
     Default,
+    UnreadableFile,
 
     //lexical
     ExpectedArrowTip,
@@ -14,7 +16,13 @@ pub enum FrontendError {
     ExpectedEscapeSequence,
     ExpectedIdentifier,
     UnrecognizedInput,
-    ExpectedColon,
+    ExpectedCloseBracket,
+    ExpectedProperty,
+    ExpectedRangeDot,
+    ExpectedCode,
+    UnterminatedCode,
+    UnterminatedLiteral,
+    ExpectedType,
 
     //syntactical
     ExpectedProdStartOrUse,
@@ -22,36 +30,187 @@ pub enum FrontendError {
     ExpectedArrow,
     ExpectedEndl,
     ExpectedUse,
+    ExpectedSkip,
+    ExpectedLayout,
+    ExpectedStart,
+    ExpectedExtends,
+    ExpectedOverride,
     ExpectedCloseCurlyBrace,
+    ExpectedCloseParen,
+    ExpectedCloseAngle,
     ExpectedPart,
+    ExpectedLiteral,
+    ExpectedModifier,
+    ExpectedRuleEnd,
+
+    //semantic
+    UnexpectedModeAction,
+    UnbalancedModePop,
+    InconsistentDedent,
+    UnknownProduction,
+    UnknownLexicalRule,
+    InvalidCharClass,
+    InvalidRange,
+    InvalidSeparatedList,
+    ArgumentCountMismatch,
+    RecursiveInstantiation,
+    MisplacedLabel,
+    UnknownCharacterProperty,
+    UnresolvedPath,
+    CyclicUse,
+    NameCollision,
+    UnknownOverride,
+    UnproductiveRule,
+    UnreachableRule,
+
+    //import, see import.rs
+    ExpectedColon,
+    ExpectedEquals,
+    ExpectedDefinition,
+    ExpectedOpenCurlyBrace,
+    ExpectedRepetition,
+    UnsupportedStackOperation,
+    UnsupportedTokenSet,
+    DroppedAction,
+    DroppedPredicate,
+    DroppedOptions,
+    DroppedImport,
+    DroppedTokenDeclaration,
+    DroppedAlternativeLabel,
+    DroppedLexerCommand,
+    DroppedConstraint,
+    DroppedEndOfInput,
+    HiddenChannel,
+    ModeSwitch,
+    NonGreedyAtEnd,
+    ApproximatedException,
+
+    //interpretation
+    UnexpectedInput,
+    UnexpectedEndOfInput,
+    NotAnEntryPoint,
+    CyclicAmbiguity,
 }
 
-impl Error for FrontendError {
-    fn description(&self) -> &str {
+impl FrontendError {
+    pub fn message(&self) -> &'static str {
         match *self {
             FrontendError::ExpectedArrowTip => "expected '>'",
             FrontendError::ExpectedCloseCurlyBrace => "expected '}'",
+            FrontendError::ExpectedCloseParen => "expected ')'",
+            FrontendError::ExpectedCloseAngle => "expected '>'",
             FrontendError::ExpectedMoreInput => "expected more input",
             FrontendError::ExpectedEscapeSequence => "expected escape sequence",
             FrontendError::ExpectedIdentifier => "expected identifer",
             FrontendError::UnrecognizedInput => "unrecognized input",
-            FrontendError::ExpectedColon => "expected ':'",
-            FrontendError::EOI => "end of input",
+            FrontendError::ExpectedCloseBracket => "expected ']'",
+            FrontendError::ExpectedProperty => "expected '\\p{NAME}' or '\\P{NAME}'",
+            FrontendError::ExpectedRangeDot => "expected '..'",
+            FrontendError::ExpectedCode => "expected '{' after '=>'",
+            FrontendError::UnterminatedCode => "unterminated code block",
+            FrontendError::UnterminatedLiteral => "unterminated literal",
+            FrontendError::ExpectedType => "expected a result type after ':'",
+            FrontendError::EOI => "unexpected end of input",
             FrontendError::Default => "default",
-            FrontendError::ExpectedProdStartOrUse => "expected production name or 'use'",
+            FrontendError::UnreadableFile => "unable to read the grammar file",
+            FrontendError::ExpectedProdStartOrUse => {
+                "expected production name, 'use', 'skip', 'layout', 'start', 'extends' or 'override'"
+            }
             FrontendError::ExpectedName => "expected Name",
             FrontendError::ExpectedArrow => "expected ->",
             FrontendError::ExpectedEndl => "expected endline",
             FrontendError::ExpectedUse => "expected 'use'",
+            FrontendError::ExpectedSkip => "expected 'skip'",
+            FrontendError::ExpectedLayout => "expected 'layout'",
+            FrontendError::ExpectedStart => "expected 'start'",
+            FrontendError::ExpectedExtends => "expected 'extends'",
+            FrontendError::ExpectedOverride => "expected 'override'",
+            FrontendError::ExpectedModifier => "expected one of: 'in', 'push', 'pop'",
+            FrontendError::ExpectedRuleEnd => "expected the end of the rule",
+            FrontendError::UnexpectedModeAction => "'push' and 'pop' are only allowed on lexical rules",
+            FrontendError::UnbalancedModePop => "'pop' without a matching 'push'",
+            FrontendError::UnknownProduction => "reference to an undefined production",
+            FrontendError::UnknownLexicalRule => "reference to an undefined lexical rule",
+            FrontendError::InvalidCharClass => "invalid character class",
+            FrontendError::InvalidRange => {
+                "a range needs two single characters in ascending order"
+            }
+            FrontendError::InvalidSeparatedList => {
+                "'%' and '%%' need a repeated part, use '*' or '+' instead of '?'"
+            }
+            FrontendError::ArgumentCountMismatch => {
+                "wrong number of arguments for a parameterized production"
+            }
+            FrontendError::RecursiveInstantiation => {
+                "parameterized production instantiates itself with new arguments"
+            }
+            FrontendError::MisplacedLabel => "labels are not allowed on labels or separators",
+            FrontendError::UnknownCharacterProperty => "unknown character property",
+            FrontendError::UnresolvedPath => "no grammar file or rule found for the used path",
+            FrontendError::CyclicUse => "grammar files use each other",
+            FrontendError::NameCollision => {
+                "rule is already defined differently in a used or extended grammar"
+            }
+            FrontendError::UnknownOverride => "override of a rule the grammar does not inherit",
+            FrontendError::UnproductiveRule => "production derives no finite input",
+            FrontendError::UnreachableRule => {
+                "rule is not used from an entry point or skip declaration"
+            }
+            FrontendError::UnexpectedInput => "input does not match the grammar",
+            FrontendError::UnexpectedEndOfInput => "unexpected end of input",
+            FrontendError::NotAnEntryPoint => "production is not declared with 'start'",
+            FrontendError::CyclicAmbiguity => "input matches, but the tree of it runs through cyclic rules",
+            FrontendError::InconsistentDedent => {
+                "dedent does not match any outer indentation level"
+            }
             FrontendError::ExpectedPart => {
-                "expected one of: LITERAL, LEXICAL RULE NAME, NAME, EPSILON"
+                "expected one of: LITERAL, CLASS, PROPERTY, LEXICAL RULE NAME, NAME, EPSILON, '(', '&', '!'"
+            }
+            FrontendError::ExpectedLiteral => "expected literal",
+            FrontendError::ExpectedColon => "expected ':'",
+            FrontendError::ExpectedEquals => "expected '='",
+            FrontendError::ExpectedDefinition => "expected '::='",
+            FrontendError::ExpectedOpenCurlyBrace => "expected '{'",
+            FrontendError::ExpectedRepetition => "expected a repetition like {2}, {2,}, {,4} or {2,4}",
+            FrontendError::UnsupportedStackOperation => {
+                "PUSH, POP, PEEK and DROP match text seen before, Gideon has no equivalent"
+            }
+            FrontendError::UnsupportedTokenSet => {
+                "'.' and '~' match any token in parser rules, Gideon has no equivalent"
+            }
+            FrontendError::DroppedAction => "actions, arguments and return values are left out",
+            FrontendError::DroppedPredicate => {
+                "semantic predicates are left out, the rule may match more than before"
+            }
+            FrontendError::DroppedOptions => "options and element options are left out",
+            FrontendError::DroppedImport => "imported grammars are left out, import them separately",
+            FrontendError::DroppedTokenDeclaration => "tokens declared without a lexer rule are left out",
+            FrontendError::DroppedAlternativeLabel => "alternative labels are left out",
+            FrontendError::DroppedLexerCommand => {
+                "lexer commands other than skip, pushMode and popMode are left out"
+            }
+            FrontendError::DroppedConstraint => "well-formedness and validity constraints are left out",
+            FrontendError::DroppedEndOfInput => {
+                "end of input inside a rule is left out, entry points match whole inputs"
+            }
+            FrontendError::HiddenChannel => "tokens sent to another channel are skipped instead",
+            FrontendError::ModeSwitch => {
+                "'mode' is imported as 'push', which keeps the previous mode on the stack"
+            }
+            FrontendError::NonGreedyAtEnd => {
+                "a non-greedy quantifier at the end of an alternative is imported as greedy"
+            }
+            FrontendError::ApproximatedException => {
+                "'A - B' is imported as '!B A', which differs unless both match single characters"
             }
         }
     }
 }
 
+impl Error for FrontendError {}
+
 impl Display for FrontendError {
     fn fmt(&self, f: &mut Formatter) -> FormatResult {
-        write!(f, "Lexical Error: {}", self.description())
+        write!(f, "Lexical Error: {}", self.message())
     }
 }
\ No newline at end of file
@@ -0,0 +1,1124 @@
+//Owned representation of a grammar, lowered from the concrete syntax tree.
+//Later stages (scanning, analysis, generation) work on this instead of the CST.
+use super::syntax_tree as cst;
+use super::frontend_error::*;
+use super::parser::SyntaxResult;
+use super::lexer::LexicalResult;
+use super::token::Token;
+use super::char_class::CharClass;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fmt::Result as FormatResult;
+
+pub type LoweringResult<T> = Result<T, FrontendError>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    Literal(String),
+    Lexical(String),
+    Name(String),
+    Class(CharClass),
+    Epsilon,
+    //parenthesized alternatives, removed by desugar
+    Group(Vec<Alternative>),
+    //lookahead, matches the empty string when the symbol does or does not
+    //match at the current position
+    And(Box<Symbol>),
+    Not(Box<Symbol>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quantifier {
+    One,
+    Optional,
+    Star,
+    Plus,
+}
+
+//`Item* % Sep` or `Item+ %% Sep`, the latter allowing a trailing separator
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Separator {
+    pub symbol: Symbol,
+    pub trailing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Item {
+    pub symbol: Symbol,
+    pub quantifier: Quantifier,
+    //separated list, the quantifier is then '*' or '+'
+    pub separator: Option<Separator>,
+    //name the action of the alternative uses for the value of the item
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Alternative {
+    pub items: Vec<Item>,
+    //Rust code building the value of the alternative, without the braces
+    pub action: Option<String>,
+}
+
+//Name of the mode the scanner starts in, literals always belong to it
+pub const DEFAULT_MODE: &str = "default";
+
+//Virtual lexical rules synthesized by the scanner in layout mode
+pub const INDENT: &str = "INDENT";
+pub const DEDENT: &str = "DEDENT";
+pub const NEWLINE: &str = "NEWLINE";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModeAction {
+    Push(String),
+    Pop,
+}
+
+//Role of a rule synthesized by desugar. A List helper and the Tail helpers
+//it uses derive the elements of one repetition, which is what a generator
+//building syntax trees would collect into a Vec. No backend builds trees
+//yet, they print helpers as ordinary rules; the interpreter splices the
+//elements of a repetition into the node that contains it instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Helper {
+    Group,
+    Optional,
+    List,
+    Tail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Production {
+    pub name: String,
+    pub nullable: bool,
+    //Rust type of the values built by the actions
+    pub result_type: Option<String>,
+    pub alternatives: Vec<Alternative>,
+    //lexical rules only: the modes the rule is active in, empty means the
+    //default mode, and the mode change performed after it matches
+    pub modes: Vec<String>,
+    pub action: Option<ModeAction>,
+    //None for rules written in the grammar
+    pub helper: Option<Helper>,
+    pub line: usize,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Skip {
+    pub modes: Vec<String>,
+    pub alternatives: Vec<Alternative>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsePath {
+    pub segments: Vec<String>,
+    pub line: usize,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideKind {
+    //`override Name -> ...`
+    Replace,
+    //`Name +-> ...`
+    Append,
+}
+
+//Change to an inherited rule, applied by the loader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Override {
+    pub kind: OverrideKind,
+    pub lexical: bool,
+    pub rule: Production,
+}
+
+//Production declared with `start`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPoint {
+    pub name: String,
+    pub line: usize,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Grammar {
+    pub uses: Vec<UsePath>,
+    //grammars whose rules this one inherits, see loader.rs
+    pub extends: Vec<UsePath>,
+    pub overrides: Vec<Override>,
+    //empty when the grammar declares none, see Grammar::entry_points
+    pub starts: Vec<EntryPoint>,
+    pub productions: Vec<Production>,
+    pub lexical_rules: Vec<Production>,
+    //text matched by a skip declaration is trivia
+    pub skip: Vec<Skip>,
+    //indentation sensitive scanning, see scanner.rs
+    pub layout: bool,
+}
+
+impl Grammar {
+    pub fn lower(tree: &cst::Grammar) -> LoweringResult<Grammar> {
+        Grammar::lower_with(tree, &[], "")
+    }
+
+    //Lowers `tree` with parameterized productions declared elsewhere, see
+    //loader.rs. Instances are named `prefix`, the template and a number.
+    pub fn lower_with<'t, 'a: 't>(
+        tree: &'t cst::Grammar<'a>,
+        templates: &[&'t cst::Prod<'a>],
+        prefix: &str,
+    ) -> LoweringResult<Grammar> {
+        let mut lowering = Lowering::new(tree, templates, prefix);
+        let mut current = Some(tree);
+        while let Some(node) = current {
+            let rest = match *node {
+                cst::Grammar::ProdDecl(ref prod, ref rest) => {
+                    lowering.lower_production(prod.as_ref().map_err(|e| *e)?)?;
+                    rest
+                }
+                cst::Grammar::PathDecl(ref path, ref rest) => {
+                    let path = lower_path(path.as_ref().map_err(|e| *e)?)?;
+                    lowering.grammar.uses.push(path);
+                    rest
+                }
+                cst::Grammar::ExtendsDecl(ref extends, ref rest) => {
+                    let extends = lower_extends(extends.as_ref().map_err(|e| *e)?)?;
+                    lowering.grammar.extends.push(extends);
+                    rest
+                }
+                cst::Grammar::OverrideDecl(ref decl, ref rest) => {
+                    let decl = decl.as_ref().map_err(|e| *e)?;
+                    decl.koverride()?;
+                    lowering.lower_override(decl.prod().as_ref().map_err(|e| *e)?)?;
+                    rest
+                }
+                cst::Grammar::SkipDecl(ref skip, ref rest) => {
+                    let skip = skip.as_ref().map_err(|e| *e)?;
+                    skip.kskip()?;
+                    let (modes, action) = lower_modifiers(skip.modifiers())?;
+                    if action.is_some() {
+                        return Err(FrontendError::UnexpectedModeAction);
+                    }
+                    let alternatives = lowering.lower_union(skip.union(), &Bindings::new())?;
+                    skip.endl()?;
+                    lowering.grammar.skip.push(Skip { modes, alternatives });
+                    lowering.flush();
+                    rest
+                }
+                cst::Grammar::LayoutDecl(ref layout, ref rest) => {
+                    let layout = layout.as_ref().map_err(|e| *e)?;
+                    layout.klayout()?;
+                    layout.endl()?;
+                    lowering.grammar.layout = true;
+                    rest
+                }
+                cst::Grammar::StartDecl(ref start, ref rest) => {
+                    for entry in lower_start(start.as_ref().map_err(|e| *e)?)? {
+                        if lowering.grammar.starts.iter().all(|s| s.name != entry.name) {
+                            lowering.grammar.starts.push(entry);
+                        }
+                    }
+                    rest
+                }
+            };
+            current = match **rest {
+                Some(ref rest) => Some(rest.as_ref().map_err(|e| *e)?),
+                None => None,
+            };
+        }
+        //start declarations may name rules of other files, they are checked
+        //once those are loaded, see loader.rs
+        Ok(lowering.grammar)
+    }
+
+    //The declared entry points, every production when there are none
+    pub fn entry_points(&self) -> Vec<&str> {
+        if self.starts.is_empty() {
+            self.productions.iter().map(|p| p.name.as_str()).collect()
+        } else {
+            self.starts.iter().map(|start| start.name.as_str()).collect()
+        }
+    }
+
+    pub fn is_entry_point(&self, name: &str) -> bool {
+        self.entry_points().contains(&name)
+    }
+
+    //Productions and lexical rules used from the entry points, as
+    //Symbol::Name and Symbol::Lexical. Skip declarations apply everywhere,
+    //so the rules they use are reachable too.
+    pub fn reachable(&self) -> HashSet<Symbol> {
+        let mut roots: Vec<&Symbol> = Vec::new();
+        for skip in &self.skip {
+            push_symbols(&mut roots, &skip.alternatives);
+        }
+        let entries: Vec<Symbol> = self.entry_points()
+            .into_iter()
+            .map(|name| Symbol::Name(name.to_string()))
+            .collect();
+        roots.extend(entries.iter());
+        self.reachable_from(roots)
+    }
+
+    //Productions and lexical rules `roots` use, including the roots
+    pub fn reachable_from(&self, roots: Vec<&Symbol>) -> HashSet<Symbol> {
+        let mut reachable = HashSet::new();
+        let mut pending = roots;
+        while let Some(symbol) = pending.pop() {
+            let rule = match *symbol {
+                Symbol::Name(ref name) => self.production(name),
+                Symbol::Lexical(ref name) => self.lexical_rule(name),
+                Symbol::Group(ref alternatives) => {
+                    push_symbols(&mut pending, alternatives);
+                    continue;
+                }
+                Symbol::And(ref symbol) | Symbol::Not(ref symbol) => {
+                    pending.push(symbol);
+                    continue;
+                }
+                _ => continue,
+            };
+            if reachable.insert(symbol.clone()) {
+                if let Some(rule) = rule {
+                    push_symbols(&mut pending, &rule.alternatives);
+                }
+            }
+        }
+        reachable
+    }
+
+    pub fn production(&self, name: &str) -> Option<&Production> {
+        self.productions.iter().find(|p| p.name == name)
+    }
+
+    pub fn lexical_rule(&self, name: &str) -> Option<&Production> {
+        self.lexical_rules.iter().find(|p| p.name == name)
+    }
+
+    //Every mode mentioned by the grammar, the default mode first
+    pub fn modes(&self) -> Vec<String> {
+        let mut modes = vec![DEFAULT_MODE.to_string()];
+        {
+            let mut add = |mode: &String| if !modes.contains(mode) {
+                modes.push(mode.clone());
+            };
+            for rule in &self.lexical_rules {
+                rule.modes.iter().for_each(&mut add);
+                if let Some(ModeAction::Push(ref mode)) = rule.action {
+                    add(mode);
+                }
+            }
+            for skip in &self.skip {
+                skip.modes.iter().for_each(&mut add);
+            }
+        }
+        modes
+    }
+}
+
+impl Quantifier {
+    pub fn nullable(self) -> bool {
+        self == Quantifier::Optional || self == Quantifier::Star
+    }
+
+    pub fn repeated(self) -> bool {
+        self == Quantifier::Star || self == Quantifier::Plus
+    }
+}
+
+impl Item {
+    pub fn new(symbol: Symbol) -> Item {
+        Item {
+            symbol,
+            quantifier: Quantifier::One,
+            separator: None,
+            label: None,
+        }
+    }
+
+    pub fn nullable(&self) -> bool {
+        self.quantifier.nullable()
+    }
+}
+
+impl Production {
+    pub fn in_mode(&self, mode: &str) -> bool {
+        if self.modes.is_empty() {
+            mode == DEFAULT_MODE
+        } else {
+            self.modes.iter().any(|m| m == mode)
+        }
+    }
+}
+
+impl Skip {
+    pub fn in_mode(&self, mode: &str) -> bool {
+        if self.modes.is_empty() {
+            mode == DEFAULT_MODE
+        } else {
+            self.modes.iter().any(|m| m == mode)
+        }
+    }
+}
+
+//Arguments bound to the parameters of the template being expanded
+type Bindings = HashMap<String, Symbol>;
+
+//Parameterized production, expanded at every use
+struct Template<'t, 'a: 't> {
+    params: Vec<String>,
+    prod: &'t cst::Prod<'a>,
+    line: usize,
+    offset: usize,
+}
+
+struct Lowering<'t, 'a: 't> {
+    grammar: Grammar,
+    templates: HashMap<String, Template<'t, 'a>>,
+    //instance names by template and arguments
+    instances: HashMap<(String, Vec<Vec<Alternative>>), String>,
+    //templates being expanded, instantiating one of them again with new
+    //arguments would never end
+    expanding: Vec<String>,
+    //instances created while lowering the current declaration
+    pending: Vec<Production>,
+    names: HashSet<String>,
+    prefix: String,
+}
+
+impl<'t, 'a: 't> Lowering<'t, 'a> {
+    //Collects the templates and the declared names
+    fn new(tree: &'t cst::Grammar<'a>, templates: &[&'t cst::Prod<'a>], prefix: &str) -> Self {
+        let mut lowering = Lowering {
+            grammar: Grammar::default(),
+            templates: HashMap::new(),
+            instances: HashMap::new(),
+            expanding: Vec::new(),
+            pending: Vec::new(),
+            names: HashSet::new(),
+            prefix: prefix.to_string(),
+        };
+        for template in templates {
+            lowering.declare(template);
+        }
+        let mut current = Some(tree);
+        while let Some(node) = current {
+            let rest = match *node {
+                cst::Grammar::ProdDecl(ref prod, ref rest) => {
+                    if let Ok(ref prod) = *prod {
+                        lowering.declare(prod);
+                    }
+                    rest
+                }
+                cst::Grammar::PathDecl(_, ref rest)
+                | cst::Grammar::SkipDecl(_, ref rest)
+                | cst::Grammar::LayoutDecl(_, ref rest)
+                | cst::Grammar::StartDecl(_, ref rest)
+                | cst::Grammar::ExtendsDecl(_, ref rest)
+                | cst::Grammar::OverrideDecl(_, ref rest) => rest,
+            };
+            current = match **rest {
+                Some(Ok(ref rest)) => Some(rest),
+                _ => None,
+            };
+        }
+        lowering
+    }
+
+    fn declare(&mut self, prod: &'t cst::Prod<'a>) {
+        let (name, params) = match *prod.name() {
+            Ok(cst::ProdName::Name(Ok(name), ref params)) => (name.data(), params),
+            Ok(cst::ProdName::LexicalRuleName(_, Ok(name), _)) => {
+                self.names.insert(name.data().text());
+                return;
+            }
+            _ => return,
+        };
+        self.names.insert(name.text());
+        let params = match **params {
+            Some(Ok(ref params)) => params,
+            _ => return,
+        };
+        let mut names = Vec::new();
+        if let Ok(param) = params.name() {
+            names.push(param.data().text());
+        }
+        let mut list = params.list();
+        while let Some(Ok(item)) = list {
+            if let Ok(param) = item.name() {
+                names.push(param.data().text());
+            }
+            list = item.list();
+        }
+        let template = Template {
+            params: names,
+            prod,
+            line: name.line(),
+            offset: name.offset(),
+        };
+        self.templates.insert(name.text(), template);
+    }
+
+    fn lower_production(&mut self, prod: &cst::Prod) -> LoweringResult<()> {
+        match self.lower_rule(prod)? {
+            Some((rule, lexical)) if appends(prod) => {
+                self.grammar.overrides.push(Override {
+                    kind: OverrideKind::Append,
+                    lexical,
+                    rule,
+                });
+            }
+            Some((rule, true)) => self.grammar.lexical_rules.push(rule),
+            Some((rule, false)) => self.grammar.productions.push(rule),
+            None if appends(prod) => return Err(FrontendError::UnknownOverride),
+            None => {}
+        }
+        self.flush();
+        Ok(())
+    }
+
+    fn lower_override(&mut self, prod: &cst::Prod) -> LoweringResult<()> {
+        let (rule, lexical) = match self.lower_rule(prod)? {
+            Some(lowered) => lowered,
+            //templates are not inherited
+            None => return Err(FrontendError::UnknownOverride),
+        };
+        let kind = if appends(prod) {
+            OverrideKind::Append
+        } else {
+            OverrideKind::Replace
+        };
+        self.grammar.overrides.push(Override { kind, lexical, rule });
+        self.flush();
+        Ok(())
+    }
+
+    //The rule and whether it is lexical, None for templates
+    fn lower_rule(&mut self, prod: &cst::Prod) -> LoweringResult<Option<(Production, bool)>> {
+        let (name, lexical) = match *prod.name().as_ref().map_err(|e| *e)? {
+            cst::ProdName::Name(name, ref params) => {
+                if let Some(ref params) = **params {
+                    //templates are expanded where they are used
+                    let params = params.as_ref().map_err(|e| *e)?;
+                    params.oangle()?;
+                    params.cangle()?;
+                    prod.arrow()?;
+                    return prod.endl().map(|_| None);
+                }
+                (name?.data(), false)
+            }
+            cst::ProdName::LexicalRuleName(obrace, name, cbrace) => {
+                obrace?;
+                cbrace?;
+                (name?.data(), true)
+            }
+        };
+        let (modes, action) = lower_modifiers(prod.modifiers())?;
+        let nullable = lower_nullable(prod.nullable())?;
+        let result_type = lower_result_type(prod.rtype())?;
+        prod.arrow()?;
+        let alternatives = self.lower_union(prod.union(), &Bindings::new())?;
+        prod.endl()?;
+        let production = Production {
+            name: name.text(),
+            nullable,
+            result_type,
+            alternatives,
+            modes,
+            action,
+            helper: None,
+            line: name.line(),
+            offset: name.offset(),
+        };
+        Ok(Some((production, lexical)))
+    }
+
+    //Instances follow the declaration that first used them
+    fn flush(&mut self) {
+        self.grammar.productions.append(&mut self.pending);
+    }
+
+    fn instantiate(&mut self, name: String, args: Vec<Vec<Alternative>>) -> LoweringResult<String> {
+        let (params, prod, line, offset) = match self.templates.get(&name) {
+            Some(template) => (
+                template.params.clone(),
+                template.prod,
+                template.line,
+                template.offset,
+            ),
+            None => return Err(FrontendError::UnknownProduction),
+        };
+        if params.len() != args.len() {
+            return Err(FrontendError::ArgumentCountMismatch);
+        }
+        let key = (name, args);
+        if let Some(instance) = self.instances.get(&key) {
+            return Ok(instance.clone());
+        }
+        let (name, args) = key;
+        if self.expanding.contains(&name) {
+            return Err(FrontendError::RecursiveInstantiation);
+        }
+        let mut index = 1;
+        let instance = loop {
+            let instance = format!("{}{}{}", self.prefix, name, index);
+            if self.names.insert(instance.clone()) {
+                break instance;
+            }
+            index += 1;
+        };
+        let bindings: Bindings = params
+            .into_iter()
+            .zip(args.iter().map(|arg| argument(arg)))
+            .collect();
+        self.instances.insert((name.clone(), args), instance.clone());
+        self.expanding.push(name);
+        let nullable = lower_nullable(prod.nullable())?;
+        let result_type = lower_result_type(prod.rtype())?;
+        let alternatives = self.lower_union(prod.union(), &bindings)?;
+        self.expanding.pop();
+        self.pending.push(Production {
+            name: instance.clone(),
+            nullable,
+            result_type,
+            alternatives,
+            modes: Vec::new(),
+            action: None,
+            helper: None,
+            line,
+            offset,
+        });
+        Ok(instance)
+    }
+
+    fn lower_union(
+        &mut self,
+        union: &SyntaxResult<cst::Union>,
+        bindings: &Bindings,
+    ) -> LoweringResult<Vec<Alternative>> {
+        let mut alternatives = Vec::new();
+        let mut current = Some(union.as_ref().map_err(|e| *e)?);
+        while let Some(union) = current {
+            let mut alternative = self.lower_body(union.body(), bindings)?;
+            if let Some(ref action) = *union.action().as_ref().map_err(|e| *e)? {
+                let action = action.as_ref().map_err(|e| *e)?;
+                action.arrow()?;
+                alternative.action = Some(action.code()?.data().text());
+            }
+            alternatives.push(alternative);
+            current = match union.obody() {
+                Some(obody) => {
+                    let obody = obody.as_ref().map_err(|e| *e)?;
+                    obody.or()?;
+                    Some(obody.union().as_ref().map_err(|e| *e)?)
+                }
+                None => None,
+            };
+        }
+        Ok(alternatives)
+    }
+
+    fn lower_body(
+        &mut self,
+        body: &SyntaxResult<cst::Body>,
+        bindings: &Bindings,
+    ) -> LoweringResult<Alternative> {
+        let mut alternative = Alternative::default();
+        let mut current = Some(body.as_ref().map_err(|e| *e)?);
+        while let Some(body) = current {
+            let (label, part) = match *body.part().as_ref().map_err(|e| *e)? {
+                cst::Part::Labeled(name, colon, ref part) => {
+                    colon?;
+                    (Some(name?.data().text()), (**part).as_ref().map_err(|e| *e)?)
+                }
+                ref part => (None, part),
+            };
+            let symbol = self.lower_part(part, bindings)?;
+            let mut quantifier = lower_quantifier(body.quantifier())?;
+            let separator = match *body.separator().as_ref().map_err(|e| *e)? {
+                Some(ref separator) => {
+                    let separator = separator.as_ref().map_err(|e| *e)?;
+                    let trailing = matches!(separator.op()?, Token::DoublePercent(_));
+                    let part = separator.part().as_ref().map_err(|e| *e)?;
+                    let symbol = self.lower_part(part, bindings)?;
+                    match quantifier {
+                        Quantifier::Optional => return Err(FrontendError::InvalidSeparatedList),
+                        Quantifier::One => quantifier = Quantifier::Plus,
+                        _ => {}
+                    }
+                    Some(Separator { symbol, trailing })
+                }
+                None => None,
+            };
+            alternative.items.push(Item {
+                symbol,
+                quantifier,
+                separator,
+                label,
+            });
+            current = match body.rbody() {
+                Some(rbody) => Some(rbody.as_ref().map_err(|e| *e)?),
+                None => None,
+            };
+        }
+        Ok(alternative)
+    }
+
+    fn lower_part(&mut self, part: &cst::Part, bindings: &Bindings) -> LoweringResult<Symbol> {
+        match *part {
+            cst::Part::Literal(literal) => Ok(Symbol::Literal(unescape(literal?.data().value()))),
+            cst::Part::Range(from, dots, to) => {
+                dots?;
+                let from: Vec<char> = unescape(from?.data().value()).chars().collect();
+                let to: Vec<char> = unescape(to?.data().value()).chars().collect();
+                if from.len() != 1 || to.len() != 1 {
+                    return Err(FrontendError::InvalidRange);
+                }
+                Ok(Symbol::Class(CharClass::range(from[0], to[0])?))
+            }
+            cst::Part::Class(class) => Ok(Symbol::Class(CharClass::parse(class?.data().value())?)),
+            cst::Part::Property(property) => {
+                Ok(Symbol::Class(CharClass::property(&property?.data().value()[1..])?))
+            }
+            cst::Part::LexicalRuleName(obrace, name, cbrace) => {
+                obrace?;
+                cbrace?;
+                Ok(Symbol::Lexical(name?.data().text()))
+            }
+            cst::Part::Name(name, ref args) => {
+                let name = name?.data().text();
+                let args = match **args {
+                    Some(ref args) => args.as_ref().map_err(|e| *e)?,
+                    None => {
+                        return match bindings.get(&name) {
+                            Some(symbol) => Ok(symbol.clone()),
+                            None if self.templates.contains_key(&name) => {
+                                Err(FrontendError::ArgumentCountMismatch)
+                            }
+                            None => Ok(Symbol::Name(name)),
+                        };
+                    }
+                };
+                args.oangle()?;
+                let mut lowered = vec![self.lower_union(args.union(), bindings)?];
+                let mut list = args.list();
+                while let Some(item) = list {
+                    let item = item.as_ref().map_err(|e| *e)?;
+                    item.comma()?;
+                    lowered.push(self.lower_union(item.union(), bindings)?);
+                    list = item.list();
+                }
+                args.cangle()?;
+                Ok(Symbol::Name(self.instantiate(name, lowered)?))
+            }
+            cst::Part::Epsilon(epsilon) => {
+                epsilon?;
+                Ok(Symbol::Epsilon)
+            }
+            cst::Part::Group(oparen, ref union, cparen) => {
+                oparen?;
+                let alternatives = self.lower_union(union, bindings)?;
+                cparen?;
+                Ok(Symbol::Group(alternatives))
+            }
+            cst::Part::And(amp, ref part) => {
+                amp?;
+                let part = (**part).as_ref().map_err(|e| *e)?;
+                Ok(Symbol::And(Box::new(self.lower_part(part, bindings)?)))
+            }
+            cst::Part::Not(bang, ref part) => {
+                bang?;
+                let part = (**part).as_ref().map_err(|e| *e)?;
+                Ok(Symbol::Not(Box::new(self.lower_part(part, bindings)?)))
+            }
+            //labels are taken by lower_body
+            cst::Part::Labeled(..) => Err(FrontendError::MisplacedLabel),
+        }
+    }
+}
+
+//Symbol a parameter stands for, a group unless the argument is a single part
+fn argument(alternatives: &[Alternative]) -> Symbol {
+    if let [ref alternative] = *alternatives {
+        if let [ref item] = *alternative.items {
+            if item.quantifier == Quantifier::One && item.separator.is_none() && item.label.is_none() {
+                return item.symbol.clone();
+            }
+        }
+    }
+    Symbol::Group(alternatives.to_vec())
+}
+
+fn lower_modifiers(
+    modifiers: Option<&SyntaxResult<cst::Modifiers>>,
+) -> LoweringResult<(Vec<String>, Option<ModeAction>)> {
+    let mut modes = Vec::new();
+    let mut action = None;
+    let mut current = modifiers;
+    while let Some(modifiers) = current {
+        let modifiers = modifiers.as_ref().map_err(|e| *e)?;
+        match *modifiers.modifier().as_ref().map_err(|e| *e)? {
+            cst::Modifier::In(kin, name, ref list) => {
+                kin?;
+                modes.push(name?.data().text());
+                let mut list = (**list).as_ref();
+                while let Some(item) = list {
+                    let item = item.as_ref().map_err(|e| *e)?;
+                    item.or()?;
+                    modes.push(item.name()?.data().text());
+                    list = item.list();
+                }
+            }
+            cst::Modifier::Push(kpush, name) => {
+                kpush?;
+                action = Some(ModeAction::Push(name?.data().text()));
+            }
+            cst::Modifier::Pop(kpop) => {
+                kpop?;
+                action = Some(ModeAction::Pop);
+            }
+        }
+        current = modifiers.rest();
+    }
+    Ok((modes, action))
+}
+
+fn lower_nullable(nullable: &SyntaxResult<cst::ONullable>) -> LoweringResult<bool> {
+    match *nullable.as_ref().map_err(|e| *e)? {
+        Some(ref nullable) => {
+            nullable.as_ref().map_err(|e| *e)?.qmark()?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn lower_result_type(rtype: &SyntaxResult<cst::OResultType>) -> LoweringResult<Option<String>> {
+    match *rtype.as_ref().map_err(|e| *e)? {
+        Some(ref rtype) => {
+            let rtype = rtype.as_ref().map_err(|e| *e)?;
+            rtype.colon()?;
+            Ok(Some(rtype.rtype()?.data().text()))
+        }
+        None => Ok(None),
+    }
+}
+
+fn lower_quantifier(quantifier: &SyntaxResult<cst::OQuantifier>) -> LoweringResult<Quantifier> {
+    match *quantifier.as_ref().map_err(|e| *e)? {
+        Some(ref quantifier) => match quantifier.as_ref().map_err(|e| *e)?.op()? {
+            Token::Star(_) => Ok(Quantifier::Star),
+            Token::Plus(_) => Ok(Quantifier::Plus),
+            _ => Ok(Quantifier::Optional),
+        },
+        None => Ok(Quantifier::One),
+    }
+}
+
+fn lower_start(start: &cst::Start) -> LoweringResult<Vec<EntryPoint>> {
+    start.kstart()?;
+    let entry = |name: Token| {
+        let name = name.data();
+        EntryPoint {
+            name: name.text(),
+            line: name.line(),
+            offset: name.offset(),
+        }
+    };
+    let mut starts = vec![entry(start.name()?)];
+    let mut current = start.list();
+    while let Some(list) = current {
+        let list = list.as_ref().map_err(|e| *e)?;
+        list.comma()?;
+        starts.push(entry(list.name()?));
+        current = list.list();
+    }
+    start.endl()?;
+    Ok(starts)
+}
+
+//Pushes the symbols of `alternatives` and their separators on a stack, so
+//they pop in order
+pub fn push_symbols<'g>(stack: &mut Vec<&'g Symbol>, alternatives: &'g [Alternative]) {
+    for alternative in alternatives.iter().rev() {
+        for item in alternative.items.iter().rev() {
+            if let Some(ref separator) = item.separator {
+                stack.push(&separator.symbol);
+            }
+            stack.push(&item.symbol);
+        }
+    }
+}
+
+//The use and extends statements of `tree`
+pub fn lower_paths(tree: &cst::Grammar) -> LoweringResult<(Vec<UsePath>, Vec<UsePath>)> {
+    let mut uses = Vec::new();
+    let mut extends = Vec::new();
+    let mut current = Some(tree);
+    while let Some(node) = current {
+        let rest = match *node {
+            cst::Grammar::PathDecl(ref path, ref rest) => {
+                uses.push(lower_path(path.as_ref().map_err(|e| *e)?)?);
+                rest
+            }
+            cst::Grammar::ExtendsDecl(ref path, ref rest) => {
+                extends.push(lower_extends(path.as_ref().map_err(|e| *e)?)?);
+                rest
+            }
+            cst::Grammar::ProdDecl(_, ref rest)
+            | cst::Grammar::SkipDecl(_, ref rest)
+            | cst::Grammar::LayoutDecl(_, ref rest)
+            | cst::Grammar::StartDecl(_, ref rest)
+            | cst::Grammar::OverrideDecl(_, ref rest) => rest,
+        };
+        current = match **rest {
+            Some(ref rest) => Some(rest.as_ref().map_err(|e| *e)?),
+            None => None,
+        };
+    }
+    Ok((uses, extends))
+}
+
+//The parameterized productions declared in `tree`, by name
+pub fn templates<'t, 'a>(tree: &'t cst::Grammar<'a>) -> Vec<(String, &'t cst::Prod<'a>)> {
+    let mut templates = Vec::new();
+    let mut current = Some(tree);
+    while let Some(node) = current {
+        let rest = match *node {
+            cst::Grammar::ProdDecl(ref prod, ref rest) => {
+                if let Ok(ref prod) = *prod {
+                    if let Ok(cst::ProdName::Name(Ok(name), ref params)) = *prod.name() {
+                        if params.is_some() {
+                            templates.push((name.data().text(), prod));
+                        }
+                    }
+                }
+                rest
+            }
+            cst::Grammar::PathDecl(_, ref rest)
+            | cst::Grammar::SkipDecl(_, ref rest)
+            | cst::Grammar::LayoutDecl(_, ref rest)
+            | cst::Grammar::StartDecl(_, ref rest)
+            | cst::Grammar::ExtendsDecl(_, ref rest)
+            | cst::Grammar::OverrideDecl(_, ref rest) => rest,
+        };
+        current = match **rest {
+            Some(Ok(ref rest)) => Some(rest),
+            _ => None,
+        };
+    }
+    templates
+}
+
+fn lower_path(path: &cst::Path) -> LoweringResult<UsePath> {
+    path.kuse()?;
+    let out = lower_segments(path.name(), path.list())?;
+    path.endl()?;
+    Ok(out)
+}
+
+fn lower_extends(extends: &cst::Extends) -> LoweringResult<UsePath> {
+    extends.kextends()?;
+    let out = lower_segments(extends.name(), extends.list())?;
+    extends.endl()?;
+    Ok(out)
+}
+
+fn lower_segments(
+    name: LexicalResult,
+    list: Option<&SyntaxResult<cst::PathItemList>>,
+) -> LoweringResult<UsePath> {
+    let name = name?.data();
+    let mut segments = vec![name.text()];
+    let mut current = list;
+    while let Some(list) = current {
+        let list = list.as_ref().map_err(|e| *e)?;
+        list.pathsep()?;
+        segments.push(list.name()?.data().text());
+        current = list.list();
+    }
+    Ok(UsePath {
+        segments,
+        line: name.line(),
+        offset: name.offset(),
+    })
+}
+
+//`Name +-> ...`
+fn appends(prod: &cst::Prod) -> bool {
+    matches!(prod.arrow(), Ok(Token::AppendArrow(_)))
+}
+
+//Resolves the escape sequences accepted by the lexer inside a literal
+pub fn unescape(value: &[char]) -> String {
+    let mut out = String::new();
+    let mut chars = value.iter();
+    while let Some(&c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(&'n') => out.push('\n'),
+                Some(&'t') => out.push('\t'),
+                Some(&'r') => out.push('\r'),
+                Some(&escaped) => out.push(escaped),
+                None => out.push(c),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub fn escape(value: &str) -> String {
+    let mut out = String::new();
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            Symbol::Literal(ref value) => write!(f, "\"{}\"", escape(value)),
+            Symbol::Lexical(ref name) => write!(f, "{{{}}}", name),
+            Symbol::Name(ref name) => write!(f, "{}", name),
+            Symbol::Class(ref class) => write!(f, "{}", class),
+            Symbol::Epsilon => write!(f, "None"),
+            Symbol::Group(ref alternatives) => {
+                write!(f, "(")?;
+                write_alternatives(f, alternatives)?;
+                write!(f, ")")
+            }
+            Symbol::And(ref symbol) => write!(f, "&{}", symbol),
+            Symbol::Not(ref symbol) => write!(f, "!{}", symbol),
+        }
+    }
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        if let Some(ref label) = self.label {
+            write!(f, "{}:", label)?;
+        }
+        write!(f, "{}", self.symbol)?;
+        match self.quantifier {
+            Quantifier::One => Ok(()),
+            Quantifier::Optional => write!(f, "?"),
+            Quantifier::Star => write!(f, "*"),
+            Quantifier::Plus => write!(f, "+"),
+        }?;
+        match self.separator {
+            Some(ref separator) if separator.trailing => write!(f, " %% {}", separator.symbol),
+            Some(ref separator) => write!(f, " % {}", separator.symbol),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Alternative {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", item)?;
+        }
+        if let Some(ref action) = self.action {
+            write!(f, " => {{{}}}", action)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_result_type(f: &mut Formatter, result_type: &Option<String>) -> FormatResult {
+    if let Some(ref result_type) = *result_type {
+        write!(f, ": {} ", result_type)?;
+    }
+    Ok(())
+}
+
+fn write_alternatives(f: &mut Formatter, alternatives: &[Alternative]) -> FormatResult {
+    for (i, alternative) in alternatives.iter().enumerate() {
+        if i > 0 {
+            write!(f, " | ")?;
+        }
+        write!(f, "{}", alternative)?;
+    }
+    Ok(())
+}
+
+fn write_modes(f: &mut Formatter, modes: &[String]) -> FormatResult {
+    if !modes.is_empty() {
+        write!(f, "in {} ", modes.join(" | "))?;
+    }
+    Ok(())
+}
+
+fn write_rule(f: &mut Formatter, rule: &Production, lexical: bool, arrow: &str) -> FormatResult {
+    if lexical {
+        write!(f, "{{{}}} ", rule.name)?;
+        write_modes(f, &rule.modes)?;
+        match rule.action {
+            Some(ModeAction::Push(ref mode)) => write!(f, "push {} ", mode)?,
+            Some(ModeAction::Pop) => write!(f, "pop ")?,
+            None => {}
+        }
+        write!(f, "{}", if rule.nullable { "? " } else { "" })?;
+    } else {
+        write!(f, "{}{} ", rule.name, if rule.nullable { "?" } else { "" })?;
+    }
+    write_result_type(f, &rule.result_type)?;
+    write!(f, "{} ", arrow)?;
+    write_alternatives(f, &rule.alternatives)?;
+    writeln!(f, " ;")
+}
+
+impl Display for Grammar {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        for path in &self.extends {
+            writeln!(f, "extends {};", path.segments.join("::"))?;
+        }
+        for path in &self.uses {
+            writeln!(f, "use {};", path.segments.join("::"))?;
+        }
+        if self.layout {
+            writeln!(f, "layout ;")?;
+        }
+        if !self.starts.is_empty() {
+            let names: Vec<&str> = self.starts.iter().map(|s| s.name.as_str()).collect();
+            writeln!(f, "start {} ;", names.join(", "))?;
+        }
+        for skip in &self.skip {
+            write!(f, "skip ")?;
+            write_modes(f, &skip.modes)?;
+            write_alternatives(f, &skip.alternatives)?;
+            writeln!(f, " ;")?;
+        }
+        for prod in &self.productions {
+            write_rule(f, prod, false, "->")?;
+        }
+        for rule in &self.lexical_rules {
+            write_rule(f, rule, true, "->")?;
+        }
+        for change in &self.overrides {
+            let arrow = match change.kind {
+                OverrideKind::Replace => {
+                    write!(f, "override ")?;
+                    "->"
+                }
+                OverrideKind::Append => "+->",
+            };
+            write_rule(f, &change.rule, change.lexical, arrow)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,340 @@
+//The plain BNF of Grammophone, in both directions.
+//
+//  Expr -> Expr PLUS Term | Term .
+//  Opt -> x | .
+//
+//A rule lists its alternatives, separated by '|', up to a '.'. Symbols are
+//separated by whitespace, the ones that are the left side of a rule are
+//nonterminals, all others are terminals. The first rule is the start.
+//
+//export desugars the grammar first, see desugar.rs, and shares helpers with
+//the same alternatives. A nullable production gets an empty alternative. A
+//lexical rule reference becomes the terminal named like the rule, a
+//literal the word it is or the name of its punctuation, see PUNCTUATION.
+//Lexical rules and skip declarations belong to the scanner and are left
+//out, as are lookaheads, which only narrow what a sequence matches. Classes
+//left in productions become terminals CLASS1, CLASS2 and so on.
+//
+//import reverses that: punctuation names become literals again, other all
+//uppercase terminals lexical rule references and the rest literals. A rule
+//with an empty alternative is nullable.
+use super::frontend_error::FrontendError;
+use super::grammar::*;
+use super::import::{ImportError, ImportResult};
+
+//Terminal names of punctuation literals, as the lexer of Gideon names them
+const PUNCTUATION: &[(&str, &str)] = &[
+    ("->", "ARROW"),
+    ("+->", "APPENDARROW"),
+    ("=>", "FATARROW"),
+    (";", "ENDL"),
+    ("|", "OR"),
+    ("{", "LBRACE"),
+    ("}", "RBRACE"),
+    ("(", "LPAREN"),
+    (")", "RPAREN"),
+    ("[", "LBRACKET"),
+    ("]", "RBRACKET"),
+    ("<", "LANGLE"),
+    (">", "RANGLE"),
+    (",", "COMMA"),
+    (":", "COLON"),
+    ("::", "PATHSEP"),
+    (".", "DOT"),
+    ("..", "RANGE"),
+    ("?", "QMARK"),
+    ("*", "STAR"),
+    ("+", "PLUS"),
+    ("-", "MINUS"),
+    ("/", "SLASH"),
+    ("%", "PERCENT"),
+    ("%%", "DPERCENT"),
+    ("&", "AMP"),
+    ("!", "BANG"),
+    ("=", "EQUALS"),
+    ("#", "HASH"),
+    ("@", "AT"),
+    ("$", "DOLLAR"),
+    ("^", "CARET"),
+    ("~", "TILDE"),
+    ("\"", "QUOTE"),
+    ("'", "APOSTROPHE"),
+    ("\\", "BACKSLASH"),
+];
+
+pub fn export(grammar: &Grammar) -> String {
+    let grammar = grammar.desugar().share_helpers();
+    let mut productions: Vec<&Production> = grammar.productions.iter().collect();
+    //the first rule is the start
+    if let Some(start) = grammar.entry_points().first() {
+        if let Some(i) = productions.iter().position(|prod| prod.name == *start) {
+            let prod = productions.remove(i);
+            productions.insert(0, prod);
+        }
+    }
+    let mut terminals = Terminals {
+        grammar: &grammar,
+        classes: Vec::new(),
+    };
+    let mut out = String::new();
+    for prod in productions {
+        let mut alternatives: Vec<String> = prod
+            .alternatives
+            .iter()
+            .map(|alternative| {
+                let symbols: Vec<String> = alternative
+                    .items
+                    .iter()
+                    .filter_map(|item| terminals.symbol(&item.symbol))
+                    .collect();
+                symbols.join(" ")
+            })
+            .collect();
+        if prod.nullable && !alternatives.iter().any(String::is_empty) {
+            alternatives.push(String::new());
+        }
+        let alternatives: Vec<String> = alternatives
+            .iter()
+            .map(|a| if a.is_empty() { a.clone() } else { format!("{} ", a) })
+            .collect();
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("{} -> {}.\n", prod.name, alternatives.join("| ")));
+    }
+    out
+}
+
+struct Terminals<'g> {
+    grammar: &'g Grammar,
+    //classes left in productions, CLASS1 is the first
+    classes: Vec<String>,
+}
+
+impl<'g> Terminals<'g> {
+    //None for symbols matching the empty string
+    fn symbol(&mut self, symbol: &Symbol) -> Option<String> {
+        match *symbol {
+            Symbol::Name(ref name) => Some(name.clone()),
+            Symbol::Lexical(ref name) => Some(self.unique(name.clone(), "_TOKEN")),
+            Symbol::Literal(ref value) => Some(self.literal(value)),
+            Symbol::Class(ref class) => {
+                let class = class.to_string();
+                let n = match self.classes.iter().position(|c| *c == class) {
+                    Some(i) => i + 1,
+                    None => {
+                        self.classes.push(class);
+                        self.classes.len()
+                    }
+                };
+                Some(format!("CLASS{}", n))
+            }
+            Symbol::Group(_) | Symbol::Epsilon | Symbol::And(_) | Symbol::Not(_) => None,
+        }
+    }
+
+    fn literal(&self, value: &str) -> String {
+        if is_word(value) {
+            self.unique(value.to_string(), "_LITERAL")
+        } else {
+            literal_name(value)
+        }
+    }
+
+    //A terminal named like a production is made distinct by `suffix`
+    fn unique(&self, name: String, suffix: &str) -> String {
+        if self.grammar.production(&name).is_some() {
+            name + suffix
+        } else {
+            name
+        }
+    }
+}
+
+//The terminal name of a literal, its punctuation name or the names of its
+//characters joined by '_', a word is its own name
+pub fn literal_name(value: &str) -> String {
+    if is_word(value) {
+        return value.to_string();
+    }
+    if let Some(&(_, name)) = PUNCTUATION.iter().find(|&&(text, _)| text == value) {
+        return name.to_string();
+    }
+    let names: Vec<String> = value
+        .chars()
+        .map(|c| {
+            let text = c.to_string();
+            match PUNCTUATION.iter().find(|&&(punctuation, _)| punctuation == text) {
+                Some(&(_, name)) => name.to_string(),
+                None if c.is_ascii_alphanumeric() => c.to_ascii_uppercase().to_string(),
+                None => format!("U{:04X}", c as u32),
+            }
+        })
+        .collect();
+    names.join("_")
+}
+
+pub fn is_word(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+pub fn import(source: &str) -> ImportResult<Grammar> {
+    let chars: Vec<char> = source.chars().collect();
+    let words = words(&chars);
+    let fail = |what, word: Option<&Word>| match word {
+        Some(word) => ImportError::new(what, word.line, word.offset),
+        None => ImportError::at_end(what, &chars),
+    };
+    //the rules as their names and alternatives of symbols
+    let mut rules: Vec<(String, Vec<Vec<String>>)> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let name = &words[i].text;
+        if name == "->" || name == "|" || name == "." {
+            return Err(fail(FrontendError::ExpectedName, words.get(i)));
+        }
+        if words.get(i + 1).map(|word| word.text.as_str()) != Some("->") {
+            return Err(fail(FrontendError::ExpectedArrow, words.get(i + 1)));
+        }
+        i += 2;
+        let mut alternatives = vec![Vec::new()];
+        loop {
+            match words.get(i).map(|word| word.text.as_str()) {
+                Some(".") => break,
+                Some("|") => alternatives.push(Vec::new()),
+                Some("->") | None => return Err(fail(FrontendError::ExpectedRuleEnd, words.get(i))),
+                Some(symbol) => {
+                    if let Some(alternative) = alternatives.last_mut() {
+                        alternative.push(symbol.to_string());
+                    }
+                }
+            }
+            i += 1;
+        }
+        i += 1;
+        match rules.iter_mut().find(|rule| rule.0 == *name) {
+            Some(rule) => rule.1.extend(alternatives),
+            None => rules.push((name.clone(), alternatives)),
+        }
+    }
+    let mut grammar = Grammar {
+        uses: Vec::new(),
+        extends: Vec::new(),
+        overrides: Vec::new(),
+        starts: Vec::new(),
+        productions: Vec::new(),
+        lexical_rules: Vec::new(),
+        skip: Vec::new(),
+        layout: false,
+    };
+    if let Some(start) = words.first() {
+        grammar.starts.push(EntryPoint {
+            name: start.text.clone(),
+            line: start.line,
+            offset: start.offset,
+        });
+    }
+    for (name, alternatives) in &rules {
+        let nullable = alternatives.iter().any(Vec::is_empty) && alternatives.len() > 1;
+        let alternatives = alternatives
+            .iter()
+            .filter(|alternative| !nullable || !alternative.is_empty())
+            .map(|alternative| {
+                let mut items: Vec<Item> = alternative
+                    .iter()
+                    .map(|symbol| {
+                        let defined = rules.iter().any(|rule| rule.0 == *symbol);
+                        Item::new(terminal(symbol, defined))
+                    })
+                    .collect();
+                if items.is_empty() {
+                    items.push(Item::new(Symbol::Epsilon));
+                }
+                Alternative { items, action: None }
+            })
+            .collect();
+        let (line, offset) = words
+            .iter()
+            .find(|word| word.text == *name)
+            .map(|word| (word.line, word.offset))
+            .unwrap_or((0, 0));
+        grammar.productions.push(Production {
+            name: name.clone(),
+            nullable,
+            result_type: None,
+            alternatives,
+            modes: Vec::new(),
+            action: None,
+            helper: None,
+            line,
+            offset,
+        });
+    }
+    Ok(grammar)
+}
+
+//The symbol a word of a rule stands for, `defined` when a rule has it as
+//its left side
+fn terminal(word: &str, defined: bool) -> Symbol {
+    if defined {
+        return Symbol::Name(word.to_string());
+    }
+    if let Some(&(text, _)) = PUNCTUATION.iter().find(|&&(_, name)| name == word) {
+        return Symbol::Literal(text.to_string());
+    }
+    let uppercase = word.starts_with(|c: char| c.is_ascii_uppercase())
+        && word.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    if uppercase {
+        Symbol::Lexical(word.to_string())
+    } else {
+        Symbol::Literal(word.to_string())
+    }
+}
+
+struct Word {
+    text: String,
+    line: usize,
+    offset: usize,
+}
+
+//The words of `input`, '->', '|' and '.' are words of their own
+fn words(input: &[char]) -> Vec<Word> {
+    let mut out = Vec::new();
+    let (mut line, mut offset) = (1, 1);
+    let mut i = 0;
+    while i < input.len() {
+        let c = input[i];
+        if c.is_whitespace() {
+            if c == '\n' {
+                line += 1;
+                offset = 1;
+            } else {
+                offset += 1;
+            }
+            i += 1;
+            continue;
+        }
+        let length = if c == '|' || c == '.' {
+            1
+        } else if c == '-' && input.get(i + 1) == Some(&'>') {
+            2
+        } else {
+            input[i..]
+                .iter()
+                .take_while(|c| !c.is_whitespace() && **c != '|' && **c != '.')
+                .count()
+        };
+        out.push(Word {
+            text: input[i..i + length].iter().collect(),
+            line,
+            offset,
+        });
+        i += length;
+        offset += length;
+    }
+    out
+}
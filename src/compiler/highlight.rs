@@ -0,0 +1,297 @@
+//Classes of the tokens of grammar source, for highlighting.
+//
+//Every token of the lossless tree gets a class, from its token::Token
+//variant and from where it is: a name declares a rule at the head of a
+//production, is a parameter inside the production declaring it, a label
+//before ':', a mode after 'in', 'push' or 'pop' and a reference to a rule
+//everywhere else. The braces around a lexical rule name share its class.
+//The head of an override or of '+->' refers to the rule it changes.
+//
+//ansi and html render the classes, the language server sends them as
+//semantic tokens.
+use super::syntax::{self, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    //the rule a production or lexical rule declares
+    Definition,
+    //references to rules
+    Production,
+    Lexical,
+    Parameter,
+    Label,
+    Mode,
+    //the names of `use` and `extends`
+    Path,
+    Keyword,
+    Literal,
+    Epsilon,
+    CharacterClass,
+    //action code and result types
+    Code,
+    Operator,
+    Comment,
+    Whitespace,
+    //input the parser could not use
+    Error,
+}
+
+//Default colors of html, one rule per class
+pub const CSS: &str = "pre.gideon { color: #24292e; }
+.gideon .definition { color: #005cc5; font-weight: bold; }
+.gideon .production { color: #005cc5; }
+.gideon .lexical { color: #6f42c1; }
+.gideon .parameter, .gideon .label { color: #e36209; }
+.gideon .mode, .gideon .path { color: #22863a; }
+.gideon .keyword { color: #d73a49; font-weight: bold; }
+.gideon .literal { color: #032f62; }
+.gideon .epsilon { color: #d73a49; }
+.gideon .character-class { color: #b31d28; }
+.gideon .code { color: #586069; }
+.gideon .operator { color: #d73a49; }
+.gideon .comment { color: #6a737d; font-style: italic; }
+.gideon .error { color: #b31d28; text-decoration: underline wavy; }
+";
+
+impl TokenClass {
+    //Also the CSS class of html
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenClass::Definition => "definition",
+            TokenClass::Production => "production",
+            TokenClass::Lexical => "lexical",
+            TokenClass::Parameter => "parameter",
+            TokenClass::Label => "label",
+            TokenClass::Mode => "mode",
+            TokenClass::Path => "path",
+            TokenClass::Keyword => "keyword",
+            TokenClass::Literal => "literal",
+            TokenClass::Epsilon => "epsilon",
+            TokenClass::CharacterClass => "character-class",
+            TokenClass::Code => "code",
+            TokenClass::Operator => "operator",
+            TokenClass::Comment => "comment",
+            TokenClass::Whitespace => "whitespace",
+            TokenClass::Error => "error",
+        }
+    }
+
+    //SGR parameters of ansi, None for plain text
+    fn sgr(self) -> Option<&'static str> {
+        match self {
+            TokenClass::Definition => Some("1;34"),
+            TokenClass::Production => Some("34"),
+            TokenClass::Lexical => Some("35"),
+            TokenClass::Parameter | TokenClass::Label => Some("36"),
+            TokenClass::Mode | TokenClass::Path => Some("32"),
+            TokenClass::Keyword => Some("1;31"),
+            TokenClass::Literal => Some("33"),
+            TokenClass::Epsilon => Some("31"),
+            TokenClass::CharacterClass => Some("91"),
+            TokenClass::Code => Some("2"),
+            TokenClass::Operator => Some("1"),
+            TokenClass::Comment => Some("2;3"),
+            TokenClass::Whitespace => None,
+            TokenClass::Error => Some("4;31"),
+        }
+    }
+}
+
+//Every token below `tree` with its class, in source order
+pub fn classify(tree: &SyntaxNode) -> Vec<(SyntaxToken, TokenClass)> {
+    let mut out = Vec::new();
+    walk(tree, &[], &mut out);
+    out
+}
+
+//`source` with ANSI color escapes
+pub fn ansi(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::new();
+    for (token, class) in classify(&syntax::parse(&chars)) {
+        match class.sgr() {
+            Some(sgr) if !token.text().is_empty() => out.push_str(&format!("\x1b[{}m{}\x1b[0m", sgr, token.text())),
+            _ => out.push_str(token.text()),
+        }
+    }
+    out
+}
+
+//`source` as a <pre> element with a span per token, see CSS
+pub fn html(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::from("<pre class=\"gideon\">");
+    for (token, class) in classify(&syntax::parse(&chars)) {
+        let text = escape(token.text());
+        match class {
+            _ if text.is_empty() => {}
+            TokenClass::Whitespace => out.push_str(&text),
+            _ => out.push_str(&format!("<span class=\"{}\">{}</span>", class.name(), text)),
+        }
+    }
+    out.push_str("</pre>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+//`params` are the parameters of the production `node` is in
+fn walk(node: &SyntaxNode, params: &[String], out: &mut Vec<(SyntaxToken, TokenClass)>) {
+    let tokens: Vec<SyntaxToken> = node
+        .children()
+        .into_iter()
+        .filter_map(|child| match child {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+        .filter(|token| !token.kind().is_trivia())
+        .collect();
+    let own;
+    let params = if node.kind() == SyntaxKind::Prod {
+        own = parameters(&tokens);
+        &own
+    } else {
+        params
+    };
+    let classes = classes(node, &tokens, params);
+    let mut next = 0;
+    for child in node.children() {
+        match child {
+            SyntaxElement::Node(child) => walk(&child, params, out),
+            SyntaxElement::Token(token) => {
+                let class = match token.kind() {
+                    SyntaxKind::Whitespace => TokenClass::Whitespace,
+                    SyntaxKind::Comment => TokenClass::Comment,
+                    _ => {
+                        next += 1;
+                        classes[next - 1]
+                    }
+                };
+                out.push((token, class));
+            }
+        }
+    }
+}
+
+//Classes of the tokens right below `node`, trivia left out
+fn classes(node: &SyntaxNode, tokens: &[SyntaxToken], params: &[String]) -> Vec<TokenClass> {
+    let mut classes: Vec<TokenClass> = tokens.iter().map(|token| by_kind(token.kind())).collect();
+    let names = tokens
+        .iter()
+        .enumerate()
+        .filter(|&(_, token)| token.kind() == SyntaxKind::Name)
+        .map(|(i, _)| i);
+    match node.kind() {
+        SyntaxKind::Path | SyntaxKind::Extends => {
+            for (i, token) in tokens.iter().enumerate() {
+                if token.kind() == SyntaxKind::Name || token.kind() == SyntaxKind::PathSeperator {
+                    classes[i] = TokenClass::Path;
+                }
+            }
+        }
+        SyntaxKind::Prod => {
+            let head = match tokens.iter().position(|token| token.kind() == SyntaxKind::Name) {
+                Some(head) => head,
+                None => return classes,
+            };
+            let braced = head > 0 && tokens[head - 1].kind() == SyntaxKind::OpenBrace;
+            let changes = node.parent().map(SyntaxNode::kind) == Some(SyntaxKind::Override)
+                || tokens.iter().any(|token| token.kind() == SyntaxKind::AppendArrow);
+            let class = match (changes, braced) {
+                (false, _) => TokenClass::Definition,
+                (true, true) => TokenClass::Lexical,
+                (true, false) => TokenClass::Production,
+            };
+            classes[head] = class;
+            if braced {
+                classes[head - 1] = class;
+                if tokens.get(head + 1).map(SyntaxToken::kind) == Some(SyntaxKind::CloseBrace) {
+                    classes[head + 1] = class;
+                }
+            }
+            //parameters and modifiers, up to the arrow
+            let mut depth = 0;
+            for i in head + 1..tokens.len() {
+                match tokens[i].kind() {
+                    SyntaxKind::Arrow | SyntaxKind::AppendArrow => break,
+                    SyntaxKind::OpenAngle => depth += 1,
+                    SyntaxKind::CloseAngle => depth -= 1,
+                    SyntaxKind::Name if depth > 0 => classes[i] = TokenClass::Parameter,
+                    SyntaxKind::Name => classes[i] = modifier(tokens[i].text()),
+                    _ => {}
+                }
+            }
+        }
+        SyntaxKind::Skip => {
+            for i in names {
+                classes[i] = modifier(tokens[i].text());
+            }
+        }
+        SyntaxKind::Part => {
+            for i in names {
+                let next = tokens.get(i + 1).map(SyntaxToken::kind);
+                if next == Some(SyntaxKind::Colon) {
+                    classes[i] = TokenClass::Label;
+                } else if i > 0 && tokens[i - 1].kind() == SyntaxKind::OpenBrace {
+                    classes[i - 1] = TokenClass::Lexical;
+                    classes[i] = TokenClass::Lexical;
+                    if next == Some(SyntaxKind::CloseBrace) {
+                        classes[i + 1] = TokenClass::Lexical;
+                    }
+                } else if params.iter().any(|param| param == tokens[i].text()) {
+                    classes[i] = TokenClass::Parameter;
+                }
+            }
+        }
+        _ => {}
+    }
+    classes
+}
+
+//Contextual keywords of lexical rule heads and skip declarations, the
+//other names there are modes
+fn modifier(name: &str) -> TokenClass {
+    match name {
+        "in" | "push" | "pop" => TokenClass::Keyword,
+        _ => TokenClass::Mode,
+    }
+}
+
+//Names of the parameters of a parameterized production, from the tokens
+//right below it
+fn parameters(tokens: &[SyntaxToken]) -> Vec<String> {
+    let open = tokens.iter().position(|token| token.kind() == SyntaxKind::OpenAngle);
+    let arrow = tokens
+        .iter()
+        .position(|token| token.kind() == SyntaxKind::Arrow || token.kind() == SyntaxKind::AppendArrow);
+    match (open, arrow) {
+        (Some(open), Some(arrow)) if open < arrow => tokens[open..arrow]
+            .iter()
+            .filter(|token| token.kind() == SyntaxKind::Name)
+            .map(|token| token.text().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn by_kind(kind: SyntaxKind) -> TokenClass {
+    match kind {
+        SyntaxKind::Name => TokenClass::Production,
+        SyntaxKind::Literal => TokenClass::Literal,
+        SyntaxKind::Epsilon => TokenClass::Epsilon,
+        SyntaxKind::Class | SyntaxKind::Property => TokenClass::CharacterClass,
+        SyntaxKind::Code | SyntaxKind::Type => TokenClass::Code,
+        SyntaxKind::Use
+        | SyntaxKind::SkipKeyword
+        | SyntaxKind::LayoutKeyword
+        | SyntaxKind::StartKeyword
+        | SyntaxKind::ExtendsKeyword
+        | SyntaxKind::OverrideKeyword => TokenClass::Keyword,
+        SyntaxKind::Whitespace => TokenClass::Whitespace,
+        SyntaxKind::Comment => TokenClass::Comment,
+        SyntaxKind::Error => TokenClass::Error,
+        _ => TokenClass::Operator,
+    }
+}
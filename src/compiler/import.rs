@@ -0,0 +1,395 @@
+//Input formats of `gideon import`, the counterpart of backend.rs.
+//
+//An import reads a grammar in another format into the grammar model, which
+//prints as Gideon source. Imported grammars stand alone, they have no use,
+//extends or override statements.
+//
+//  grammophone: the plain BNF of Grammophone, see grammophone.rs
+//  pest:        PEG grammars of pest, see pest.rs
+//  antlr:       ANTLR4 grammars, see antlr.rs
+//  w3c-ebnf:    EBNF of the XML specification, see notation.rs
+//
+//Syntax errors stop an import. Constructs Gideon has no equivalent for are
+//left out or approximated with a warning, unless that would change what the
+//grammar matches beyond recognition, which is an error too.
+//
+//Importers read references as Symbol::Name and call finish, which moves the
+//rules they choose to the lexical rules, rewrites the references to them
+//and gives every rule, label and mode a name Gideon accepts.
+use super::antlr;
+use super::frontend_error::FrontendError;
+use super::grammar::*;
+use super::grammophone;
+use super::notation;
+use super::pest;
+
+use std::collections::HashSet;
+
+pub type ImportResult<T> = Result<T, ImportError>;
+
+//What failed and where, lines and offsets count from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportError {
+    pub what: FrontendError,
+    pub line: usize,
+    pub offset: usize,
+}
+
+impl ImportError {
+    pub fn new(what: FrontendError, line: usize, offset: usize) -> ImportError {
+        ImportError { what, line, offset }
+    }
+
+    //At the end of `input`
+    pub fn at_end(what: FrontendError, input: &[char]) -> ImportError {
+        let line = input.iter().filter(|c| **c == '\n').count() + 1;
+        let offset = input.iter().rev().take_while(|c| **c != '\n').count() + 1;
+        ImportError::new(what, line, offset)
+    }
+}
+
+//An imported grammar and what it lost on the way
+#[derive(Debug, Clone)]
+pub struct Imported {
+    pub grammar: Grammar,
+    pub warnings: Vec<ImportError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Import {
+    Grammophone,
+    Pest,
+    Antlr,
+    W3cEbnf,
+}
+
+pub const IMPORTS: &[Import] = &[Import::Grammophone, Import::Pest, Import::Antlr, Import::W3cEbnf];
+
+impl Import {
+    pub fn from_name(name: &str) -> Option<Import> {
+        IMPORTS.iter().cloned().find(|import| import.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Import::Grammophone => "grammophone",
+            Import::Pest => "pest",
+            Import::Antlr => "antlr",
+            Import::W3cEbnf => "w3c-ebnf",
+        }
+    }
+
+    pub fn import(self, source: &str) -> ImportResult<Imported> {
+        match self {
+            Import::Grammophone => grammophone::import(source).map(|grammar| Imported {
+                grammar,
+                warnings: Vec::new(),
+            }),
+            Import::Pest => pest::import(source),
+            Import::Antlr => antlr::import(source),
+            Import::W3cEbnf => notation::import(source),
+        }
+    }
+}
+
+//Words the lexer of Gideon reads as keywords, and the modifiers of lexical
+//rules
+const KEYWORDS: &[&str] = &[
+    "None", "use", "skip", "layout", "start", "extends", "override", "in", "push", "pop",
+];
+
+//`name` as a Gideon name: characters it does not allow become '_', and a
+//keyword gets a '_' appended
+pub fn rule_name(name: &str) -> String {
+    let mut out: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if out.chars().next().is_none_or(|c| !c.is_alphabetic() && c != '_') {
+        out.insert(0, '_');
+    }
+    if KEYWORDS.contains(&out.as_str()) {
+        out.push('_');
+    }
+    out
+}
+
+//The rules of an import as productions, with the rules named in `lexical`
+//and the ones they refer to as lexical rules
+pub fn finish(mut grammar: Grammar, lexical: &[&str]) -> Grammar {
+    let mut lexical: HashSet<String> = lexical.iter().map(|name| name.to_string()).collect();
+    let mut pending: Vec<String> = lexical.iter().cloned().collect();
+    while let Some(name) = pending.pop() {
+        let rule = match grammar.productions.iter().find(|prod| prod.name == name) {
+            Some(rule) => rule,
+            None => continue,
+        };
+        let mut stack = Vec::new();
+        push_symbols(&mut stack, &rule.alternatives);
+        while let Some(symbol) = stack.pop() {
+            match *symbol {
+                Symbol::Name(ref name) | Symbol::Lexical(ref name) if lexical.insert(name.clone()) => {
+                    pending.push(name.clone())
+                }
+                Symbol::Group(ref alternatives) => push_symbols(&mut stack, alternatives),
+                Symbol::And(ref symbol) | Symbol::Not(ref symbol) => stack.push(symbol),
+                _ => {}
+            }
+        }
+    }
+    let productions = ::std::mem::take(&mut grammar.productions);
+    for mut prod in productions {
+        prod.name = rule_name(&prod.name);
+        prod.modes = prod.modes.iter().map(|mode| rule_name(mode)).collect();
+        prod.action = match prod.action {
+            Some(ModeAction::Push(ref mode)) => Some(ModeAction::Push(rule_name(mode))),
+            ref action => action.clone(),
+        };
+        rewrite(&mut prod.alternatives, &lexical);
+        if lexical.contains(&prod.name) {
+            grammar.lexical_rules.push(prod);
+        } else {
+            grammar.productions.push(prod);
+        }
+    }
+    for skip in &mut grammar.skip {
+        skip.modes = skip.modes.iter().map(|mode| rule_name(mode)).collect();
+        rewrite(&mut skip.alternatives, &lexical);
+    }
+    for start in &mut grammar.starts {
+        start.name = rule_name(&start.name);
+    }
+    grammar
+}
+
+//References to rules in `lexical` become Symbol::Lexical, plain groups of
+//a single sequence their items and empty sequences None
+fn rewrite(alternatives: &mut [Alternative], lexical: &HashSet<String>) {
+    for alternative in alternatives.iter_mut() {
+        let items = ::std::mem::take(&mut alternative.items);
+        for item in items {
+            flatten(item, &mut alternative.items);
+        }
+        if alternative.items.is_empty() {
+            alternative.items.push(Item::new(Symbol::Epsilon));
+        }
+        for item in &mut alternative.items {
+            item.label = item.label.as_ref().map(|label| rule_name(label));
+            rewrite_symbol(&mut item.symbol, lexical);
+            if let Some(ref mut separator) = item.separator {
+                rewrite_symbol(&mut separator.symbol, lexical);
+            }
+        }
+    }
+}
+
+fn flatten(item: Item, out: &mut Vec<Item>) {
+    let plain = item.quantifier == Quantifier::One && item.separator.is_none() && item.label.is_none();
+    match item.symbol {
+        Symbol::Group(ref group) if plain && group.len() == 1 => {
+            for item in &group[0].items {
+                flatten(item.clone(), out);
+            }
+        }
+        _ => out.push(item),
+    }
+}
+
+fn rewrite_symbol(symbol: &mut Symbol, lexical: &HashSet<String>) {
+    let replacement = match *symbol {
+        Symbol::Name(ref name) if lexical.contains(name) => Symbol::Lexical(rule_name(name)),
+        Symbol::Name(ref name) => Symbol::Name(rule_name(name)),
+        Symbol::Lexical(ref name) => Symbol::Lexical(rule_name(name)),
+        Symbol::Group(ref mut alternatives) => {
+            rewrite(alternatives, lexical);
+            return;
+        }
+        Symbol::And(ref mut symbol) | Symbol::Not(ref mut symbol) => {
+            rewrite_symbol(symbol, lexical);
+            return;
+        }
+        _ => return,
+    };
+    *symbol = replacement;
+}
+
+//A rule read by an importer, a production until finish
+pub fn rule(name: &str, alternatives: Vec<Alternative>, line: usize, offset: usize) -> Production {
+    Production {
+        name: name.to_string(),
+        nullable: false,
+        result_type: None,
+        alternatives,
+        modes: Vec::new(),
+        action: None,
+        helper: None,
+        line,
+        offset,
+    }
+}
+
+//The item matching `items` in sequence
+pub fn sequence(mut items: Vec<Item>) -> Item {
+    if items.len() == 1 {
+        return items.remove(0);
+    }
+    Item::new(Symbol::Group(vec![Alternative { items, action: None }]))
+}
+
+//The item matching one of `alternatives`
+pub fn choice(mut alternatives: Vec<Alternative>) -> Item {
+    if alternatives.len() == 1 {
+        let alternative = alternatives.remove(0);
+        return sequence(alternative.items);
+    }
+    Item::new(Symbol::Group(alternatives))
+}
+
+//`item` as a single symbol, in a group when it is more than that
+pub fn symbol(item: Item) -> Symbol {
+    if item.quantifier == Quantifier::One && item.separator.is_none() && item.label.is_none() {
+        return item.symbol;
+    }
+    Symbol::Group(vec![Alternative {
+        items: vec![item],
+        action: None,
+    }])
+}
+
+//`item` with `quantifier`, in a group when it has one already
+pub fn quantified(item: Item, quantifier: Quantifier) -> Item {
+    if quantifier == Quantifier::One {
+        return item;
+    }
+    let mut item = match (item.quantifier, item.separator.is_some()) {
+        (Quantifier::One, false) => item,
+        _ => Item::new(Symbol::Group(vec![Alternative {
+            items: vec![item],
+            action: None,
+        }])),
+    };
+    item.quantifier = quantifier;
+    item
+}
+
+//Source text read by the importers, with the position of every character
+pub struct Cursor {
+    chars: Vec<char>,
+    i: usize,
+    line: usize,
+    offset: usize,
+    //comment delimiters, a line comment ends with "\n"
+    comments: &'static [(&'static str, &'static str)],
+}
+
+impl Cursor {
+    pub fn new(source: &str, comments: &'static [(&'static str, &'static str)]) -> Cursor {
+        Cursor {
+            chars: source.chars().collect(),
+            i: 0,
+            line: 1,
+            offset: 1,
+            comments,
+        }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.chars.get(self.i).cloned()
+    }
+
+    pub fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.get(self.i + n).cloned()
+    }
+
+    pub fn at(&self, text: &str) -> bool {
+        text.chars().enumerate().all(|(n, c)| self.peek_at(n) == Some(c))
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.i >= self.chars.len()
+    }
+
+    pub fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.i += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.offset = 1;
+        } else {
+            self.offset += 1;
+        }
+        Some(c)
+    }
+
+    pub fn eat(&mut self, text: &str) -> bool {
+        if !self.at(text) {
+            return false;
+        }
+        for _ in text.chars() {
+            self.bump();
+        }
+        true
+    }
+
+    //Whitespace and comments
+    pub fn skip_space(&mut self) {
+        loop {
+            if self.peek().is_some_and(char::is_whitespace) {
+                self.bump();
+                continue;
+            }
+            match self.comments.iter().find(|&&(open, _)| self.at(open)) {
+                Some(&(open, close)) => {
+                    self.eat(open);
+                    while !self.at_end() && !self.eat(close) {
+                        self.bump();
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+
+    //`text` after whitespace and comments
+    pub fn expect(&mut self, text: &str, what: FrontendError) -> ImportResult<()> {
+        self.skip_space();
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.error(what))
+        }
+    }
+
+    //The characters up to the first one `f` rejects
+    pub fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> String {
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+            out.push(c);
+            self.bump();
+        }
+        out
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.line, self.offset)
+    }
+
+    pub fn error(&self, what: FrontendError) -> ImportError {
+        ImportError::new(what, self.line, self.offset)
+    }
+
+    //The position to return to with restore
+    pub fn save(&self) -> (usize, usize, usize) {
+        (self.i, self.line, self.offset)
+    }
+
+    pub fn restore(&mut self, saved: (usize, usize, usize)) {
+        let (i, line, offset) = saved;
+        self.i = i;
+        self.line = line;
+        self.offset = offset;
+    }
+}
@@ -0,0 +1,872 @@
+//Grammar interpreter, parses input directly with a lowered grammar.
+//
+//The grammar is compiled to plain BNF rules and run through an Earley
+//recognizer, so any context free grammar is accepted, including left
+//recursive and ambiguous ones. An ambiguous input yields one of its trees.
+//
+//Two input levels are supported:
+//  Tokens:      the scanner splits the input into lexemes first and the
+//               literals and lexical rules of the productions are terminals.
+//  Scannerless: the productions work on characters. Literals match
+//               character by character, lexical rules are ordinary
+//               productions whose match becomes a single token node, and
+//               skip declarations are matched implicitly before every
+//               literal, character class and lexical rule used by a
+//               production.
+//
+//Predicates are zero width symbols, `&X` and `!X` hold where a sub
+//recognizer does or does not find some prefix of the remaining input
+//derived from X. In productions trivia is matched before the operand, as it
+//would be in the sequence.
+//
+//The tree of an accepted input is rebuilt without backtracking, where all
+//its choices run through rules of a cycle like A -> B, B -> A the parse
+//fails with CyclicAmbiguity.
+use super::grammar::*;
+use super::frontend_error::*;
+use super::scanner::Scanner;
+use super::char_class::CharClass;
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fmt::Result as FormatResult;
+
+pub type InterpretResult<T> = Result<T, FrontendError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Tokens,
+    Scannerless,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Production {
+        name: String,
+        children: Vec<Node>,
+    },
+    Token {
+        symbol: Symbol,
+        text: String,
+        line: usize,
+        offset: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Terminal {
+    Token(Symbol),
+    Char(char),
+    Class(CharClass),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Sym {
+    NonTerminal(usize),
+    Terminal(usize),
+    Predicate(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    Production(String),
+    //matched text becomes a single token node
+    Lexical(String),
+    Literal(String),
+    //children are spliced into the parent
+    Hidden,
+    //dropped from the tree
+    Trivia,
+}
+
+struct NonTerminal {
+    kind: Kind,
+    rules: Vec<usize>,
+}
+
+struct Predicate {
+    nt: usize,
+    negated: bool,
+}
+
+struct Rule {
+    lhs: usize,
+    rhs: Vec<Sym>,
+}
+
+//One unit of input, a lexeme or a character
+struct Unit {
+    terminal: Terminal,
+    text: String,
+    line: usize,
+    offset: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: usize,
+    dot: usize,
+    origin: usize,
+}
+
+pub struct Interpreter<'a> {
+    grammar: &'a Grammar,
+    level: Level,
+    nonterminals: Vec<NonTerminal>,
+    rules: Vec<Rule>,
+    terminals: Vec<Terminal>,
+    predicates: Vec<Predicate>,
+    nullable: Vec<bool>,
+    productions: HashMap<String, usize>,
+    lexical: HashMap<String, usize>,
+    literals: HashMap<String, usize>,
+    skip: Option<usize>,
+    //outcome of a predicate by the number of units left after it, the
+    //units of a parse are suffixes of each other
+    lookaheads: RefCell<HashMap<(usize, usize), bool>>,
+    //position of the last error
+    line: Cell<usize>,
+    offset: Cell<usize>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(grammar: &'a Grammar, level: Level) -> InterpretResult<Interpreter<'a>> {
+        let mut interpreter = Interpreter {
+            grammar,
+            level,
+            nonterminals: Vec::new(),
+            rules: Vec::new(),
+            terminals: Vec::new(),
+            predicates: Vec::new(),
+            nullable: Vec::new(),
+            productions: HashMap::new(),
+            lexical: HashMap::new(),
+            literals: HashMap::new(),
+            skip: None,
+            lookaheads: RefCell::new(HashMap::new()),
+            line: Cell::new(1),
+            offset: Cell::new(0),
+        };
+        interpreter.compile()?;
+        Ok(interpreter)
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    //Position of the last error
+    pub fn line(&self) -> usize {
+        self.line.get()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset.get()
+    }
+
+    //`start` has to be an entry point when the grammar declares any
+    pub fn parse(&self, start: &str, input: &[char]) -> InterpretResult<Node> {
+        let start = match self.productions.get(start) {
+            Some(_) if !self.grammar.is_entry_point(start) => {
+                return Err(FrontendError::NotAnEntryPoint)
+            }
+            Some(&start) => start,
+            None => return Err(FrontendError::UnknownProduction),
+        };
+        let units = self.units(input)?;
+        self.lookaheads.borrow_mut().clear();
+        let accept = self.rules.len();
+        let mut goal = vec![Sym::NonTerminal(start)];
+        if let Some(skip) = self.skip {
+            goal.push(Sym::NonTerminal(skip));
+        }
+        let sets = self.recognize(&goal, &units);
+        //the recognizer stops at the first unit nothing expects, which is
+        //where the input is wrong
+        let accepted = sets.len() == units.len() + 1
+            && sets[units.len()].iter().any(|item| {
+                item.rule == accept && item.dot == goal.len() && item.origin == 0
+            });
+        if !accepted {
+            let furthest = sets.len() - 1;
+            return Err(self.fail(&units, input, furthest));
+        }
+        let builder = TreeBuilder::new(self, &units, &sets, accept, &goal);
+        match builder.build() {
+            Some(children) => {
+                let mut children = children;
+                if children.len() == 1 {
+                    Ok(children.remove(0))
+                } else {
+                    Ok(Node::Production {
+                        name: String::new(),
+                        children,
+                    })
+                }
+            }
+            None => {
+                self.line.set(1);
+                self.offset.set(0);
+                Err(FrontendError::CyclicAmbiguity)
+            }
+        }
+    }
+
+    fn fail(&self, units: &[Unit], input: &[char], furthest: usize) -> FrontendError {
+        match units.get(furthest) {
+            Some(unit) => {
+                self.line.set(unit.line);
+                self.offset.set(unit.offset);
+                FrontendError::UnexpectedInput
+            }
+            None => {
+                let (line, offset) = end_position(input);
+                self.line.set(line);
+                self.offset.set(offset);
+                FrontendError::UnexpectedEndOfInput
+            }
+        }
+    }
+
+    fn units(&self, input: &[char]) -> InterpretResult<Vec<Unit>> {
+        match self.level {
+            Level::Tokens => {
+                let scanner = Scanner::new(self.grammar, input);
+                let mut units = Vec::new();
+                loop {
+                    match scanner.next() {
+                        Ok(lexeme) => units.push(Unit {
+                            terminal: Terminal::Token(lexeme.symbol().clone()),
+                            text: lexeme.text(),
+                            line: lexeme.line(),
+                            offset: lexeme.offset(),
+                        }),
+                        Err(FrontendError::EOI) => return Ok(units),
+                        Err(what) => {
+                            self.line.set(scanner.line());
+                            self.offset.set(scanner.offset());
+                            return Err(what);
+                        }
+                    }
+                }
+            }
+            Level::Scannerless => {
+                let mut line = 1;
+                let mut offset = 0;
+                let mut units = Vec::new();
+                for &c in input {
+                    units.push(Unit {
+                        terminal: Terminal::Char(c),
+                        text: c.to_string(),
+                        line,
+                        offset,
+                    });
+                    if c == '\n' {
+                        line += 1;
+                        offset = 0;
+                    } else {
+                        offset += 1;
+                    }
+                }
+                Ok(units)
+            }
+        }
+    }
+
+    fn matches(&self, terminal: usize, unit: &Unit) -> bool {
+        match (&self.terminals[terminal], &unit.terminal) {
+            (Terminal::Class(class), Terminal::Char(c)) => class.matches(*c),
+            (terminal, unit) => terminal == unit,
+        }
+    }
+
+    //True where the predicate holds at units[k..]
+    fn lookahead(&self, predicate: usize, units: &[Unit], k: usize) -> bool {
+        let key = (predicate, units.len() - k);
+        if let Some(&holds) = self.lookaheads.borrow().get(&key) {
+            return holds;
+        }
+        let Predicate { nt, negated } = self.predicates[predicate];
+        let goal = [Sym::NonTerminal(nt)];
+        let accept = self.rules.len();
+        let found = self.recognize(&goal, &units[k..]).iter().any(|set| {
+            set.iter().any(|item| item.rule == accept && item.dot == 1)
+        });
+        self.lookaheads.borrow_mut().insert(key, found != negated);
+        found != negated
+    }
+
+    //Earley recognizer, `goal` is the right hand side of the start rule
+    fn recognize(&self, goal: &[Sym], units: &[Unit]) -> Vec<Vec<Item>> {
+        let accept = self.rules.len();
+        let rhs = |rule: usize| -> &[Sym] {
+            if rule == accept {
+                goal
+            } else {
+                &self.rules[rule].rhs
+            }
+        };
+        let mut sets: Vec<Vec<Item>> = Vec::new();
+        let mut next = vec![Item {
+            rule: accept,
+            dot: 0,
+            origin: 0,
+        }];
+        for k in 0..units.len() + 1 {
+            let mut set: Vec<Item> = Vec::new();
+            let mut seen: HashSet<Item> = HashSet::new();
+            //completed without consuming input, through a predicate
+            let mut empty: HashSet<usize> = HashSet::new();
+            for item in next.drain(..) {
+                if seen.insert(item) {
+                    set.push(item);
+                }
+            }
+            if set.is_empty() {
+                break;
+            }
+            let mut i = 0;
+            while i < set.len() {
+                let item = set[i];
+                i += 1;
+                let symbols = rhs(item.rule);
+                if item.dot < symbols.len() {
+                    match symbols[item.dot] {
+                        Sym::NonTerminal(nt) => {
+                            for &rule in &self.nonterminals[nt].rules {
+                                let predicted = Item {
+                                    rule,
+                                    dot: 0,
+                                    origin: k,
+                                };
+                                if seen.insert(predicted) {
+                                    set.push(predicted);
+                                }
+                            }
+                            if self.nullable[nt] || empty.contains(&nt) {
+                                let skipped = Item {
+                                    dot: item.dot + 1,
+                                    ..item
+                                };
+                                if seen.insert(skipped) {
+                                    set.push(skipped);
+                                }
+                            }
+                        }
+                        Sym::Terminal(terminal) => {
+                            if k < units.len() && self.matches(terminal, &units[k]) {
+                                next.push(Item {
+                                    dot: item.dot + 1,
+                                    ..item
+                                });
+                            }
+                        }
+                        Sym::Predicate(predicate) => {
+                            if self.lookahead(predicate, units, k) {
+                                let advanced = Item {
+                                    dot: item.dot + 1,
+                                    ..item
+                                };
+                                if seen.insert(advanced) {
+                                    set.push(advanced);
+                                }
+                            }
+                        }
+                    }
+                } else if item.rule != accept {
+                    let lhs = self.rules[item.rule].lhs;
+                    if item.origin == k {
+                        empty.insert(lhs);
+                    }
+                    let waiting = |parent: &&Item| {
+                        let symbols = rhs(parent.rule);
+                        parent.dot < symbols.len() && symbols[parent.dot] == Sym::NonTerminal(lhs)
+                    };
+                    let parents: Vec<Item> = if item.origin == k {
+                        set.iter().filter(waiting).cloned().collect()
+                    } else {
+                        sets[item.origin].iter().filter(waiting).cloned().collect()
+                    };
+                    for parent in parents {
+                        let advanced = Item {
+                            dot: parent.dot + 1,
+                            ..parent
+                        };
+                        if seen.insert(advanced) {
+                            set.push(advanced);
+                        }
+                    }
+                }
+            }
+            sets.push(set);
+        }
+        sets
+    }
+
+    fn compile(&mut self) -> InterpretResult<()> {
+        for prod in &self.grammar.productions {
+            let nt = self.add_nonterminal(Kind::Production(prod.name.clone()));
+            self.productions.insert(prod.name.clone(), nt);
+        }
+        if self.level == Level::Scannerless {
+            for rule in &self.grammar.lexical_rules {
+                let nt = self.add_nonterminal(Kind::Lexical(rule.name.clone()));
+                self.lexical.insert(rule.name.clone(), nt);
+            }
+            if !self.grammar.skip.is_empty() {
+                let skip = self.add_nonterminal(Kind::Trivia);
+                self.skip = Some(skip);
+                self.add_rule(skip, Vec::new());
+                let alternatives: Vec<Alternative> = self.grammar
+                    .skip
+                    .iter()
+                    .filter(|skip| skip.in_mode(DEFAULT_MODE))
+                    .flat_map(|skip| skip.alternatives.iter().cloned())
+                    .collect();
+                for alternative in &alternatives {
+                    let mut rhs = self.compile_alternative(alternative, false)?;
+                    rhs.push(Sym::NonTerminal(skip));
+                    self.add_rule(skip, rhs);
+                }
+            }
+        }
+        for prod in &self.grammar.productions {
+            let nt = self.productions[&prod.name];
+            self.compile_production(nt, prod, true)?;
+        }
+        if self.level == Level::Scannerless {
+            for rule in &self.grammar.lexical_rules {
+                let nt = self.lexical[&rule.name];
+                self.compile_production(nt, rule, false)?;
+            }
+        }
+        self.compute_nullable();
+        Ok(())
+    }
+
+    fn compile_production(
+        &mut self,
+        nt: usize,
+        prod: &Production,
+        syntactic: bool,
+    ) -> InterpretResult<()> {
+        if prod.nullable {
+            self.add_rule(nt, Vec::new());
+        }
+        for alternative in &prod.alternatives {
+            let rhs = self.compile_alternative(alternative, syntactic)?;
+            self.add_rule(nt, rhs);
+        }
+        Ok(())
+    }
+
+    //`syntactic` alternatives belong to productions, in scannerless mode
+    //they match trivia before their literals and lexical rules
+    fn compile_alternative(
+        &mut self,
+        alternative: &Alternative,
+        syntactic: bool,
+    ) -> InterpretResult<Vec<Sym>> {
+        let mut rhs = Vec::new();
+        for item in &alternative.items {
+            let symbol = match self.compile_symbol(&item.symbol, syntactic)? {
+                Some(symbol) => symbol,
+                None => continue,
+            };
+            let symbol = match item.quantifier {
+                Quantifier::One => symbol,
+                Quantifier::Optional => {
+                    let optional = self.add_nonterminal(Kind::Hidden);
+                    self.add_rule(optional, vec![symbol]);
+                    self.add_rule(optional, Vec::new());
+                    Sym::NonTerminal(optional)
+                }
+                //left recursive, which suits the Earley recognizer
+                Quantifier::Star | Quantifier::Plus => {
+                    let separator = match item.separator {
+                        Some(ref separator) => self.compile_symbol(&separator.symbol, syntactic)?,
+                        None => None,
+                    };
+                    let repeat = self.add_nonterminal(Kind::Hidden);
+                    let mut rhs = vec![Sym::NonTerminal(repeat)];
+                    rhs.extend(separator);
+                    rhs.push(symbol);
+                    self.add_rule(repeat, rhs);
+                    self.add_rule(repeat, vec![symbol]);
+                    let mut list = repeat;
+                    if item.separator.as_ref().is_some_and(|s| s.trailing) {
+                        list = self.add_nonterminal(Kind::Hidden);
+                        self.add_rule(list, vec![Sym::NonTerminal(repeat)]);
+                        let mut rhs = vec![Sym::NonTerminal(repeat)];
+                        rhs.extend(separator);
+                        self.add_rule(list, rhs);
+                    }
+                    if item.quantifier == Quantifier::Star {
+                        let optional = self.add_nonterminal(Kind::Hidden);
+                        self.add_rule(optional, vec![Sym::NonTerminal(list)]);
+                        self.add_rule(optional, Vec::new());
+                        list = optional;
+                    }
+                    Sym::NonTerminal(list)
+                }
+            };
+            rhs.push(symbol);
+        }
+        Ok(rhs)
+    }
+
+    fn compile_symbol(&mut self, symbol: &Symbol, syntactic: bool) -> InterpretResult<Option<Sym>> {
+        let compiled = match *symbol {
+            Symbol::Epsilon => return Ok(None),
+            Symbol::Group(ref alternatives) => {
+                let group = self.add_nonterminal(Kind::Hidden);
+                for alternative in alternatives {
+                    let rhs = self.compile_alternative(alternative, syntactic)?;
+                    self.add_rule(group, rhs);
+                }
+                return Ok(Some(Sym::NonTerminal(group)));
+            }
+            Symbol::And(ref operand) | Symbol::Not(ref operand) => {
+                let nt = self.add_nonterminal(Kind::Hidden);
+                let rhs = self.compile_symbol(operand, syntactic)?.into_iter().collect();
+                self.add_rule(nt, rhs);
+                self.predicates.push(Predicate {
+                    nt,
+                    negated: matches!(*symbol, Symbol::Not(_)),
+                });
+                return Ok(Some(Sym::Predicate(self.predicates.len() - 1)));
+            }
+            Symbol::Name(ref name) => match self.productions.get(name) {
+                Some(&nt) => Sym::NonTerminal(nt),
+                None => return Err(FrontendError::UnknownProduction),
+            },
+            Symbol::Literal(_) | Symbol::Lexical(_) | Symbol::Class(_)
+                if self.level == Level::Tokens => {
+                Sym::Terminal(self.add_terminal(Terminal::Token(symbol.clone())))
+            }
+            Symbol::Class(ref class) => Sym::Terminal(self.add_terminal(Terminal::Class(class.clone()))),
+            Symbol::Literal(ref value) => Sym::NonTerminal(self.literal(value)),
+            Symbol::Lexical(ref name) => match self.lexical.get(name) {
+                Some(&nt) => Sym::NonTerminal(nt),
+                None => return Err(FrontendError::UnknownLexicalRule),
+            },
+        };
+        let production = match compiled {
+            Sym::NonTerminal(nt) => self.is_production(nt),
+            Sym::Terminal(_) | Sym::Predicate(_) => false,
+        };
+        match self.skip {
+            Some(skip) if syntactic && !production => {
+                let with_trivia = self.add_nonterminal(Kind::Hidden);
+                self.add_rule(with_trivia, vec![Sym::NonTerminal(skip), compiled]);
+                Ok(Some(Sym::NonTerminal(with_trivia)))
+            }
+            _ => Ok(Some(compiled)),
+        }
+    }
+
+    fn is_production(&self, nt: usize) -> bool {
+        matches!(self.nonterminals[nt].kind, Kind::Production(_))
+    }
+
+    fn literal(&mut self, value: &str) -> usize {
+        if let Some(&nt) = self.literals.get(value) {
+            return nt;
+        }
+        let nt = self.add_nonterminal(Kind::Literal(value.to_string()));
+        let rhs = value
+            .chars()
+            .map(|c| Sym::Terminal(self.add_terminal(Terminal::Char(c))))
+            .collect();
+        self.add_rule(nt, rhs);
+        self.literals.insert(value.to_string(), nt);
+        nt
+    }
+
+    fn add_nonterminal(&mut self, kind: Kind) -> usize {
+        self.nonterminals.push(NonTerminal {
+            kind,
+            rules: Vec::new(),
+        });
+        self.nonterminals.len() - 1
+    }
+
+    fn add_rule(&mut self, lhs: usize, rhs: Vec<Sym>) {
+        self.rules.push(Rule { lhs, rhs });
+        let rule = self.rules.len() - 1;
+        self.nonterminals[lhs].rules.push(rule);
+    }
+
+    fn add_terminal(&mut self, terminal: Terminal) -> usize {
+        match self.terminals.iter().position(|t| *t == terminal) {
+            Some(index) => index,
+            None => {
+                self.terminals.push(terminal);
+                self.terminals.len() - 1
+            }
+        }
+    }
+
+    fn compute_nullable(&mut self) {
+        self.nullable = vec![false; self.nonterminals.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in &self.rules {
+                if self.nullable[rule.lhs] {
+                    continue;
+                }
+                let nullable = rule.rhs.iter().all(|sym| match *sym {
+                    Sym::NonTerminal(nt) => self.nullable[nt],
+                    //recognized through the lookahead instead
+                    Sym::Terminal(_) | Sym::Predicate(_) => false,
+                });
+                if nullable {
+                    self.nullable[rule.lhs] = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+}
+
+//Rebuilds a derivation from the Earley sets, right to left: the symbol
+//before the dot of an item ends where the item is found and starts where
+//the item with the dot one step back is. Rules being rebuilt are frames of
+//an explicit stack, a repetition nests one level per element.
+struct TreeBuilder<'i, 'a: 'i> {
+    interpreter: &'i Interpreter<'a>,
+    units: &'i [Unit],
+    accept: usize,
+    goal: &'i [Sym],
+    items: Vec<HashSet<Item>>,
+    //nonterminal and end position to the rules and start positions completed
+    completed: HashMap<(usize, usize), Vec<(usize, usize)>>,
+}
+
+struct Frame {
+    rule: usize,
+    //nonterminal of the rule, none for the start rule
+    nt: Option<usize>,
+    origin: usize,
+    end: usize,
+    dot: usize,
+    pos: usize,
+    //frame whose children the nodes go to, the closest one that is not
+    //hidden, hidden rules splice their children into it
+    owner: usize,
+    //in reverse order
+    children: Vec<Node>,
+}
+
+impl<'i, 'a> TreeBuilder<'i, 'a> {
+    fn new(
+        interpreter: &'i Interpreter<'a>,
+        units: &'i [Unit],
+        sets: &[Vec<Item>],
+        accept: usize,
+        goal: &'i [Sym],
+    ) -> Self {
+        let mut completed: HashMap<(usize, usize), Vec<(usize, usize)>> = HashMap::new();
+        for (end, set) in sets.iter().enumerate() {
+            for item in set {
+                if item.rule != accept && item.dot == interpreter.rules[item.rule].rhs.len() {
+                    let lhs = interpreter.rules[item.rule].lhs;
+                    completed.entry((lhs, end)).or_default().push((item.rule, item.origin));
+                }
+            }
+        }
+        TreeBuilder {
+            interpreter,
+            units,
+            accept,
+            goal,
+            items: sets.iter().map(|set| set.iter().cloned().collect()).collect(),
+            completed,
+        }
+    }
+
+    fn rhs(&self, rule: usize) -> &[Sym] {
+        if rule == self.accept {
+            self.goal
+        } else {
+            &self.interpreter.rules[rule].rhs
+        }
+    }
+
+    //Children of the start rule, none where only cyclic derivations are
+    //left to choose from
+    fn build(&self) -> Option<Vec<Node>> {
+        let mut stack = vec![Frame {
+            rule: self.accept,
+            nt: None,
+            origin: 0,
+            end: self.units.len(),
+            dot: self.goal.len(),
+            pos: self.units.len(),
+            owner: 0,
+            children: Vec::new(),
+        }];
+        let mut active: HashSet<(usize, usize, usize)> = HashSet::new();
+        loop {
+            let top = stack.len() - 1;
+            let Frame { rule, nt, origin, end, dot, pos, owner, .. } = *stack.last()?;
+            if dot == 0 {
+                let frame = stack.pop()?;
+                let nt = match nt {
+                    Some(nt) => nt,
+                    None => return Some(frame.children.into_iter().rev().collect()),
+                };
+                active.remove(&(rule, origin, end));
+                if owner == top {
+                    let children = frame.children.into_iter().rev().collect();
+                    let nodes = self.wrap(nt, children, origin, end);
+                    let owner = stack.last()?.owner;
+                    stack[owner].children.extend(nodes.into_iter().rev());
+                }
+                continue;
+            }
+            let dot = dot - 1;
+            stack[top].dot = dot;
+            match self.rhs(rule)[dot] {
+                Sym::Terminal(terminal) => {
+                    stack[owner].children.push(self.unit(terminal, pos - 1));
+                    stack[top].pos = pos - 1;
+                }
+                Sym::Predicate(_) => {}
+                Sym::NonTerminal(nt) => {
+                    //the shortest match for the later symbol, so the
+                    //earlier ones match as much as they can
+                    let previous = Item { rule, dot, origin };
+                    let (sub_rule, start) = self.completed
+                        .get(&(nt, pos))?
+                        .iter()
+                        .cloned()
+                        .filter(|&(sub_rule, start)| {
+                            self.items[start].contains(&previous)
+                                && !active.contains(&(sub_rule, start, pos))
+                        })
+                        .max_by_key(|&(sub_rule, start)| (start, Reverse(sub_rule)))?;
+                    stack[top].pos = start;
+                    match self.interpreter.nonterminals[nt].kind {
+                        Kind::Production(_) | Kind::Hidden => {
+                            active.insert((sub_rule, start, pos));
+                            let hidden = self.interpreter.nonterminals[nt].kind == Kind::Hidden;
+                            stack.push(Frame {
+                                rule: sub_rule,
+                                nt: Some(nt),
+                                origin: start,
+                                end: pos,
+                                dot: self.rhs(sub_rule).len(),
+                                pos,
+                                owner: if hidden { owner } else { top + 1 },
+                                children: Vec::new(),
+                            });
+                        }
+                        //a single token or nothing, whatever derived it
+                        Kind::Lexical(_) | Kind::Literal(_) | Kind::Trivia => {
+                            let nodes = self.wrap(nt, Vec::new(), start, pos);
+                            stack[owner].children.extend(nodes.into_iter().rev());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    //Token node of the unit at `pos` matched by `terminal`
+    fn unit(&self, terminal: usize, pos: usize) -> Node {
+        let unit = &self.units[pos];
+        let symbol = match (&self.interpreter.terminals[terminal], &unit.terminal) {
+            (Terminal::Class(class), _) => Symbol::Class(class.clone()),
+            (_, Terminal::Token(symbol)) => symbol.clone(),
+            _ => Symbol::Literal(unit.text.clone()),
+        };
+        Node::Token {
+            symbol,
+            text: unit.text.clone(),
+            line: unit.line,
+            offset: unit.offset,
+        }
+    }
+
+    fn wrap(&self, nt: usize, children: Vec<Node>, start: usize, end: usize) -> Vec<Node> {
+        match self.interpreter.nonterminals[nt].kind {
+            Kind::Production(ref name) => vec![
+                Node::Production {
+                    name: name.clone(),
+                    children,
+                },
+            ],
+            Kind::Lexical(_) | Kind::Literal(_) if start == end => Vec::new(),
+            Kind::Lexical(ref name) => vec![self.token(Symbol::Lexical(name.clone()), start, end)],
+            Kind::Literal(ref value) => vec![self.token(Symbol::Literal(value.clone()), start, end)],
+            Kind::Hidden => children,
+            Kind::Trivia => Vec::new(),
+        }
+    }
+
+    fn token(&self, symbol: Symbol, start: usize, end: usize) -> Node {
+        let text = self.units[start..end].iter().map(|u| u.text.as_str()).collect();
+        Node::Token {
+            symbol,
+            text,
+            line: self.units[start].line,
+            offset: self.units[start].offset,
+        }
+    }
+}
+
+fn end_position(input: &[char]) -> (usize, usize) {
+    let mut line = 1;
+    let mut offset = 0;
+    for &c in input {
+        if c == '\n' {
+            line += 1;
+            offset = 0;
+        } else {
+            offset += 1;
+        }
+    }
+    (line, offset)
+}
+
+impl Node {
+    fn write(&self, f: &mut Formatter, depth: usize) -> FormatResult {
+        match *self {
+            Node::Production {
+                ref name,
+                ref children,
+            } => {
+                writeln!(f, "{:width$}{}", "", name, width = depth * 2)?;
+                for child in children {
+                    child.write(f, depth + 1)?;
+                }
+                Ok(())
+            }
+            Node::Token {
+                ref symbol,
+                ref text,
+                line,
+                offset,
+            } => writeln!(
+                f,
+                "{:width$}{} \"{}\" {}:{}",
+                "",
+                symbol,
+                escape(text),
+                line,
+                offset,
+                width = depth * 2
+            ),
+        }
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        self.write(f, 0)
+    }
+}
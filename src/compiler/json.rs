@@ -0,0 +1,302 @@
+//JSON values for the language server, see lsp.rs.
+//
+//Objects keep their keys in order, so replies come out as they are built.
+//Numbers are f64, integers print without a fraction.
+use std::fmt::{Display, Formatter, Write};
+use std::fmt::Result as FormatResult;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    //The value of `key` in an object, Null when it is missing
+    pub fn get(&self, key: &str) -> &Json {
+        const NULL: &Json = &Json::Null;
+        match *self {
+            Json::Object(ref members) => members
+                .iter()
+                .find(|member| member.0 == key)
+                .map(|member| &member.1)
+                .unwrap_or(NULL),
+            _ => NULL,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Json::Number(n) if n >= 0.0 && n.fract() == 0.0 => Some(n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        match *self {
+            Json::Array(ref items) => items,
+            _ => &[],
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Json::Null
+    }
+}
+
+//An object from its members
+pub fn object(members: Vec<(&str, Json)>) -> Json {
+    Json::Object(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+impl<'s> From<&'s str> for Json {
+    fn from(text: &'s str) -> Json {
+        Json::String(text.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(text: String) -> Json {
+        Json::String(text)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", n as i64),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(ref text) => quote(f, text),
+            Json::Array(ref items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(ref members) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    quote(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn quote(f: &mut Formatter, text: &str) -> FormatResult {
+    f.write_char('"')?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+//None when `text` is not a single JSON value
+pub fn parse(text: &str) -> Option<Json> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut reader = Reader {
+        input: &chars,
+        current: 0,
+    };
+    let value = reader.value()?;
+    reader.space();
+    if reader.current == chars.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+struct Reader<'a> {
+    input: &'a [char],
+    current: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.current).cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.current += 1;
+        c
+    }
+
+    fn space(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.current += 1;
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Option<Json> {
+        for expected in word.chars() {
+            if self.next() != Some(expected) {
+                return None;
+            }
+        }
+        Some(value)
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.space();
+        match self.peek()? {
+            'n' => self.keyword("null", Json::Null),
+            't' => self.keyword("true", Json::Bool(true)),
+            'f' => self.keyword("false", Json::Bool(false)),
+            '"' => self.string().map(Json::String),
+            '[' => {
+                self.current += 1;
+                let mut items = Vec::new();
+                self.space();
+                if self.peek() == Some(']') {
+                    self.current += 1;
+                    return Some(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.space();
+                    match self.next()? {
+                        ',' => {}
+                        ']' => return Some(Json::Array(items)),
+                        _ => return None,
+                    }
+                }
+            }
+            '{' => {
+                self.current += 1;
+                let mut members = Vec::new();
+                self.space();
+                if self.peek() == Some('}') {
+                    self.current += 1;
+                    return Some(Json::Object(members));
+                }
+                loop {
+                    self.space();
+                    if self.peek() != Some('"') {
+                        return None;
+                    }
+                    let key = self.string()?;
+                    self.space();
+                    if self.next() != Some(':') {
+                        return None;
+                    }
+                    members.push((key, self.value()?));
+                    self.space();
+                    match self.next()? {
+                        ',' => {}
+                        '}' => return Some(Json::Object(members)),
+                        _ => return None,
+                    }
+                }
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let start = self.current;
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' | '-' | '+' | '.' | 'e' | 'E' => self.current += 1,
+                _ => break,
+            }
+        }
+        let text: String = self.input[start..self.current].iter().collect();
+        text.parse().ok().map(Json::Number)
+    }
+
+    //After the opening quote is peeked
+    fn string(&mut self) -> Option<String> {
+        self.current += 1;
+        let mut out = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Some(out),
+                '\\' => match self.next()? {
+                    '"' => out.push('"'),
+                    '\\' => out.push('\\'),
+                    '/' => out.push('/'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    't' => out.push('\t'),
+                    'u' => {
+                        let high = self.hex()?;
+                        let code = if (0xD800..0xDC00).contains(&high) {
+                            if self.next() != Some('\\') || self.next() != Some('u') {
+                                return None;
+                            }
+                            let low = self.hex()?;
+                            0x10000 + ((high - 0xD800) << 10) + (low.checked_sub(0xDC00)? & 0x3FF)
+                        } else {
+                            high
+                        };
+                        out.push(::std::char::from_u32(code)?);
+                    }
+                    _ => return None,
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            code = code * 16 + self.next()?.to_digit(16)?;
+        }
+        Some(code)
+    }
+}
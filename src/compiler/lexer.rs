@@ -9,8 +9,42 @@ OR: \|
 ENDL ;
 EPSILON: ϵ | None
 LEXICAL: \{ NAME \}
-LITERAL: "([^"] |\\")+"
+LITERAL: "([^"] |\\["'\\ntr])+" | '([^'] |\\["'\\ntr])+'
+CLASS: \[ ([^\]] | \\.)* \]
+PROPERTY: \\[pP]\{[^}]*\}
+RANGE: \.\.
+OPEN PAREN: \(
+CLOSE PAREN: \)
+STAR: \*
+PLUS: \+
+APPEND ARROW: \+->
+PERCENT: %
+DOUBLE PERCENT: %%
+OPEN ANGLE: <
+CLOSE ANGLE: >
+COMMA: ,
+COLON: :
+FAT ARROW: =>
+AMP: &
+BANG: !
 COMMENT: #.*\r?\n
+USE: use
+SKIP: skip
+LAYOUT: layout
+START: start
+EXTENDS: extends
+OVERRIDE: override
+
+Keywords other than None only start declarations, elsewhere they are names,
+as they are in front of a production head: ->, +->, ?, : or <.
+
+CODE and TYPE are opaque and only scanned when the parser asks for them:
+
+CODE: \{ balanced braces, skipping Rust strings, chars and comments \}
+TYPE: everything up to the next -> outside of (), [] and <>
+
+Whitespace and comments are trivia. By default they are skipped, with
+Trivia::Attach they are kept as the leading trivia of the next token.
 */
 
 use super::token::*;
@@ -19,9 +53,20 @@ use std::cell::Cell;
 
 const NONE: &[char; 4] = &['N', 'o', 'n', 'e'];
 const USE: &[char; 3] = &['u', 's', 'e'];
+const SKIP: &[char; 4] = &['s', 'k', 'i', 'p'];
+const LAYOUT: &[char; 6] = &['l', 'a', 'y', 'o', 'u', 't'];
+const START: &[char; 5] = &['s', 't', 'a', 'r', 't'];
+const EXTENDS: &[char; 7] = &['e', 'x', 't', 'e', 'n', 'd', 's'];
+const OVERRIDE: &[char; 8] = &['o', 'v', 'e', 'r', 'r', 'i', 'd', 'e'];
 
 pub type LexicalResult<'a> = Result<Token<'a>, FrontendError>;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trivia {
+    Skip,
+    Attach,
+}
+
 pub struct Lexer<'a> {
     input: &'a [char],
     current: Cell<usize>,
@@ -30,17 +75,24 @@ pub struct Lexer<'a> {
     line: Cell<usize>,
     offset: Cell<usize>,
     current_token: Cell<LexicalResult<'a>>,
+    mode: Trivia,
+    trivia: Cell<&'a [char]>,
 }
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a [char]) -> Lexer<'a> {
+        Lexer::with_trivia(input, Trivia::Skip)
+    }
+    pub fn with_trivia(input: &'a [char], mode: Trivia) -> Lexer<'a> {
         Lexer {
-            input: input,
+            input,
             current: Cell::from(0),
             last: Cell::from(0),
             end: Cell::from(input.len()),
             line: Cell::from(1),
             offset: Cell::from(0),
             current_token: Cell::from(Err(FrontendError::Default)),
+            mode,
+            trivia: Cell::from(&input[..0]),
         }
     }
     fn current(&'a self) -> Option<char> {
@@ -64,27 +116,109 @@ impl<'a> Lexer<'a> {
 
     fn accept(&'a self) -> TokenData<'a> {
         let slice = self.current_match();
+        let start = self.last.get();
         let line_inc = slice.iter().fold(0, |n: usize, c: &char| if *c == '\n' {
             n + 1
         } else {
             n
         });
         self.last.set(self.current.get());
-        let out = TokenData::new(slice, self.line.get(), self.offset.get());
+        let out = TokenData::new(slice, self.line.get(), self.offset.get())
+            .with_trivia(self.trivia.get())
+            .with_span(start, start + slice.len());
         if line_inc > 0 {
+            let tail = slice.iter().rev().take_while(|c| **c != '\n').count();
             self.line.set(self.line.get() + line_inc);
-            self.offset.set(0);
+            self.offset.set(tail);
         } else {
             self.offset.set(self.offset.get() + slice.len());
         }
         out
     }
 
+    //Position the lexer has reached
+    pub fn line(&self) -> usize {
+        self.line.get()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset.get()
+    }
+
+    //Index of the input the next token starts at or, after an error, the
+    //start of the token that failed
+    pub fn token_start(&self) -> usize {
+        self.last.get()
+    }
+
+    //Index of the input read up to, after an error the character the
+    //lexer failed on
+    pub fn position(&self) -> usize {
+        self.current.get()
+    }
+
     pub fn current_out(&'a self) -> LexicalResult<'a> {
         self.current_token.get()
     }
 
+    //Trivia that was not followed by a token, only kept in Trivia::Attach mode
+    pub fn trailing_trivia(&'a self) -> &'a [char] {
+        match self.current_token.get() {
+            Err(FrontendError::EOI) => self.trivia.get(),
+            _ => &self.input[..0],
+        }
+    }
+
+    //Consumes whitespace and comments ahead of the next token
+    fn skip_trivia(&'a self) {
+        let start = self.current.get();
+        while let Some(currc) = self.current() {
+            if currc == '#' {
+                self.skip();
+                'comment: while let Some(currc) = self.current() {
+                    self.skip();
+                    if currc == '\n' {
+                        self.line.set(self.line.get() + 1);
+                        self.offset.set(0);
+                        break 'comment;
+                    }
+                }
+            } else if currc.is_whitespace() {
+                self.skip();
+                if currc == '\n' {
+                    self.line.set(self.line.get() + 1);
+                    self.offset.set(0);
+                }
+            } else {
+                break;
+            }
+        }
+        match self.mode {
+            Trivia::Skip => self.trivia.set(&self.input[..0]),
+            Trivia::Attach => self.trivia.set(&self.input[start..self.current.get()]),
+        }
+    }
+
+    //Whether a keyword read up to the current character starts a declaration:
+    //it follows a ';' and no production head follows it
+    fn declaration_start(&'a self) -> bool {
+        match self.current_token.get() {
+            Ok(Token::Endl(_)) | Err(_) => {}
+            _ => return false,
+        }
+        let mut rest = self.input[self.current.get()..].iter();
+        while let Some(&c) = rest.next() {
+            if c == '#' {
+                rest.position(|c| *c == '\n');
+            } else if !c.is_whitespace() {
+                return !['-', '+', '?', ':', '<'].contains(&c);
+            }
+        }
+        true
+    }
+
     pub fn next(&'a self) -> LexicalResult<'a> {
+        self.skip_trivia();
         if let Some(currc) = self.current() {
             let tok = match currc {
                 //Match Or
@@ -102,24 +236,23 @@ impl<'a> Lexer<'a> {
                     Ok(Token::CloseBrace(self.accept()))
                 }
                 //Literal
-                '"' => {
+                quote @ '"' | quote @ '\'' => {
                     self.step();
-                    while let Some(currc) = self.current() {
-                        if currc == '"' {
+                    loop {
+                        let currc = match self.current() {
+                            Some(currc) => currc,
+                            None => {
+                                self.current_token.set(Err(FrontendError::UnterminatedLiteral));
+                                return self.current_token.get();
+                            }
+                        };
+                        if currc == quote {
                             self.step();
                             break;
                         } else if currc == '\\' {
                             self.step();
-                            if let Some(currc) = self.current() {
-                                match currc {
-                                    '"' | '\\' => self.step(),
-                                    _ => {
-                                        self.current_token.set(Err(
-                                            FrontendError::ExpectedEscapeSequence,
-                                        ));
-                                        return self.current_token.get();
-                                    }
-                                }
+                            if let Some('"' | '\'' | '\\' | 'n' | 't' | 'r') = self.current() {
+                                self.step();
                             } else {
                                 self.current_token.set(
                                     Err(FrontendError::ExpectedEscapeSequence),
@@ -161,40 +294,142 @@ impl<'a> Lexer<'a> {
                 }
                 ':' => {
                     self.step();
-                    if let Some(look) = self.current() {
-                        if look == ':' {
-                            self.step();
-                            Ok(Token::PathSeperator(self.accept()))
-                        } else {
-                            self.current_token.set(Err(FrontendError::ExpectedColon));
-                            return self.current_token.get();
-                        }
+                    if let Some(':') = self.current() {
+                        self.step();
+                        Ok(Token::PathSeperator(self.accept()))
                     } else {
-                        self.current_token.set(
-                            Err(FrontendError::ExpectedMoreInput),
-                        );
-                        return self.current_token.get();
+                        Ok(Token::Colon(self.accept()))
                     }
                 }
-                '#' => {
-                    self.skip();
-                    'comment: while let Some(currc) = self.current() {
-                        if currc == '\n' {
-                            self.skip();
-                            self.line.set(self.line.get() + 1);
-                            self.offset.set(0);
-                            break 'comment;
-                        } else {
-                            self.skip();
-                        }
+                //Action block start
+                '=' => {
+                    self.step();
+                    if let Some('>') = self.current() {
+                        self.step();
+                        Ok(Token::FatArrow(self.accept()))
+                    } else {
+                        self.current_token.set(Err(FrontendError::ExpectedArrowTip));
+                        return self.current_token.get();
                     }
-                    self.current_token.set(self.next());
-                    return self.current_token.get();
                 }
                 '?' => {
                     self.step();
                     Ok(Token::QMark(self.accept()))
                 }
+                //Grouping and repetition
+                '(' => {
+                    self.step();
+                    Ok(Token::OpenParen(self.accept()))
+                }
+                ')' => {
+                    self.step();
+                    Ok(Token::CloseParen(self.accept()))
+                }
+                '*' => {
+                    self.step();
+                    Ok(Token::Star(self.accept()))
+                }
+                '+' => {
+                    self.step();
+                    let tip = self.input.get(self.current.get() + 1);
+                    if self.current() == Some('-') && tip == Some(&'>') {
+                        self.step();
+                        self.step();
+                        Ok(Token::AppendArrow(self.accept()))
+                    } else {
+                        Ok(Token::Plus(self.accept()))
+                    }
+                }
+                //Lookahead predicates
+                '&' => {
+                    self.step();
+                    Ok(Token::Amp(self.accept()))
+                }
+                '!' => {
+                    self.step();
+                    Ok(Token::Bang(self.accept()))
+                }
+                //Parameters and arguments of parameterized productions
+                '<' => {
+                    self.step();
+                    Ok(Token::OpenAngle(self.accept()))
+                }
+                '>' => {
+                    self.step();
+                    Ok(Token::CloseAngle(self.accept()))
+                }
+                ',' => {
+                    self.step();
+                    Ok(Token::Comma(self.accept()))
+                }
+                //Separated list, %% allows a trailing separator
+                '%' => {
+                    self.step();
+                    if let Some('%') = self.current() {
+                        self.step();
+                        Ok(Token::DoublePercent(self.accept()))
+                    } else {
+                        Ok(Token::Percent(self.accept()))
+                    }
+                }
+                //Character class, validated when the grammar is lowered
+                '[' => {
+                    self.step();
+                    loop {
+                        match self.current() {
+                            Some(']') => {
+                                self.step();
+                                break;
+                            }
+                            Some('\\') => {
+                                self.step();
+                                if self.current().is_some() {
+                                    self.step();
+                                }
+                            }
+                            Some(_) => self.step(),
+                            None => {
+                                self.current_token.set(
+                                    Err(FrontendError::ExpectedCloseBracket),
+                                );
+                                return self.current_token.get();
+                            }
+                        }
+                    }
+                    Ok(Token::Class(self.accept()).clean())
+                }
+                //Unicode property
+                '\\' => {
+                    self.step();
+                    match self.current() {
+                        Some('p') | Some('P') => self.step(),
+                        _ => {
+                            self.current_token.set(Err(FrontendError::ExpectedProperty));
+                            return self.current_token.get();
+                        }
+                    }
+                    if self.current() != Some('{') {
+                        self.current_token.set(Err(FrontendError::ExpectedProperty));
+                        return self.current_token.get();
+                    }
+                    while let Some(currc) = self.current() {
+                        self.step();
+                        if currc == '}' {
+                            break;
+                        }
+                    }
+                    Ok(Token::Property(self.accept()))
+                }
+                '.' => {
+                    self.step();
+                    if let Some('.') = self.current() {
+                        self.step();
+                        Ok(Token::Range(self.accept()))
+                    } else {
+                        self.current_token.set(Err(FrontendError::ExpectedRangeDot));
+                        return self.current_token.get();
+                    }
+                }
                 _ => {
                     if currc.is_alphabetic() || currc == '_' {
                         //NAME
@@ -221,27 +456,23 @@ impl<'a> Lexer<'a> {
                         }
                         if self.current_match() == NONE {
                             Ok(Token::Epsilon(self.accept()))
+                        } else if !self.declaration_start() {
+                            Ok(Token::Name(self.accept()))
                         } else if self.current_match() == USE {
                             Ok(Token::Use(self.accept()))
+                        } else if self.current_match() == SKIP {
+                            Ok(Token::Skip(self.accept()))
+                        } else if self.current_match() == LAYOUT {
+                            Ok(Token::Layout(self.accept()))
+                        } else if self.current_match() == START {
+                            Ok(Token::Start(self.accept()))
+                        } else if self.current_match() == EXTENDS {
+                            Ok(Token::Extends(self.accept()))
+                        } else if self.current_match() == OVERRIDE {
+                            Ok(Token::Override(self.accept()))
                         } else {
                             Ok(Token::Name(self.accept()))
                         }
-                    } else if currc.is_whitespace() {
-                        self.skip();
-                        //Skip all whitespace
-                        'whitespace: while let Some(currc) = self.current() {
-                            if currc.is_whitespace() {
-                                self.skip();
-                                if currc == '\n' {
-                                    self.line.set(self.line.get() + 1);
-                                    self.offset.set(0);
-                                }
-                            } else {
-                                break 'whitespace;
-                            }
-                        }
-                        self.current_token.set(self.next());
-                        return self.current_token.get();
                     } else {
                         self.current_token.set(
                             Err(FrontendError::UnrecognizedInput),
@@ -257,4 +488,127 @@ impl<'a> Lexer<'a> {
             self.current_token.get()
         }
     }
+    //Opaque block of Rust code after '=>', the braces must balance. Braces
+    //inside strings, character literals and comments do not count.
+    pub fn next_code(&'a self) -> LexicalResult<'a> {
+        self.skip_trivia();
+        if self.current() != Some('{') {
+            let error = match self.current() {
+                Some(_) => FrontendError::ExpectedCode,
+                None => FrontendError::EOI,
+            };
+            self.current_token.set(Err(error));
+            return self.current_token.get();
+        }
+        let mut depth = 0;
+        while let Some(currc) = self.current() {
+            self.step();
+            match currc {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.current_token.set(Ok(Token::Code(self.accept()).clean()));
+                        return self.current_token.get();
+                    }
+                }
+                '"' => self.skip_quoted('"'),
+                '\'' => {
+                    //a character literal, otherwise a lifetime
+                    let next = self.input.get(self.current.get() + 1);
+                    if self.current() == Some('\\') || next == Some(&'\'') {
+                        self.skip_quoted('\'');
+                    }
+                }
+                '/' => match self.current() {
+                    Some('/') => {
+                        while let Some(currc) = self.current() {
+                            if currc == '\n' {
+                                break;
+                            }
+                            self.step();
+                        }
+                    }
+                    Some('*') => {
+                        self.step();
+                        let mut nested = 1;
+                        while nested > 0 && self.current().is_some() {
+                            let pair = (self.current(), self.input.get(self.current.get() + 1));
+                            match pair {
+                                (Some('/'), Some(&'*')) => {
+                                    nested += 1;
+                                    self.step();
+                                }
+                                (Some('*'), Some(&'/')) => {
+                                    nested -= 1;
+                                    self.step();
+                                }
+                                _ => {}
+                            }
+                            self.step();
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        self.current_token.set(Err(FrontendError::UnterminatedCode));
+        self.current_token.get()
+    }
+
+    //Skips to the closing quote, honoring backslash escapes
+    fn skip_quoted(&'a self, quote: char) {
+        while let Some(currc) = self.current() {
+            self.step();
+            if currc == '\\' {
+                self.step();
+            } else if currc == quote {
+                break;
+            }
+        }
+    }
+
+    //Opaque result type after ':' in a production head, it ends before the
+    //next '->' outside of brackets, so bare function types need parentheses
+    pub fn next_type(&'a self) -> LexicalResult<'a> {
+        self.skip_trivia();
+        let mut depth = 0usize;
+        let mut end = self.current.get();
+        loop {
+            match self.current() {
+                Some('-') if depth == 0 && self.input.get(self.current.get() + 1) == Some(&'>') => {
+                    break;
+                }
+                Some('-') if self.input.get(self.current.get() + 1) == Some(&'>') => {
+                    self.step();
+                    self.step();
+                    end = self.current.get();
+                }
+                Some(currc) => {
+                    match currc {
+                        '(' | '[' | '<' => depth += 1,
+                        ')' | ']' | '>' => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    self.step();
+                    if !currc.is_whitespace() {
+                        end = self.current.get();
+                    }
+                }
+                None => {
+                    self.current_token.set(Err(FrontendError::ExpectedArrow));
+                    return self.current_token.get();
+                }
+            }
+        }
+        //trailing whitespace is left for the next token
+        self.current.set(end);
+        if end == self.last.get() {
+            self.current_token.set(Err(FrontendError::ExpectedType));
+        } else {
+            self.current_token.set(Ok(Token::Type(self.accept())));
+        }
+        self.current_token.get()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,523 @@
+//Resolves the `use` statements of grammar files.
+//
+//`use a::b::c;` names the file a/b/c.gideon below the search root and
+//imports all of its productions, lexical rules and parameterized
+//productions. Without such a file a/b.gideon is tried instead and only its
+//rule `c` is imported, together with the rules `c` uses. Lexical rules are
+//named without braces.
+//
+//Paths starting with `std` name the built in modules of the std directory,
+//they are part of the binary and shadow the files below the search root.
+//
+//`extends a::b;` inherits every rule, start, skip and layout declaration of
+//a/b.gideon. The extending file redefines an inherited rule with
+//`override Name -> ...;` and adds alternatives with `Name +-> ...;`, both
+//also apply to imported rules. A plain redefinition collides.
+//
+//Imports are transitive, the rules a file imports are part of it. A rule
+//reached through several paths is imported once, two different rules with
+//one name collide. Start, skip and layout declarations stay in their file.
+//Parameterized productions are expanded in the file using them, so the
+//rules their alternatives name have to be in scope there. The instances of
+//a file loaded for another are named after its path, std::json names the
+//instance of CommaList it uses StdJsonCommaList1, so that they do not
+//collide with the instances of the files importing it.
+use super::grammar::*;
+use super::frontend_error::*;
+use super::parser::Parser;
+use super::syntax_tree as cst;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+pub type LoadResult<T> = Result<T, FrontendError>;
+
+pub const EXTENSION: &str = "gideon";
+
+pub const STD: &str = "std";
+
+//Built in modules by name
+const STD_MODULES: &[(&str, &str)] = &[
+    ("json", include_str!("std/json.gideon")),
+    ("ident", include_str!("std/ident.gideon")),
+    ("number", include_str!("std/number.gideon")),
+    ("space", include_str!("std/space.gideon")),
+    ("list", include_str!("std/list.gideon")),
+];
+
+//A loaded file, its rules include the imported ones
+#[derive(Clone)]
+struct Module {
+    grammar: Grammar,
+    //parameterized productions visible in the file and the files
+    //declaring them
+    templates: Vec<(String, PathBuf)>,
+}
+
+//What one use statement brings into scope
+struct Import {
+    path: UsePath,
+    productions: Vec<Production>,
+    lexical_rules: Vec<Production>,
+    templates: Vec<(String, PathBuf)>,
+}
+
+pub struct Loader {
+    root: PathBuf,
+    //lowered files by canonical path, built in modules by their std path,
+    //and the prefix of the names of their instances
+    loaded: HashMap<(PathBuf, String), Module>,
+    //files being loaded, using one of them again is a cycle
+    loading: Vec<PathBuf>,
+    //text given by load_source and overlay, read instead of the file
+    sources: HashMap<PathBuf, String>,
+    //location of the last error, the position is 0:0 for errors that
+    //concern the whole file
+    file: PathBuf,
+    line: usize,
+    offset: usize,
+}
+
+impl Loader {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Loader {
+            root: root.into(),
+            loaded: HashMap::new(),
+            loading: Vec::new(),
+            sources: HashMap::new(),
+            file: PathBuf::new(),
+            line: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    //Location of the last error
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    //Loads `file`, relative to the search root, with everything it uses
+    pub fn load<P: AsRef<Path>>(&mut self, file: P) -> LoadResult<Grammar> {
+        let file = self.root.join(file);
+        match fs::canonicalize(&file) {
+            Ok(file) => self.load_file(&file).map(|module| module.grammar),
+            Err(_) => {
+                self.fail_at(&file, 0, 0);
+                Err(FrontendError::UnreadableFile)
+            }
+        }
+    }
+
+    //Loads `source` as the text of `file`, which does not have to exist.
+    //Paths it uses are resolved as usual.
+    pub fn load_source<P: AsRef<Path>>(&mut self, file: P, source: &str) -> LoadResult<Grammar> {
+        let file = self.overlay(file, source);
+        self.load_file(&file).map(|module| module.grammar)
+    }
+
+    //Reads `source` as the text of `file` from now on, like the unsaved
+    //text of an editor. Returns the path the file is known by.
+    pub fn overlay<P: AsRef<Path>>(&mut self, file: P, source: &str) -> PathBuf {
+        let file = self.root.join(file);
+        let file = fs::canonicalize(&file).unwrap_or(file);
+        //files loaded before may depend on the old text
+        self.loaded.clear();
+        self.sources.insert(file.clone(), source.to_string());
+        file
+    }
+
+    fn load_file(&mut self, file: &Path) -> LoadResult<Module> {
+        let prefix = if self.loading.is_empty() { String::new() } else { self.prefix(file) };
+        if let Some(module) = self.loaded.get(&(file.to_path_buf(), prefix.clone())) {
+            return Ok(module.clone());
+        }
+        let chars = self.source(file)?;
+        let parser = Parser::new(&chars);
+        let tree = match parser.parse() {
+            Ok(tree) => tree,
+            Err(what) => {
+                self.fail_at(file, parser.line(), parser.offset());
+                return Err(what);
+            }
+        };
+        let (uses, extends) = match lower_paths(&tree) {
+            Ok(paths) => paths,
+            Err(what) => {
+                self.fail_in(file, &parser, &tree);
+                return Err(what);
+            }
+        };
+        self.loading.push(file.to_path_buf());
+        let imports = self.resolve(file, &uses);
+        let bases = self.bases(file, &extends);
+        self.loading.pop();
+        let (imports, bases) = (imports?, bases?);
+
+        let mut module = Module {
+            grammar: Grammar::default(),
+            templates: templates(&tree)
+                .into_iter()
+                .map(|(name, _)| (name, file.to_path_buf()))
+                .collect(),
+        };
+        let visible = bases
+            .iter()
+            .map(|(path, base)| (path, &base.templates))
+            .chain(imports.iter().map(|import| (&import.path, &import.templates)));
+        for (path, templates) in visible {
+            if merge(&mut module.templates, templates.clone()).is_some() {
+                self.fail_at(file, path.line, path.offset);
+                return Err(FrontendError::NameCollision);
+            }
+        }
+
+        //imported templates are expanded from the syntax trees of their files
+        let mut files: Vec<&PathBuf> = Vec::new();
+        for (_, from) in &module.templates {
+            if from != file && !files.contains(&from) {
+                files.push(from);
+            }
+        }
+        let mut sources = Vec::new();
+        for from in &files {
+            sources.push(self.source(from)?);
+        }
+        let parsers: Vec<Parser> = sources.iter().map(|chars| Parser::new(chars)).collect();
+        let mut trees = Vec::new();
+        for (from, parser) in files.iter().zip(parsers.iter()) {
+            match parser.parse() {
+                Ok(tree) => trees.push(tree),
+                Err(what) => {
+                    self.fail_at(from, parser.line(), parser.offset());
+                    return Err(what);
+                }
+            }
+        }
+        let mut imported: Vec<&cst::Prod> = Vec::new();
+        for (from, tree) in files.iter().zip(trees.iter()) {
+            for (name, prod) in templates(tree) {
+                if module.templates.contains(&(name, from.to_path_buf())) {
+                    imported.push(prod);
+                }
+            }
+        }
+        module.grammar = match Grammar::lower_with(&tree, &imported, &prefix) {
+            Ok(grammar) => grammar,
+            Err(what) => {
+                self.fail_in(file, &parser, &tree);
+                return Err(what);
+            }
+        };
+
+        self.inherit(file, &mut module.grammar, bases)?;
+        for import in imports {
+            let grammar = &mut module.grammar;
+            if merge(&mut grammar.productions, import.productions).is_some()
+                || merge(&mut grammar.lexical_rules, import.lexical_rules).is_some()
+            {
+                self.fail_at(file, import.path.line, import.path.offset);
+                return Err(FrontendError::NameCollision);
+            }
+        }
+        self.apply_overrides(file, &mut module.grammar)?;
+        let grammar = &module.grammar;
+        if let Some(start) = grammar.starts.iter().find(|s| grammar.production(&s.name).is_none()) {
+            let (line, offset) = (start.line, start.offset);
+            self.fail_at(file, line, offset);
+            return Err(FrontendError::UnknownProduction);
+        }
+        self.loaded.insert((file.to_path_buf(), prefix), module.clone());
+        Ok(module)
+    }
+
+    //Loads the files named by `uses`
+    fn resolve(&mut self, file: &Path, uses: &[UsePath]) -> LoadResult<Vec<Import>> {
+        let mut imports = Vec::new();
+        for path in uses {
+            imports.push(self.import(file, path)?);
+        }
+        Ok(imports)
+    }
+
+    //Loads the grammars named by `extends`
+    fn bases(&mut self, file: &Path, extends: &[UsePath]) -> LoadResult<Vec<(UsePath, Module)>> {
+        let mut bases = Vec::new();
+        for path in extends {
+            let base = match self.locate(&path.segments) {
+                Some((base, None)) => base,
+                _ => {
+                    self.fail_at(file, path.line, path.offset);
+                    return Err(FrontendError::UnresolvedPath);
+                }
+            };
+            if self.loading.contains(&base) {
+                self.fail_at(file, path.line, path.offset);
+                return Err(FrontendError::CyclicUse);
+            }
+            bases.push((path.clone(), self.load_file(&base)?));
+        }
+        Ok(bases)
+    }
+
+    //Puts the rules of the base grammars before the rules of `grammar`,
+    //which has to override the ones it redefines
+    fn inherit(
+        &mut self,
+        file: &Path,
+        grammar: &mut Grammar,
+        bases: Vec<(UsePath, Module)>,
+    ) -> LoadResult<()> {
+        if bases.is_empty() {
+            return Ok(());
+        }
+        let mut inherited = Grammar::default();
+        for (path, base) in bases {
+            let base = base.grammar;
+            if merge(&mut inherited.productions, base.productions).is_some()
+                || merge(&mut inherited.lexical_rules, base.lexical_rules).is_some()
+            {
+                self.fail_at(file, path.line, path.offset);
+                return Err(FrontendError::NameCollision);
+            }
+            for skip in base.skip {
+                if !inherited.skip.contains(&skip) {
+                    inherited.skip.push(skip);
+                }
+            }
+            for start in base.starts {
+                if inherited.starts.iter().all(|s| s.name != start.name) {
+                    inherited.starts.push(start);
+                }
+            }
+            inherited.layout |= base.layout;
+        }
+        let productions = merge(&mut inherited.productions, grammar.productions.drain(..).collect());
+        let lexical_rules = merge(&mut inherited.lexical_rules, grammar.lexical_rules.drain(..).collect());
+        if let Some(rule) = productions.or(lexical_rules) {
+            self.fail_at(file, rule.line, rule.offset);
+            return Err(FrontendError::NameCollision);
+        }
+        grammar.productions = inherited.productions;
+        grammar.lexical_rules = inherited.lexical_rules;
+        inherited.skip.append(&mut grammar.skip);
+        grammar.skip = inherited.skip;
+        for start in grammar.starts.drain(..) {
+            if inherited.starts.iter().all(|s| s.name != start.name) {
+                inherited.starts.push(start);
+            }
+        }
+        grammar.starts = inherited.starts;
+        grammar.layout |= inherited.layout;
+        Ok(())
+    }
+
+    //Replaces or extends the rules named by `override` and `+->`
+    fn apply_overrides(&mut self, file: &Path, grammar: &mut Grammar) -> LoadResult<()> {
+        for change in grammar.overrides.drain(..) {
+            let rules = if change.lexical {
+                &mut grammar.lexical_rules
+            } else {
+                &mut grammar.productions
+            };
+            let rule = match rules.iter_mut().find(|r| r.name == change.rule.name) {
+                Some(rule) => rule,
+                None => {
+                    self.fail_at(file, change.rule.line, change.rule.offset);
+                    return Err(FrontendError::UnknownOverride);
+                }
+            };
+            match change.kind {
+                OverrideKind::Replace => *rule = change.rule,
+                OverrideKind::Append => {
+                    rule.nullable |= change.rule.nullable;
+                    rule.alternatives.extend(change.rule.alternatives);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn import(&mut self, file: &Path, path: &UsePath) -> LoadResult<Import> {
+        let (module, name) = match self.locate(&path.segments) {
+            Some(found) => found,
+            None => {
+                self.fail_at(file, path.line, path.offset);
+                return Err(FrontendError::UnresolvedPath);
+            }
+        };
+        if self.loading.contains(&module) {
+            self.fail_at(file, path.line, path.offset);
+            return Err(FrontendError::CyclicUse);
+        }
+        let module = self.load_file(&module)?;
+        let mut import = Import {
+            path: path.clone(),
+            productions: Vec::new(),
+            lexical_rules: Vec::new(),
+            templates: Vec::new(),
+        };
+        let grammar = module.grammar;
+        let name = match name {
+            Some(name) => name,
+            None => {
+                import.productions = grammar.productions;
+                import.lexical_rules = grammar.lexical_rules;
+                import.templates = module.templates;
+                return Ok(import);
+            }
+        };
+        let root = if grammar.production(name).is_some() {
+            Symbol::Name(name.to_string())
+        } else if grammar.lexical_rule(name).is_some() {
+            Symbol::Lexical(name.to_string())
+        } else if let Some(template) = module.templates.into_iter().find(|t| t.0 == name) {
+            import.templates.push(template);
+            return Ok(import);
+        } else {
+            self.fail_at(file, path.line, path.offset);
+            return Err(FrontendError::UnresolvedPath);
+        };
+        let used = grammar.reachable_from(vec![&root]);
+        import.productions = grammar
+            .productions
+            .into_iter()
+            .filter(|p| used.contains(&Symbol::Name(p.name.clone())))
+            .collect();
+        import.lexical_rules = grammar
+            .lexical_rules
+            .into_iter()
+            .filter(|p| used.contains(&Symbol::Lexical(p.name.clone())))
+            .collect();
+        Ok(import)
+    }
+
+    //The path of the file `segments` names, canonical for files below the
+    //search root, and the rule when the last segment names one inside it
+    fn locate<'s>(&self, segments: &'s [String]) -> Option<(PathBuf, Option<&'s str>)> {
+        if segments.first().map(String::as_str) == Some(STD) {
+            return match segments.len() {
+                2 | 3 if std_source(&std_path(&segments[1])).is_some() => Some((
+                    std_path(&segments[1]),
+                    segments.get(2).map(String::as_str),
+                )),
+                _ => None,
+            };
+        }
+        let file = |segments: &[String]| {
+            let mut file = self.root.clone();
+            file.extend(segments);
+            file.set_extension(EXTENSION);
+            fs::canonicalize(file).ok().filter(|file| file.is_file())
+        };
+        if let Some(module) = file(segments) {
+            return Some((module, None));
+        }
+        match segments.split_last() {
+            Some((name, parent)) if !parent.is_empty() => {
+                file(parent).map(|module| (module, Some(name.as_str())))
+            }
+            _ => None,
+        }
+    }
+
+    //The path of `file` below the search root in the form of a name
+    fn prefix(&self, file: &Path) -> String {
+        let root = fs::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
+        let path = file.strip_prefix(&root).unwrap_or(file).with_extension("");
+        let mut prefix = String::new();
+        for component in path.components() {
+            if let Component::Normal(segment) = component {
+                let mut segment = segment.to_string_lossy().into_owned();
+                segment.retain(|c| c.is_ascii_alphanumeric());
+                let mut chars = segment.chars();
+                if let Some(first) = chars.next() {
+                    prefix.extend(first.to_uppercase());
+                    prefix.extend(chars);
+                }
+            }
+        }
+        prefix
+    }
+
+    fn source(&mut self, file: &Path) -> LoadResult<Vec<char>> {
+        if let Some(source) = std_source(file) {
+            return Ok(source.chars().collect());
+        }
+        if let Some(source) = self.sources.get(file) {
+            return Ok(source.chars().collect());
+        }
+        match fs::read_to_string(file) {
+            Ok(source) => Ok(source.chars().collect()),
+            Err(_) => {
+                self.fail_at(file, 0, 0);
+                Err(FrontendError::UnreadableFile)
+            }
+        }
+    }
+
+    //Lowering errors carry no position, syntax errors are found in the tree
+    fn fail_in<'a>(&mut self, file: &Path, parser: &'a Parser<'a>, tree: &cst::Grammar<'a>) {
+        let error = parser.first_error(tree);
+        let (line, offset) = error.map_or((0, 0), |(_, line, offset)| (line, offset));
+        self.fail_at(file, line, offset);
+    }
+
+    fn fail_at(&mut self, file: &Path, line: usize, offset: usize) {
+        self.file = file.to_path_buf();
+        self.line = line;
+        self.offset = offset;
+    }
+}
+
+fn std_path(module: &str) -> PathBuf {
+    Path::new(STD).join(module).with_extension(EXTENSION)
+}
+
+fn std_source(file: &Path) -> Option<&'static str> {
+    STD_MODULES
+        .iter()
+        .find(|&&(module, _)| std_path(module) == file)
+        .map(|&(_, source)| source)
+}
+
+//Adds the `imported` rules missing from `rules`, returns the first one
+//that differs from the rule of that name already there
+fn merge<T: Named + PartialEq>(rules: &mut Vec<T>, imported: Vec<T>) -> Option<T> {
+    for rule in imported {
+        match rules.iter().find(|r| r.name() == rule.name()) {
+            Some(existing) if *existing == rule => {}
+            Some(_) => return Some(rule),
+            None => rules.push(rule),
+        }
+    }
+    None
+}
+
+trait Named {
+    fn name(&self) -> &str;
+}
+
+impl Named for Production {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Named for (String, PathBuf) {
+    fn name(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,1097 @@
+//Language server for grammar files, run by `gideon lsp`.
+//
+//Messages are JSON-RPC with Content-Length headers on stdin and stdout.
+//Server::handle answers one message, so the server can be driven without
+//the transport too. Supported:
+//
+//  textDocument/didOpen, didChange (incremental), didSave, didClose
+//  textDocument/publishDiagnostics  lexer, parser and analysis findings
+//  textDocument/definition          from a use of a rule to its declaration
+//  textDocument/references
+//  textDocument/rename              across the files connected by `use`
+//  textDocument/hover               nullable, FIRST and FOLLOW of productions
+//  textDocument/completion          production and lexical rule names
+//  textDocument/documentSymbol
+//  textDocument/semanticTokens/full the classes of highlight.rs
+//
+//Like `gideon check`, paths are resolved below the directory of the file.
+//Names are looked up in the file first, then in the files it uses and
+//extends. References are searched in the open documents below that
+//directory and the files they use or extend, directly or not, no other
+//files are read. Open documents are read instead of the files they belong
+//to. The files found and the loaded grammars are kept until a document
+//opens, changes, is saved or closes. Positions count UTF-16 code units, as
+//the protocol requires.
+use super::analysis::{Lookahead, Severity};
+use super::document::Document;
+use super::frontend_error::FrontendError;
+use super::json::{self, object, Json};
+use super::lexer::Lexer;
+use super::grammar::Grammar;
+use super::highlight::{self, TokenClass};
+use super::loader::{Loader, EXTENSION, STD};
+use super::parser::Parser;
+use super::syntax::{self, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+use super::token::Token;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+//Symbol kinds of the protocol
+const MODULE: usize = 2;
+const FUNCTION: usize = 12;
+const CONSTANT: usize = 14;
+const KEY: usize = 20;
+
+//Completion item kinds of the protocol
+const COMPLETE_FUNCTION: usize = 3;
+const COMPLETE_CONSTANT: usize = 21;
+
+//Semantic token types and modifiers of the legend, see semantic_type
+const SEMANTIC_TYPES: &[&str] = &[
+    "function", "type", "parameter", "property", "namespace", "keyword", "string", "enumMember", "regexp",
+    "macro", "operator", "comment",
+];
+const SEMANTIC_MODIFIERS: &[&str] = &["declaration"];
+
+//Reads messages from `input` until `exit` or the end of input, returns
+//whether the client asked to shut down before
+pub fn serve<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<bool> {
+    let mut server = Server::new();
+    while let Some(body) = read_message(&mut input)? {
+        let replies = match json::parse(&body) {
+            Some(message) => server.handle(&message),
+            None => vec![failure(Json::Null, PARSE_ERROR, "invalid JSON")],
+        };
+        for reply in replies {
+            let body = reply.to_string();
+            write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+        }
+        output.flush()?;
+        if server.exited {
+            break;
+        }
+    }
+    Ok(server.shut_down)
+}
+
+//The body of the next message, None at the end of input
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        let mut header = line.splitn(2, ':');
+        let name = header.next().unwrap_or("");
+        if name.eq_ignore_ascii_case("content-length") {
+            length = header.next().and_then(|value| value.trim().parse().ok());
+        }
+    }
+    let mut body = vec![0; length.unwrap_or(0)];
+    input.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+pub struct Server {
+    documents: HashMap<String, Document>,
+    //the files of the workspaces by their root, see workspace
+    files: RefCell<HashMap<PathBuf, Vec<PathBuf>>>,
+    //the grammars of the open documents and the files by their URI
+    grammars: RefCell<HashMap<String, Loaded>>,
+    shut_down: bool,
+    exited: bool,
+}
+
+type Reply = Result<Json, (i64, String)>;
+
+//A loaded grammar or what failed and where, see Loader
+type Loaded = Result<Rc<Grammar>, (FrontendError, PathBuf, usize, usize)>;
+
+impl Server {
+    pub fn new() -> Server {
+        Server {
+            documents: HashMap::new(),
+            files: RefCell::new(HashMap::new()),
+            grammars: RefCell::new(HashMap::new()),
+            shut_down: false,
+            exited: false,
+        }
+    }
+
+    //Whether `exit` was received
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    //The response to `message`, when it is a request, and the
+    //notifications it causes
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let id = message.get("id").clone();
+        let params = message.get("params");
+        let method = match message.get("method").as_str() {
+            Some(method) => method,
+            //responses to requests of the server, it sends none
+            None => return Vec::new(),
+        };
+        if self.shut_down && method != "exit" {
+            return match id {
+                Json::Null => Vec::new(),
+                id => vec![failure(id, INVALID_REQUEST, "the server is shut down")],
+            };
+        }
+        let mut out = Vec::new();
+        let reply = match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Json::Null)
+            }
+            "exit" => {
+                self.exited = true;
+                return out;
+            }
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument");
+                if let (Some(uri), Some(text)) = (document.get("uri").as_str(), document.get("text").as_str()) {
+                    self.documents.insert(uri.to_string(), Document::new(text));
+                }
+                self.invalidate();
+                out.extend(self.diagnostics());
+                return out;
+            }
+            "textDocument/didChange" => {
+                let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+                if let Some(document) = self.documents.get_mut(uri) {
+                    for change in params.get("contentChanges").as_array() {
+                        apply(document, change);
+                    }
+                }
+                self.invalidate();
+                out.extend(self.diagnostics());
+                return out;
+            }
+            "textDocument/didClose" => {
+                let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+                if self.documents.remove(uri).is_some() {
+                    out.push(publish(uri, Vec::new()));
+                }
+                self.invalidate();
+                out.extend(self.diagnostics());
+                return out;
+            }
+            //the files the open documents use may have been saved too
+            "textDocument/didSave" => {
+                self.invalidate();
+                out.extend(self.diagnostics());
+                return out;
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/references" => self.references(params),
+            "textDocument/rename" => self.rename(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/documentSymbol" => self.symbols(params),
+            "textDocument/semanticTokens/full" => self.semantic_tokens(params),
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method {}", method))),
+        };
+        //notifications get no response, not even an error
+        if id != Json::Null {
+            out.push(match reply {
+                Ok(result) => object(vec![("jsonrpc", "2.0".into()), ("id", id), ("result", result)]),
+                Err((code, message)) => failure(id, code, &message),
+            });
+        }
+        out
+    }
+
+    fn definition(&self, params: &Json) -> Reply {
+        let (workspace, offset) = self.locate(params)?;
+        Ok(match workspace.target(0, offset) {
+            Some(Target::Rule(def)) => workspace.location(def.0, def.1),
+            Some(Target::File(file)) => workspace.location(file, (0, 0)),
+            None => Json::Null,
+        })
+    }
+
+    fn references(&self, params: &Json) -> Reply {
+        let (workspace, offset) = self.locate(params)?;
+        let declaration = params.get("context").get("includeDeclaration").as_bool().unwrap_or(true);
+        let occurrences = match workspace.target(0, offset) {
+            Some(Target::Rule(def)) => workspace.occurrences(def, declaration),
+            _ => Vec::new(),
+        };
+        let locations = occurrences.into_iter().map(|(file, range)| workspace.location(file, range));
+        Ok(Json::Array(locations.collect()))
+    }
+
+    fn rename(&self, params: &Json) -> Reply {
+        let (workspace, offset) = self.locate(params)?;
+        let name = params.get("newName").as_str().unwrap_or("");
+        if !is_name(name) {
+            return Err((INVALID_PARAMS, format!("'{}' is not a rule name", name)));
+        }
+        let def = match workspace.target(0, offset) {
+            Some(Target::Rule(def)) => def,
+            _ => return Err((INVALID_PARAMS, "no rule to rename at the position".to_string())),
+        };
+        let mut changes: Vec<(String, Json)> = Vec::new();
+        for (file, range) in workspace.occurrences(def, true) {
+            let source = &workspace.files[file];
+            let edit = object(vec![
+                ("range", lsp_range(&source.text, range)),
+                ("newText", name.into()),
+            ]);
+            match changes.iter_mut().find(|change| change.0 == source.uri) {
+                Some(&mut (_, Json::Array(ref mut edits))) => edits.push(edit),
+                _ => changes.push((source.uri.clone(), Json::Array(vec![edit]))),
+            }
+        }
+        Ok(object(vec![("changes", Json::Object(changes))]))
+    }
+
+    fn hover(&self, params: &Json) -> Reply {
+        let (workspace, offset) = self.locate(params)?;
+        let token = match workspace.name_at(0, offset) {
+            Some(token) => token,
+            None => return Ok(Json::Null),
+        };
+        let (file, range) = match workspace.target(0, offset) {
+            Some(Target::Rule(def)) => def,
+            _ => return Ok(Json::Null),
+        };
+        let def = match workspace.indexes[file].defs.iter().find(|d| d.range == range) {
+            Some(def) => def,
+            None => return Ok(Json::Null),
+        };
+        let mut text = if def.lexical {
+            format!("{{{}}}\n  lexical rule\n", def.text)
+        } else {
+            format!("{}\n", def.text)
+        };
+        if !def.lexical {
+            if let Ok(grammar) = self.load(&workspace) {
+                if grammar.production(&def.text).is_some() {
+                    let analysis = grammar.analyze();
+                    let set = |set: Vec<&Lookahead>| {
+                        set.iter().map(|l| l.to_string()).collect::<Vec<String>>().join(" ")
+                    };
+                    let nullable = if analysis.nullable(&def.text) { "yes" } else { "no" };
+                    text.push_str(&format!("  nullable: {}\n", nullable));
+                    text.push_str(&format!("  first:    {}\n", set(analysis.first(&def.text))));
+                    text.push_str(&format!("  follow:   {}\n", set(analysis.follow(&def.text))));
+                }
+            }
+        }
+        let contents = object(vec![
+            ("kind", "markdown".into()),
+            ("value", format!("```text\n{}```", text).into()),
+        ]);
+        Ok(object(vec![
+            ("contents", contents),
+            ("range", lsp_range(&workspace.files[0].text, token.range())),
+        ]))
+    }
+
+    fn completion(&self, params: &Json) -> Reply {
+        let (workspace, offset) = self.locate(params)?;
+        //inside the braces of a lexical rule only lexical rules fit
+        let tokens = workspace.files[0].tree.tokens();
+        let before = tokens
+            .iter()
+            .rev()
+            .filter(|token| !token.kind().is_trivia() && token.range().1 <= offset)
+            .find(|token| !(token.kind() == SyntaxKind::Name && token.range().1 == offset));
+        let braced = before.map(|token| token.kind() == SyntaxKind::OpenBrace).unwrap_or(false);
+        let mut names: Vec<(String, bool)> = Vec::new();
+        for file in workspace.visible(0) {
+            for def in &workspace.indexes[file].defs {
+                names.push((def.text.clone(), def.lexical));
+            }
+        }
+        if let Ok(grammar) = self.load(&workspace) {
+            names.extend(grammar.productions.iter().map(|p| (p.name.clone(), false)));
+            names.extend(grammar.lexical_rules.iter().map(|p| (p.name.clone(), true)));
+        }
+        names.sort();
+        names.dedup();
+        let items = names
+            .into_iter()
+            .filter(|&(_, lexical)| lexical || !braced)
+            .map(|(name, lexical)| {
+                let (label, kind, detail) = match (lexical, braced) {
+                    (false, _) => (name, COMPLETE_FUNCTION, "production"),
+                    (true, true) => (name, COMPLETE_CONSTANT, "lexical rule"),
+                    (true, false) => (format!("{{{}}}", name), COMPLETE_CONSTANT, "lexical rule"),
+                };
+                object(vec![("label", label.into()), ("kind", kind.into()), ("detail", detail.into())])
+            });
+        Ok(Json::Array(items.collect()))
+    }
+
+    fn symbols(&self, params: &Json) -> Reply {
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+        let source = self.source(uri).ok_or_else(|| unknown(uri))?;
+        let mut symbols = Vec::new();
+        for declaration in source.tree.child_nodes() {
+            let tokens: Vec<SyntaxToken> = declaration
+                .tokens()
+                .into_iter()
+                .filter(|token| !token.kind().is_trivia())
+                .collect();
+            let first = match tokens.first() {
+                Some(first) => first,
+                None => continue,
+            };
+            let prod = match declaration.kind() {
+                SyntaxKind::Override => declaration.child_nodes().into_iter().next(),
+                SyntaxKind::Prod => Some(declaration.clone()),
+                _ => None,
+            };
+            let (name, kind, selection) = match prod.as_ref().and_then(head) {
+                Some(name) => {
+                    let kind = if name.lexical { CONSTANT } else { FUNCTION };
+                    let text = if name.lexical { format!("{{{}}}", name.text) } else { name.text };
+                    (text, kind, name.range)
+                }
+                None => {
+                    let words: Vec<&str> = tokens
+                        .iter()
+                        .take_while(|token| token.kind() != SyntaxKind::Endl)
+                        .map(SyntaxToken::text)
+                        .collect();
+                    let kind = match declaration.kind() {
+                        SyntaxKind::Path | SyntaxKind::Extends => MODULE,
+                        _ => KEY,
+                    };
+                    (words.join(" ").replace(" :: ", "::"), kind, first.range())
+                }
+            };
+            let detail = match declaration.kind() {
+                SyntaxKind::Override => "override",
+                _ if prod.map(|p| appends(&p)).unwrap_or(false) => "appended alternatives",
+                _ => "",
+            };
+            let range = (first.range().0, declaration.range().1);
+            symbols.push(object(vec![
+                ("name", name.into()),
+                ("detail", detail.into()),
+                ("kind", kind.into()),
+                ("range", lsp_range(&source.text, range)),
+                ("selectionRange", lsp_range(&source.text, selection)),
+            ]));
+        }
+        Ok(Json::Array(symbols))
+    }
+
+    fn semantic_tokens(&self, params: &Json) -> Reply {
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+        let source = self.source(uri).ok_or_else(|| unknown(uri))?;
+        let mut data = Vec::new();
+        //the line and UTF-16 character of the text read and of the last
+        //token sent, tokens spanning lines are sent a line at a time
+        let mut position = (0, 0);
+        let mut last = (0, 0);
+        let mut send = |start: (usize, usize), end: (usize, usize), legend: Option<(usize, usize)>| {
+            if let Some((kind, modifiers)) = legend {
+                if end.1 > start.1 {
+                    let character = if start.0 == last.0 { start.1 - last.1 } else { start.1 };
+                    let token = [start.0 - last.0, character, end.1 - start.1, kind, modifiers];
+                    data.extend(token.iter().map(|n| Json::from(*n)));
+                    last = start;
+                }
+            }
+        };
+        for (token, class) in highlight::classify(&source.tree) {
+            let legend = semantic_type(class);
+            let mut start = position;
+            for c in token.text().chars() {
+                if c == '\n' {
+                    send(start, position, legend);
+                    position = (position.0 + 1, 0);
+                    start = position;
+                } else {
+                    position.1 += c.len_utf16();
+                }
+            }
+            send(start, position, legend);
+        }
+        Ok(object(vec![("data", Json::Array(data))]))
+    }
+
+    //The workspace of the document in `params` and the offset of its
+    //position
+    fn locate(&self, params: &Json) -> Result<(Workspace, usize), (i64, String)> {
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+        let source = self.source(uri).ok_or_else(|| unknown(uri))?;
+        let position = params.get("position");
+        let line = position.get("line").as_usize().unwrap_or(0);
+        let character = position.get("character").as_usize().unwrap_or(0);
+        let offset = offset_at(&source.text, line, character);
+        Ok((self.workspace(source), offset))
+    }
+
+    //The open document or the file of `uri`
+    fn source(&self, uri: &str) -> Option<Source> {
+        let text = match self.documents.get(uri) {
+            Some(document) => document.text(),
+            None => fs::read_to_string(uri_path(uri)?).ok()?,
+        };
+        Some(Source::new(uri.to_string(), &text))
+    }
+
+    //`source` followed by the open documents below its directory and the
+    //files they use or extend
+    fn workspace(&self, source: Source) -> Workspace {
+        let root = source.path.as_ref().and_then(|p| p.parent()).map(Path::to_path_buf).unwrap_or_default();
+        let cached = self.files.borrow().get(&root).cloned();
+        let paths = match cached {
+            Some(paths) => paths,
+            None => {
+                let paths = self.reachable(&root);
+                self.files.borrow_mut().insert(root.clone(), paths.clone());
+                paths
+            }
+        };
+        let mut files = vec![source];
+        for path in paths {
+            if files.iter().all(|f| f.path.as_ref() != Some(&path)) {
+                if let Some(source) = self.source(&path_uri(&path)) {
+                    files.push(source);
+                }
+            }
+        }
+        let indexes = files.iter().map(|file| Index::new(&file.tree)).collect();
+        Workspace { root, files, indexes }
+    }
+
+    //The open documents below `root` and the files their paths name,
+    //directly or not
+    fn reachable(&self, root: &Path) -> Vec<PathBuf> {
+        let mut uris: Vec<&String> = self.documents.keys().collect();
+        uris.sort();
+        let mut paths: Vec<PathBuf> = uris
+            .into_iter()
+            .filter_map(|uri| uri_path(uri))
+            .map(|path| fs::canonicalize(&path).unwrap_or(path))
+            .filter(|path| path.starts_with(root))
+            .collect();
+        let mut i = 0;
+        while i < paths.len() {
+            let source = self.source(&path_uri(&paths[i]));
+            for path in source.iter().flat_map(|source| Index::new(&source.tree).paths) {
+                let segments: Vec<&str> = path.iter().map(|segment| segment.text.as_str()).collect();
+                if let Some(file) = locate(root, &segments) {
+                    if !paths.contains(&file) {
+                        paths.push(file);
+                    }
+                }
+            }
+            i += 1;
+        }
+        paths
+    }
+
+    //The grammar of the first file of the workspace with everything it
+    //uses, or where loading failed
+    fn load(&self, workspace: &Workspace) -> Loaded {
+        let source = &workspace.files[0];
+        if let Some(loaded) = self.grammars.borrow().get(&source.uri) {
+            return loaded.clone();
+        }
+        let mut loader = Loader::new(&workspace.root);
+        for (uri, document) in &self.documents {
+            if let Some(path) = uri_path(uri) {
+                loader.overlay(path, &document.text());
+            }
+        }
+        let path = source.path.clone().unwrap_or_else(|| PathBuf::from(&source.uri));
+        let loaded = match loader.load_source(path, &source.text.iter().collect::<String>()) {
+            Ok(grammar) => Ok(Rc::new(grammar)),
+            Err(what) => Err((what, loader.file().to_path_buf(), loader.line(), loader.offset())),
+        };
+        self.grammars.borrow_mut().insert(source.uri.clone(), loaded.clone());
+        loaded
+    }
+
+    fn invalidate(&self) {
+        self.files.borrow_mut().clear();
+        self.grammars.borrow_mut().clear();
+    }
+
+    //Diagnostics of every open document
+    fn diagnostics(&self) -> Vec<Json> {
+        let mut uris: Vec<&String> = self.documents.keys().collect();
+        uris.sort();
+        let mut out = Vec::new();
+        for uri in uris {
+            if let Some(source) = self.source(uri) {
+                let workspace = self.workspace(source);
+                out.push(publish(uri, self.check(&workspace, &self.documents[uri])));
+            }
+        }
+        out
+    }
+
+    fn check(&self, workspace: &Workspace, document: &Document) -> Vec<Json> {
+        let text = document.chars();
+        let mut found = Vec::new();
+        let lexical: Vec<(FrontendError, (usize, usize))> = document
+            .tokens()
+            .iter()
+            .filter_map(|token| token.error.map(|what| (what, (token.start, token.end))))
+            .collect();
+        for &(what, range) in &lexical {
+            found.push(diagnostic(text, range, Severity::Error, what.message()));
+        }
+        let parser = Parser::new(text);
+        let syntax = match parser.parse() {
+            Ok(ref tree) => parser.first_error(tree),
+            Err(what) => Some((what, parser.line(), parser.offset())),
+        };
+        //errors the parser ran into after the lexer failed follow from that
+        if let Some((what, line, offset)) = syntax {
+            let start = offset_of(text, line, offset);
+            if lexical.iter().all(|&(error, range)| error != what && start < range.0) {
+                let range = (start, (start + 1).min(text.len()));
+                found.push(diagnostic(text, range, Severity::Error, what.message()));
+            }
+        }
+        //the parser stops quietly at input that cannot start a declaration,
+        //everything after is left over
+        let junk: Vec<(usize, usize)> = document
+            .tree()
+            .children()
+            .into_iter()
+            .filter(|child| child.kind() == SyntaxKind::Error)
+            .map(|child| child.range())
+            .filter(|&(start, end)| {
+                start < end && lexical.iter().all(|&(_, range)| end <= range.0 || range.1 <= start)
+            })
+            .collect();
+        if let (true, Some(first), Some(last)) = (syntax.is_none(), junk.first(), junk.last()) {
+            let message = FrontendError::ExpectedProdStartOrUse.message();
+            found.push(diagnostic(text, (first.0, last.1), Severity::Error, message));
+        }
+        if !found.is_empty() {
+            return found;
+        }
+
+        match self.load(workspace) {
+            Ok(grammar) => {
+                let index = &workspace.indexes[0];
+                let analysis = grammar.analyze();
+                for finding in analysis.diagnostics() {
+                    //rules of other files are reported in those
+                    let declared = index.defs.iter().chain(&index.heads).any(|d| d.text == finding.rule);
+                    if !finding.rule.is_empty() && !declared {
+                        continue;
+                    }
+                    let start = offset_of(text, finding.line, finding.offset);
+                    let range = document
+                        .tree()
+                        .token_at(start)
+                        .filter(|token| token.range().0 == start)
+                        .map(|token| token.range())
+                        .unwrap_or((start, start));
+                    let message = format!("{}: {}", finding.error.message(), finding.name);
+                    found.push(diagnostic(text, range, finding.severity, &message));
+                }
+            }
+            //failures in other files are reported at the start of this one
+            Err((what, ref failed, line, offset)) => {
+                let here = workspace.files[0].path.as_ref().map(|path| path == failed).unwrap_or(true);
+                let (message, start) = if here {
+                    (what.message().to_string(), offset_of(text, line, offset))
+                } else {
+                    let file = failed.strip_prefix(&workspace.root).unwrap_or(failed);
+                    let location = format!("{}:{}:{}", file.display(), line, offset);
+                    (format!("{}: {}", location, what.message()), 0)
+                };
+                found.push(diagnostic(text, (start, start), Severity::Error, &message));
+            }
+        }
+        found
+    }
+}
+
+impl Default for Server {
+    fn default() -> Server {
+        Server::new()
+    }
+}
+
+fn capabilities() -> Json {
+    let sync = object(vec![("openClose", true.into()), ("change", 2.into()), ("save", true.into())]);
+    let completion = object(vec![("triggerCharacters", Json::Array(vec!["{".into()]))]);
+    let names = |names: &[&str]| Json::Array(names.iter().map(|name| Json::from(*name)).collect());
+    let legend = object(vec![
+        ("tokenTypes", names(SEMANTIC_TYPES)),
+        ("tokenModifiers", names(SEMANTIC_MODIFIERS)),
+    ]);
+    let semantic = object(vec![("legend", legend), ("full", true.into())]);
+    object(vec![
+        (
+            "capabilities",
+            object(vec![
+                ("textDocumentSync", sync),
+                ("definitionProvider", true.into()),
+                ("referencesProvider", true.into()),
+                ("renameProvider", true.into()),
+                ("hoverProvider", true.into()),
+                ("completionProvider", completion),
+                ("documentSymbolProvider", true.into()),
+                ("semanticTokensProvider", semantic),
+            ]),
+        ),
+        ("serverInfo", object(vec![("name", "gideon".into())])),
+    ])
+}
+
+//The index of the type of `class` in SEMANTIC_TYPES and its modifier bits,
+//None for the classes left to the client
+fn semantic_type(class: TokenClass) -> Option<(usize, usize)> {
+    let kind = match class {
+        TokenClass::Definition => return Some((0, 1)),
+        TokenClass::Production => 0,
+        TokenClass::Lexical => 1,
+        TokenClass::Parameter => 2,
+        TokenClass::Label => 3,
+        TokenClass::Mode | TokenClass::Path => 4,
+        TokenClass::Keyword => 5,
+        TokenClass::Literal => 6,
+        TokenClass::Epsilon => 7,
+        TokenClass::CharacterClass => 8,
+        TokenClass::Code => 9,
+        TokenClass::Operator => 10,
+        TokenClass::Comment => 11,
+        TokenClass::Whitespace | TokenClass::Error => return None,
+    };
+    Some((kind, 0))
+}
+
+fn failure(id: Json, code: i64, message: &str) -> Json {
+    let error = object(vec![("code", Json::Number(code as f64)), ("message", message.into())]);
+    object(vec![("jsonrpc", "2.0".into()), ("id", id), ("error", error)])
+}
+
+fn unknown(uri: &str) -> (i64, String) {
+    (INVALID_PARAMS, format!("unknown document {}", uri))
+}
+
+fn publish(uri: &str, diagnostics: Vec<Json>) -> Json {
+    let params = object(vec![("uri", uri.into()), ("diagnostics", Json::Array(diagnostics))]);
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        ("params", params),
+    ])
+}
+
+fn diagnostic(text: &[char], range: (usize, usize), severity: Severity, message: &str) -> Json {
+    let severity = match severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+    };
+    object(vec![
+        ("range", lsp_range(text, range)),
+        ("severity", severity.into()),
+        ("source", "gideon".into()),
+        ("message", message.into()),
+    ])
+}
+
+//Applies a change of didChange, without a range it replaces the text
+fn apply(document: &mut Document, change: &Json) {
+    let text = change.get("text").as_str().unwrap_or("");
+    let range = change.get("range");
+    if range.is_null() {
+        *document = Document::new(text);
+        return;
+    }
+    let offset = |position: &Json| {
+        let line = position.get("line").as_usize().unwrap_or(0);
+        let character = position.get("character").as_usize().unwrap_or(0);
+        offset_at(document.chars(), line, character)
+    };
+    let (start, end) = (offset(range.get("start")), offset(range.get("end")));
+    document.edit(start, end, text);
+}
+
+//A grammar file of the workspace
+struct Source {
+    uri: String,
+    path: Option<PathBuf>,
+    text: Vec<char>,
+    tree: SyntaxNode,
+}
+
+impl Source {
+    fn new(uri: String, text: &str) -> Source {
+        let text: Vec<char> = text.chars().collect();
+        let path = uri_path(&uri).map(|path| fs::canonicalize(&path).unwrap_or(path));
+        let tree = syntax::parse(&text);
+        Source { uri, path, text, tree }
+    }
+}
+
+//A rule name in a file and whether it names a lexical rule
+#[derive(Clone)]
+struct Name {
+    text: String,
+    lexical: bool,
+    range: (usize, usize),
+}
+
+//The names of a file
+struct Index {
+    //rules declared in the file
+    defs: Vec<Name>,
+    //rules the file overrides or adds alternatives to
+    heads: Vec<Name>,
+    //uses of rules
+    refs: Vec<Name>,
+    //paths of `use` and `extends`
+    paths: Vec<Vec<Name>>,
+}
+
+impl Index {
+    fn new(tree: &SyntaxNode) -> Index {
+        let mut index = Index {
+            defs: Vec::new(),
+            heads: Vec::new(),
+            refs: Vec::new(),
+            paths: Vec::new(),
+        };
+        //the last token that is not trivia and the node of the last path
+        let mut previous: Option<(SyntaxToken, TokenClass)> = None;
+        let mut path = None;
+        for (token, class) in highlight::classify(tree) {
+            if token.kind() == SyntaxKind::Name {
+                match class {
+                    TokenClass::Definition => {
+                        let lexical = match previous {
+                            Some((ref open, TokenClass::Definition)) => open.kind() == SyntaxKind::OpenBrace,
+                            _ => false,
+                        };
+                        index.defs.push(name(&token, lexical));
+                    }
+                    TokenClass::Production | TokenClass::Lexical => {
+                        let used = name(&token, class == TokenClass::Lexical);
+                        if token.parent().kind() == SyntaxKind::Prod {
+                            index.heads.push(used);
+                        } else {
+                            index.refs.push(used);
+                        }
+                    }
+                    TokenClass::Path => {
+                        let node = token.parent().range();
+                        if path != Some(node) {
+                            path = Some(node);
+                            index.paths.push(Vec::new());
+                        }
+                        if let Some(segments) = index.paths.last_mut() {
+                            segments.push(name(&token, false));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if !token.kind().is_trivia() {
+                previous = Some((token, class));
+            }
+        }
+        index
+    }
+}
+
+//What a name in a file stands for
+enum Target {
+    //the file and range of the declaration of a rule
+    Rule((usize, (usize, usize))),
+    //a file named by a path
+    File(usize),
+}
+
+struct Workspace {
+    root: PathBuf,
+    files: Vec<Source>,
+    indexes: Vec<Index>,
+}
+
+impl Workspace {
+    //The name token at `offset` in `file`, also when `offset` is right
+    //after it
+    fn name_at(&self, file: usize, offset: usize) -> Option<SyntaxToken> {
+        let tree = &self.files[file].tree;
+        let found = tree.token_at(offset).filter(|token| token.kind() == SyntaxKind::Name);
+        found.or_else(|| {
+            let before = offset.checked_sub(1)?;
+            tree.token_at(before).filter(|token| token.kind() == SyntaxKind::Name)
+        })
+    }
+
+    fn target(&self, file: usize, offset: usize) -> Option<Target> {
+        let range = self.name_at(file, offset)?.range();
+        let index = &self.indexes[file];
+        if index.defs.iter().any(|d| d.range == range) {
+            return Some(Target::Rule((file, range)));
+        }
+        let used = index.heads.iter().chain(&index.refs).find(|r| r.range == range);
+        if let Some(used) = used {
+            return self.resolve(file, &used.text, used.lexical).map(Target::Rule);
+        }
+        for path in &index.paths {
+            if let Some(i) = path.iter().position(|segment| segment.range == range) {
+                return match self.module(&path[..i + 1]) {
+                    Some((module, None)) => Some(Target::File(module)),
+                    Some((module, Some(rule))) => self
+                        .resolve(module, rule, false)
+                        .or_else(|| self.resolve(module, rule, true))
+                        .map(Target::Rule),
+                    None => None,
+                };
+            }
+        }
+        None
+    }
+
+    //The declaration a use of `name` in `file` refers to
+    fn resolve(&self, file: usize, name: &str, lexical: bool) -> Option<(usize, (usize, usize))> {
+        for file in self.visible(file) {
+            let def = self.indexes[file].defs.iter().find(|d| d.text == name && d.lexical == lexical);
+            if let Some(def) = def {
+                return Some((file, def.range));
+            }
+        }
+        None
+    }
+
+    //`file` and the files it uses or extends, directly or not, in the order
+    //names are looked up in
+    fn visible(&self, file: usize) -> Vec<usize> {
+        let mut visible = vec![file];
+        let mut i = 0;
+        while i < visible.len() {
+            for path in &self.indexes[visible[i]].paths {
+                if let Some((module, _)) = self.module(path) {
+                    if !visible.contains(&module) {
+                        visible.push(module);
+                    }
+                }
+            }
+            i += 1;
+        }
+        visible
+    }
+
+    //The file a path names and the rule when its last segment names one in
+    //that file, see loader.rs
+    fn module<'n>(&self, path: &'n [Name]) -> Option<(usize, Option<&'n str>)> {
+        let segments: Vec<&str> = path.iter().map(|segment| segment.text.as_str()).collect();
+        if segments.first() == Some(&STD) {
+            return None;
+        }
+        let file = locate(&self.root, &segments)?;
+        let module = self.files.iter().position(|source| source.path.as_ref() == Some(&file))?;
+        let whole = module_path(&self.root, &segments) == file;
+        Some((module, if whole { None } else { path.last().map(|rule| rule.text.as_str()) }))
+    }
+
+    //Every name referring to the rule declared at `def`
+    fn occurrences(&self, def: (usize, (usize, usize)), declaration: bool) -> Vec<(usize, (usize, usize))> {
+        let mut out = Vec::new();
+        if declaration {
+            out.push(def);
+        }
+        for (file, index) in self.indexes.iter().enumerate() {
+            for used in index.heads.iter().chain(&index.refs) {
+                if self.resolve(file, &used.text, used.lexical) == Some(def) {
+                    out.push((file, used.range));
+                }
+            }
+            for path in &index.paths {
+                if let Some((module, Some(rule))) = self.module(path) {
+                    let found = self.resolve(module, rule, false).or_else(|| self.resolve(module, rule, true));
+                    if found == Some(def) {
+                        out.push((file, path[path.len() - 1].range));
+                    }
+                }
+            }
+        }
+        out.sort();
+        out.dedup();
+        out
+    }
+
+    fn location(&self, file: usize, range: (usize, usize)) -> Json {
+        let source = &self.files[file];
+        object(vec![("uri", source.uri.as_str().into()), ("range", lsp_range(&source.text, range))])
+    }
+}
+
+//The file below `root` a path of `use` or `extends` names, the file of all
+//segments or the one without the last, see loader.rs
+fn locate(root: &Path, segments: &[&str]) -> Option<PathBuf> {
+    if segments.first() == Some(&STD) {
+        return None;
+    }
+    let file = |segments: &[&str]| {
+        let file = module_path(root, segments);
+        if file.is_file() { Some(file) } else { None }
+    };
+    match file(segments) {
+        Some(file) => Some(file),
+        None if segments.len() > 1 => file(&segments[..segments.len() - 1]),
+        None => None,
+    }
+}
+
+fn module_path(root: &Path, segments: &[&str]) -> PathBuf {
+    let mut file = root.to_path_buf();
+    file.extend(segments);
+    file.set_extension(EXTENSION);
+    fs::canonicalize(&file).unwrap_or(file)
+}
+
+//The name a production declares, its first name
+fn head(prod: &SyntaxNode) -> Option<Name> {
+    let tokens = direct_tokens(prod);
+    let braced = tokens.first().map(SyntaxToken::kind) == Some(SyntaxKind::OpenBrace);
+    tokens
+        .iter()
+        .find(|token| token.kind() == SyntaxKind::Name)
+        .map(|token| name(token, braced))
+}
+
+//Whether the production adds alternatives with '+->'
+fn appends(prod: &SyntaxNode) -> bool {
+    direct_tokens(prod).iter().any(|token| token.kind() == SyntaxKind::AppendArrow)
+}
+
+//The tokens right below `node` that are not trivia
+fn direct_tokens(node: &SyntaxNode) -> Vec<SyntaxToken> {
+    node.children()
+        .into_iter()
+        .filter_map(|child| match child {
+            SyntaxElement::Token(ref token) if !token.kind().is_trivia() => Some(token.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn name(token: &SyntaxToken, lexical: bool) -> Name {
+    Name {
+        text: token.text().to_string(),
+        lexical,
+        range: token.range(),
+    }
+}
+
+//Whether `text` lexes as a single name, keywords do not
+fn is_name(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let lexer = Lexer::new(&chars);
+    match lexer.next() {
+        Ok(Token::Name(_)) => matches!(lexer.next(), Err(FrontendError::EOI)),
+        _ => false,
+    }
+}
+
+//The index of `line` and `character` of the protocol in `text`, lines
+//count from 0 and characters in UTF-16 code units
+fn offset_at(text: &[char], line: usize, character: usize) -> usize {
+    let mut offset = 0;
+    let mut current = 0;
+    while current < line && offset < text.len() {
+        if text[offset] == '\n' {
+            current += 1;
+        }
+        offset += 1;
+    }
+    let mut units = 0;
+    while offset < text.len() && text[offset] != '\n' && units < character {
+        units += text[offset].len_utf16();
+        offset += 1;
+    }
+    offset
+}
+
+//The index of a position of the front end, lines count from 1 and
+//offsets in characters
+fn offset_of(text: &[char], line: usize, offset: usize) -> usize {
+    let mut start = 0;
+    let mut current = 1;
+    while current < line && start < text.len() {
+        if text[start] == '\n' {
+            current += 1;
+        }
+        start += 1;
+    }
+    (start + offset).min(text.len())
+}
+
+fn lsp_position(text: &[char], offset: usize) -> Json {
+    let before = &text[..offset.min(text.len())];
+    let line = before.iter().filter(|c| **c == '\n').count();
+    let start = before.iter().rposition(|c| *c == '\n').map(|i| i + 1).unwrap_or(0);
+    let character: usize = before[start..].iter().map(|c| c.len_utf16()).sum();
+    object(vec![("line", line.into()), ("character", character.into())])
+}
+
+fn lsp_range(text: &[char], range: (usize, usize)) -> Json {
+    object(vec![("start", lsp_position(text, range.0)), ("end", lsp_position(text, range.1))])
+}
+
+//The path of a file URI
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| ::std::str::from_utf8(hex).ok());
+        match hex.map(|hex| u8::from_str_radix(hex, 16)) {
+            Some(Ok(byte)) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+fn path_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
@@ -3,3 +3,25 @@ pub mod frontend_error;
 pub mod lexer;
 pub mod parser;
 pub mod syntax_tree;
+pub mod char_class;
+pub mod unicode;
+pub mod grammar;
+pub mod scanner;
+pub mod interpreter;
+pub mod desugar;
+pub mod loader;
+pub mod analysis;
+pub mod backend;
+pub mod notation;
+pub mod grammophone;
+pub mod bison;
+pub mod antlr;
+pub mod pest;
+pub mod import;
+pub mod visitor;
+pub mod formatter;
+pub mod syntax;
+pub mod document;
+pub mod json;
+pub mod highlight;
+pub mod lsp;
@@ -0,0 +1,765 @@
+//Standard grammar notations for specification documents, see backend.rs.
+//
+//  w3c:  the EBNF of the XML specification    A ::= B C? | "x"
+//  iso:  ISO/IEC 14977 EBNF                   A = B, [ C ] | "x" ;
+//  abnf: RFC 5234 ABNF                        A = B [ C ] / %s"x"
+//
+//Productions come first, then the lexical rules as rules of their own. A
+//lexical rule whose name a production has gets a `_TOKEN` suffix. A
+//nullable rule `A? -> B | C` has its alternatives made optional. Literals
+//are case sensitive in all three, ABNF strings are not, so literals with
+//letters use %s from RFC 7405.
+//
+//Constructs with no equivalent are kept as their Gideon text in a special
+//sequence (ISO) or prose (ABNF). W3C has neither, there they are rules of
+//their own, UNSUPPORTED1 and so on, that match no input and have the Gideon
+//text in a comment. A note ahead of the rules says so. Notes also list
+//what is left out: actions, labels, result types and lexer modes.
+//
+//import reads the W3C notation, rule numbers like [1] included. The first
+//rule is the entry point. Rules named in capitals and the rules they use
+//become lexical rules, the others productions. `A - B` becomes `!B A`,
+//which is exact when both match single characters, and constraints like
+//[ wfc: Name ] are left out, both with a warning. The range #x0-#x10FFFF
+//is \p{Any}, as it is exported.
+use super::backend;
+use super::char_class::{CharClass, ClassItem};
+use super::frontend_error::FrontendError;
+use super::grammar::*;
+use super::import::*;
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    W3c,
+    Iso,
+    Abnf,
+}
+
+//ISO classes with up to this many characters are written as alternatives
+//of literals
+const ISO_CLASS_LIMIT: u32 = 64;
+
+
+pub fn export(grammar: &Grammar, notation: Notation) -> String {
+    let mut exporter = Exporter::new(grammar, notation);
+    let mut rules = String::new();
+    for prod in &grammar.productions {
+        rules.push_str(&exporter.rule(prod, false));
+    }
+    for rule in &grammar.lexical_rules {
+        rules.push_str(&exporter.rule(rule, true));
+    }
+    if !grammar.skip.is_empty() {
+        let name = exporter.unique("Skip");
+        exporter.added.push(name.clone());
+        let alternatives = grammar.skip.iter().flat_map(|skip| skip.alternatives.iter().cloned());
+        let skip = Production {
+            name: name.clone(),
+            nullable: false,
+            result_type: None,
+            alternatives: alternatives.collect(),
+            modes: Vec::new(),
+            action: None,
+            helper: None,
+            line: 0,
+            offset: 0,
+        };
+        if grammar.skip.iter().any(|skip| !skip.modes.is_empty()) {
+            exporter.note(backend::MODES_LEFT_OUT.to_string());
+        }
+        exporter.note(format!("{} is skipped between tokens, no rule refers to it", name));
+        rules.push_str(&exporter.rule(&skip, true));
+    }
+    for (name, text) in &exporter.placeholders {
+        rules.push_str(&format!("{} ::= [^#x0-#x10FFFF] /* {} */\n", name, text.replace("*/", "* /")));
+    }
+    if grammar.layout {
+        exporter.note(format!("{}, {} and {} are made from the indentation", INDENT, DEDENT, NEWLINE));
+    }
+    let mut out = String::new();
+    let starts = grammar.entry_points();
+    if !starts.is_empty() {
+        out.push_str(&exporter.comment(&format!("start: {}", starts.join(", "))));
+    }
+    for note in &exporter.notes {
+        out.push_str(&exporter.comment(note));
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&rules);
+    out
+}
+
+struct Exporter {
+    notation: Notation,
+    //output names of the productions and lexical rules, by whether the rule
+    //is lexical and its name
+    names: HashMap<(bool, String), String>,
+    //names of the rules the exporter adds, the skip rule and the
+    //placeholders of unsupported constructs with their Gideon text
+    added: Vec<String>,
+    placeholders: Vec<(String, String)>,
+    notes: Vec<String>,
+}
+
+impl Exporter {
+    fn new(grammar: &Grammar, notation: Notation) -> Exporter {
+        let mut exporter = Exporter {
+            notation,
+            names: HashMap::new(),
+            added: Vec::new(),
+            placeholders: Vec::new(),
+            notes: Vec::new(),
+        };
+        for prod in &grammar.productions {
+            let name = exporter.unique(&prod.name);
+            exporter.names.insert((false, prod.name.clone()), name);
+        }
+        for rule in &grammar.lexical_rules {
+            let name = exporter.unique(&rule.name);
+            if name != exporter.converted(&rule.name) {
+                exporter.note(format!("{{{}}} is written as {}", rule.name, name));
+            }
+            exporter.names.insert((true, rule.name.clone()), name);
+        }
+        exporter
+    }
+
+    fn note(&mut self, note: String) {
+        if !self.notes.contains(&note) {
+            self.notes.push(note);
+        }
+    }
+
+    //`name` as the notation allows it, ABNF has no '_' in names
+    fn converted(&self, name: &str) -> String {
+        match self.notation {
+            Notation::Abnf => {
+                let name = name.replace('_', "-");
+                if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                    name
+                } else {
+                    format!("R{}", name)
+                }
+            }
+            _ => name.to_string(),
+        }
+    }
+
+    //`name` or, when another rule has it, `name` with a suffix
+    fn unique(&self, name: &str) -> String {
+        let base = self.converted(name);
+        let suffix = if self.notation == Notation::Abnf { "-TOKEN" } else { "_TOKEN" };
+        let mut out = base.clone();
+        let mut n = 1;
+        while self.taken(&out) {
+            out = if n == 1 {
+                format!("{}{}", base, suffix)
+            } else {
+                format!("{}{}{}", base, suffix, n)
+            };
+            n += 1;
+        }
+        out
+    }
+
+    //ABNF names are case insensitive
+    fn taken(&self, name: &str) -> bool {
+        self.names.values().chain(self.added.iter()).any(|taken| match self.notation {
+            Notation::Abnf => taken.eq_ignore_ascii_case(name),
+            _ => taken == name,
+        })
+    }
+
+    fn name(&self, lexical: bool, name: &str) -> String {
+        match self.names.get(&(lexical, name.to_string())) {
+            Some(name) => name.clone(),
+            //virtual rules of layout mode
+            None => self.converted(name),
+        }
+    }
+
+    fn comment(&self, text: &str) -> String {
+        match self.notation {
+            Notation::W3c => format!("/* {} */\n", text),
+            Notation::Iso => format!("(* {} *)\n", text),
+            Notation::Abnf => format!("; {}\n", text),
+        }
+    }
+
+    //A construct with no equivalent, kept as its Gideon text
+    fn unsupported(&mut self, what: &str, text: &str) -> String {
+        let (kept, out) = match self.notation {
+            Notation::W3c => ("rules that match nothing", self.placeholder(text)),
+            Notation::Iso => ("a special sequence", format!("? {} ?", text.replace('?', "(?)"))),
+            Notation::Abnf => ("prose", format!("<{}>", text.replace('>', ""))),
+        };
+        self.note(format!("{} have no equivalent and are kept as {}", what, kept));
+        out
+    }
+
+    //The name of a W3C rule standing for `text`, one per distinct text
+    fn placeholder(&mut self, text: &str) -> String {
+        if let Some((name, _)) = self.placeholders.iter().find(|(_, kept)| kept == text) {
+            return name.clone();
+        }
+        let mut n = self.placeholders.len() + 1;
+        let mut name = format!("UNSUPPORTED{}", n);
+        while self.taken(&name) {
+            n += 1;
+            name = format!("UNSUPPORTED{}", n);
+        }
+        self.added.push(name.clone());
+        self.placeholders.push((name.clone(), text.to_string()));
+        name
+    }
+
+    fn rule(&mut self, rule: &Production, lexical: bool) -> String {
+        if rule.result_type.is_some() {
+            self.note(backend::LEFT_OUT.to_string());
+        }
+        if !rule.modes.is_empty() || rule.action.is_some() {
+            self.note(backend::MODES_LEFT_OUT.to_string());
+        }
+        let name = self.name(lexical, &rule.name);
+        let (define, separator, end) = match self.notation {
+            Notation::W3c => ("::=", "|", ""),
+            Notation::Iso => ("=", "|", " ;"),
+            Notation::Abnf => ("=", "/", ""),
+        };
+        //alternatives on lines of their own, aligned below the first
+        let indent = " ".repeat(name.chars().count() + define.len() - separator.len() + 1);
+        let mut body = String::new();
+        for (i, alternative) in rule.alternatives.iter().enumerate() {
+            if i > 0 {
+                body.push_str(&format!("\n{}{} ", indent, separator));
+            }
+            body.push_str(&self.sequence(alternative));
+        }
+        if rule.nullable {
+            body = match self.notation {
+                Notation::W3c => format!("( {} )?", body),
+                _ => format!("[ {} ]", body),
+            };
+        }
+        format!("{} {} {}{}\n", name, define, body, end)
+    }
+
+    fn sequence(&mut self, alternative: &Alternative) -> String {
+        if alternative.action.is_some() {
+            self.note(backend::LEFT_OUT.to_string());
+        }
+        let items: Vec<String> = alternative.items.iter().filter_map(|item| self.item(item)).collect();
+        if items.is_empty() {
+            return match self.notation {
+                Notation::W3c => "/* empty */".to_string(),
+                Notation::Iso => "(* empty *)".to_string(),
+                Notation::Abnf => "\"\"".to_string(),
+            };
+        }
+        let separator = if self.notation == Notation::Iso { ", " } else { " " };
+        items.join(separator)
+    }
+
+    //None for an item matching only the empty string
+    fn item(&mut self, item: &Item) -> Option<String> {
+        if item.symbol == Symbol::Epsilon {
+            return None;
+        }
+        if item.label.is_some() {
+            self.note(backend::LEFT_OUT.to_string());
+        }
+        let (symbol, atomic) = self.symbol(&item.symbol);
+        let primary = if atomic || self.notation == Notation::Iso {
+            symbol.clone()
+        } else {
+            format!("( {} )", symbol)
+        };
+        let separator = match item.separator {
+            Some(ref separator) => separator,
+            None => {
+                return Some(match (self.notation, item.quantifier) {
+                    (_, Quantifier::One) => symbol,
+                    (Notation::W3c, Quantifier::Optional) => format!("{}?", primary),
+                    (Notation::W3c, Quantifier::Star) => format!("{}*", primary),
+                    (Notation::W3c, Quantifier::Plus) => format!("{}+", primary),
+                    (Notation::Iso, Quantifier::Optional) => format!("[ {} ]", symbol),
+                    (Notation::Iso, Quantifier::Star) => format!("{{ {} }}", symbol),
+                    (Notation::Iso, Quantifier::Plus) => format!("{}, {{ {} }}", primary, symbol),
+                    (Notation::Abnf, Quantifier::Optional) => format!("[ {} ]", symbol),
+                    (Notation::Abnf, Quantifier::Star) => format!("*{}", primary),
+                    (Notation::Abnf, Quantifier::Plus) => format!("1*{}", primary),
+                })
+            }
+        };
+        //`X* % S` is `(X (S X)*)?`, `%%` allows a trailing S
+        let (sep, atomic) = self.symbol(&separator.symbol);
+        let (list, trailing) = match self.notation {
+            Notation::W3c if atomic => (format!("{} ( {} {} )*", primary, sep, primary), format!(" {}?", sep)),
+            Notation::W3c => (format!("{} ( {} {} )*", primary, sep, primary), format!(" ( {} )?", sep)),
+            Notation::Iso => (format!("{}, {{ {}, {} }}", primary, sep, primary), format!(", [ {} ]", sep)),
+            Notation::Abnf => (format!("{} *( {} {} )", primary, sep, primary), format!(" [ {} ]", sep)),
+        };
+        let list = if separator.trailing { list + &trailing } else { list };
+        Some(match (self.notation, item.quantifier) {
+            (_, Quantifier::Plus) => list,
+            (Notation::W3c, _) => format!("( {} )?", list),
+            _ => format!("[ {} ]", list),
+        })
+    }
+
+    //The symbol and whether a quantifier can follow it as it is
+    fn symbol(&mut self, symbol: &Symbol) -> (String, bool) {
+        match *symbol {
+            Symbol::Literal(ref value) => self.literal(value),
+            Symbol::Lexical(ref name) => (self.name(true, name), true),
+            Symbol::Name(ref name) => (self.name(false, name), true),
+            Symbol::Class(ref class) => self.class(class),
+            Symbol::Epsilon => match self.notation {
+                Notation::W3c => ("/* empty */".to_string(), true),
+                Notation::Iso => ("(* empty *)".to_string(), true),
+                Notation::Abnf => ("\"\"".to_string(), true),
+            },
+            Symbol::Group(ref alternatives) => {
+                let separator = if self.notation == Notation::Abnf { " / " } else { " | " };
+                let alternatives: Vec<String> = alternatives.iter().map(|a| self.sequence(a)).collect();
+                (format!("( {} )", alternatives.join(separator)), true)
+            }
+            Symbol::And(_) | Symbol::Not(_) => (self.unsupported("lookaheads", &symbol.to_string()), true),
+        }
+    }
+
+    fn literal(&mut self, value: &str) -> (String, bool) {
+        //runs of printable characters and the characters between them
+        let mut pieces = Vec::new();
+        let mut run = String::new();
+        for c in value.chars() {
+            let printable = c >= ' ' && c != '\u{7f}' && (self.notation != Notation::Abnf || c <= '~');
+            let conflict = match self.notation {
+                //no quote can hold both quotes
+                Notation::W3c | Notation::Iso => {
+                    (c == '"' && run.contains('\'')) || (c == '\'' && run.contains('"'))
+                }
+                Notation::Abnf => c == '"',
+            };
+            if !run.is_empty() && (!printable || conflict) {
+                pieces.push(self.quoted(&run));
+                run.clear();
+            }
+            if printable && !(self.notation == Notation::Abnf && c == '"') {
+                run.push(c);
+            } else {
+                pieces.push(self.code(c));
+            }
+        }
+        if !run.is_empty() || pieces.is_empty() {
+            pieces.push(self.quoted(&run));
+        }
+        let separator = if self.notation == Notation::Iso { ", " } else { " " };
+        (pieces.join(separator), pieces.len() == 1)
+    }
+
+    fn quoted(&mut self, run: &str) -> String {
+        match self.notation {
+            Notation::Abnf if run.chars().any(|c| c.is_ascii_alphabetic()) => {
+                self.note("%s marks case sensitive strings, see RFC 7405".to_string());
+                format!("%s\"{}\"", run)
+            }
+            Notation::Abnf => format!("\"{}\"", run),
+            _ if run.contains('"') => format!("'{}'", run),
+            _ => format!("\"{}\"", run),
+        }
+    }
+
+    //A character outside of quotes
+    fn code(&mut self, c: char) -> String {
+        match self.notation {
+            Notation::W3c => format!("#x{:X}", c as u32),
+            Notation::Iso => {
+                self.note("characters that cannot be quoted are written as special sequences".to_string());
+                format!("? U+{:04X} ?", c as u32)
+            }
+            Notation::Abnf => format!("%x{:X}", c as u32),
+        }
+    }
+
+    fn class(&mut self, class: &CharClass) -> (String, bool) {
+        let ranges = match ranges(class) {
+            Some(ranges) => ranges,
+            None => return (self.unsupported("Unicode properties", &class.to_string()), true),
+        };
+        match self.notation {
+            Notation::W3c => {
+                let mut out = String::from(if class.negated { "[^" } else { "[" });
+                let mut coded = false;
+                for &(from, to) in &ranges {
+                    out.push_str(&class_char(from, &mut coded));
+                    if to > from {
+                        out.push('-');
+                        coded = false;
+                        out.push_str(&class_char(to, &mut coded));
+                    }
+                }
+                out.push(']');
+                (out, true)
+            }
+            Notation::Iso => {
+                let size: u32 = ranges.iter().map(|&(from, to)| to - from + 1).sum();
+                if class.negated || size > ISO_CLASS_LIMIT {
+                    return (self.unsupported("character classes", &class.to_string()), true);
+                }
+                let chars = ranges
+                    .iter()
+                    .flat_map(|&(from, to)| from..to + 1)
+                    .filter_map(::std::char::from_u32)
+                    .map(|c| self.literal(&c.to_string()).0)
+                    .collect::<Vec<String>>();
+                match chars.len() {
+                    1 => (chars[0].clone(), true),
+                    _ => (format!("( {} )", chars.join(" | ")), true),
+                }
+            }
+            Notation::Abnf => {
+                let ranges = if class.negated { complement(&ranges) } else { ranges };
+                let ranges: Vec<String> = ranges
+                    .iter()
+                    .map(|&(from, to)| if from == to {
+                        format!("%x{:X}", from)
+                    } else {
+                        format!("%x{:X}-{:X}", from, to)
+                    })
+                    .collect();
+                match ranges.len() {
+                    0 => (self.unsupported("empty character classes", &class.to_string()), true),
+                    1 => (ranges[0].clone(), true),
+                    _ => (format!("( {} )", ranges.join(" / ")), true),
+                }
+            }
+        }
+    }
+}
+
+//The code point ranges of the items of `class`, sorted and merged, None
+//when it has a property other than ASCII and Any
+fn ranges(class: &CharClass) -> Option<Vec<(u32, u32)>> {
+    let mut ranges = Vec::new();
+    for item in &class.items {
+        let range = match *item {
+            ClassItem::Char(c) => (c as u32, c as u32),
+            ClassItem::Range(from, to) => (from as u32, to as u32),
+            ClassItem::Property(ref name, false) if name == "ASCII" => (0, 0x7F),
+            ClassItem::Property(ref name, true) if name == "ASCII" => (0x80, 0x10FFFF),
+            ClassItem::Property(ref name, false) if name == "Any" => (0, 0x10FFFF),
+            ClassItem::Property(..) => return None,
+        };
+        ranges.push(range);
+    }
+    ranges.sort();
+    let mut merged: Vec<(u32, u32)> = Vec::new();
+    for (from, to) in ranges {
+        match merged.last_mut() {
+            Some(last) if from <= last.1 + 1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+    Some(merged)
+}
+
+fn complement(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut out = Vec::new();
+    let mut next = 0;
+    for &(from, to) in ranges {
+        if from > next {
+            out.push((next, from - 1));
+        }
+        next = to + 1;
+    }
+    if next <= 0x10FFFF {
+        out.push((next, 0x10FFFF));
+    }
+    out
+}
+
+//A character in a W3C class, letters and digits as they are unless they
+//would continue the #xN written before them, `coded` tells if one was
+fn class_char(code: u32, coded: &mut bool) -> String {
+    match ::std::char::from_u32(code) {
+        Some(c) if c.is_ascii_alphanumeric() && !(*coded && c.is_ascii_hexdigit()) => {
+            *coded = false;
+            c.to_string()
+        }
+        _ => {
+            *coded = true;
+            format!("#x{:X}", code)
+        }
+    }
+}
+
+const COMMENTS: &[(&str, &str)] = &[("/*", "*/")];
+
+pub fn import(source: &str) -> ImportResult<Imported> {
+    let mut importer = Importer {
+        cursor: Cursor::new(source, COMMENTS),
+        warnings: Vec::new(),
+    };
+    let mut grammar = Grammar::default();
+    loop {
+        importer.constraints()?;
+        if importer.cursor.at_end() {
+            break;
+        }
+        importer.number();
+        importer.cursor.skip_space();
+        let (line, offset) = importer.cursor.position();
+        let name = importer.name()?;
+        importer.cursor.expect("::=", FrontendError::ExpectedDefinition)?;
+        let alternatives = importer.alternatives()?;
+        if grammar.starts.is_empty() {
+            grammar.starts.push(EntryPoint {
+                name: name.clone(),
+                line,
+                offset,
+            });
+        }
+        grammar.productions.push(rule(&name, alternatives, line, offset));
+    }
+    let lexical: Vec<String> = grammar
+        .productions
+        .iter()
+        .map(|prod| prod.name.clone())
+        .filter(|name| name.chars().any(char::is_uppercase) && !name.chars().any(char::is_lowercase))
+        .collect();
+    let lexical: Vec<&str> = lexical.iter().map(String::as_str).collect();
+    Ok(Imported {
+        grammar: finish(grammar, &lexical),
+        warnings: importer.warnings,
+    })
+}
+
+struct Importer {
+    cursor: Cursor,
+    warnings: Vec<ImportError>,
+}
+
+impl Importer {
+    fn name(&mut self) -> ImportResult<String> {
+        self.cursor.skip_space();
+        match self.cursor.peek() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return Err(self.cursor.error(FrontendError::ExpectedName)),
+        }
+        let mut name = String::new();
+        while let Some(c) = self.cursor.peek() {
+            //'-' only inside a name, `A - B` is an exception
+            let inside = (c == '-' || c == '.') && self.cursor.peek_at(1).is_some_and(char::is_alphanumeric);
+            if !(c.is_alphanumeric() || c == '_' || inside) {
+                break;
+            }
+            name.push(c);
+            self.cursor.bump();
+        }
+        Ok(name)
+    }
+
+    //A rule number like [12]
+    fn number(&mut self) -> bool {
+        self.cursor.skip_space();
+        let saved = self.cursor.save();
+        if self.cursor.eat("[") {
+            self.cursor.skip_space();
+            let digits = self.cursor.take_while(|c| c.is_ascii_digit());
+            self.cursor.skip_space();
+            if !digits.is_empty() && self.cursor.eat("]") {
+                return true;
+            }
+        }
+        self.cursor.restore(saved);
+        false
+    }
+
+    //Whether a rule starts here
+    fn at_rule(&mut self) -> bool {
+        let saved = self.cursor.save();
+        self.number();
+        let found = self.name().is_ok() && {
+            self.cursor.skip_space();
+            self.cursor.at("::=")
+        };
+        self.cursor.restore(saved);
+        found
+    }
+
+    //Whether a constraint like [ wfc: Name ] starts here
+    fn at_constraint(&mut self) -> bool {
+        self.cursor.skip_space();
+        let saved = self.cursor.save();
+        let found = self.cursor.eat("[") && {
+            self.cursor.skip_space();
+            let kind = self.cursor.take_while(char::is_alphabetic);
+            kind.len() > 1 && self.cursor.eat(":")
+        };
+        self.cursor.restore(saved);
+        found
+    }
+
+    //Skips constraints, with a warning
+    fn constraints(&mut self) -> ImportResult<()> {
+        while self.at_constraint() {
+            let position = self.cursor.position();
+            while self.cursor.peek().is_some_and(|c| c != ']') {
+                self.cursor.bump();
+            }
+            if !self.cursor.eat("]") {
+                return Err(ImportError::new(FrontendError::ExpectedCloseBracket, position.0, position.1));
+            }
+            self.warnings.push(ImportError::new(FrontendError::DroppedConstraint, position.0, position.1));
+        }
+        self.cursor.skip_space();
+        Ok(())
+    }
+
+    fn alternatives(&mut self) -> ImportResult<Vec<Alternative>> {
+        let mut alternatives = vec![self.sequence()?];
+        loop {
+            self.cursor.skip_space();
+            if !self.cursor.eat("|") {
+                return Ok(alternatives);
+            }
+            alternatives.push(self.sequence()?);
+        }
+    }
+
+    fn sequence(&mut self) -> ImportResult<Alternative> {
+        let mut items = Vec::new();
+        loop {
+            self.cursor.skip_space();
+            match self.cursor.peek() {
+                None | Some('|') | Some(')') => break,
+                _ if self.at_rule() || self.at_constraint() => break,
+                _ => items.push(self.exception()?),
+            }
+        }
+        Ok(Alternative { items, action: None })
+    }
+
+    //`A - B`, or just A
+    fn exception(&mut self) -> ImportResult<Item> {
+        let item = self.postfix()?;
+        self.cursor.skip_space();
+        let position = self.cursor.position();
+        if !self.cursor.eat("-") {
+            return Ok(item);
+        }
+        let except = self.postfix()?;
+        if !single_character(&item) || !single_character(&except) {
+            self.warnings.push(ImportError::new(FrontendError::ApproximatedException, position.0, position.1));
+        }
+        let not = Item::new(Symbol::Not(Box::new(symbol(except))));
+        Ok(sequence(vec![not, item]))
+    }
+
+    fn postfix(&mut self) -> ImportResult<Item> {
+        let mut item = self.primary()?;
+        loop {
+            self.cursor.skip_space();
+            let quantifier = match self.cursor.peek() {
+                Some('?') => Quantifier::Optional,
+                Some('*') => Quantifier::Star,
+                Some('+') => Quantifier::Plus,
+                _ => return Ok(item),
+            };
+            self.cursor.bump();
+            item = quantified(item, quantifier);
+        }
+    }
+
+    fn primary(&mut self) -> ImportResult<Item> {
+        self.cursor.skip_space();
+        let (line, offset) = self.cursor.position();
+        match self.cursor.peek() {
+            Some('(') => {
+                self.cursor.bump();
+                let alternatives = self.alternatives()?;
+                self.cursor.expect(")", FrontendError::ExpectedCloseParen)?;
+                Ok(choice(alternatives))
+            }
+            Some(quote @ '"') | Some(quote @ '\'') => {
+                self.cursor.bump();
+                let value = self.cursor.take_while(|c| c != quote);
+                if !self.cursor.eat(&quote.to_string()) {
+                    return Err(self.cursor.error(FrontendError::EOI));
+                }
+                if value.is_empty() {
+                    return Ok(Item::new(Symbol::Epsilon));
+                }
+                Ok(Item::new(Symbol::Literal(value)))
+            }
+            Some('#') => {
+                let c = self.character()?;
+                Ok(Item::new(Symbol::Literal(c.to_string())))
+            }
+            Some('[') => {
+                self.cursor.bump();
+                let mut class = CharClass {
+                    negated: self.cursor.eat("^"),
+                    items: Vec::new(),
+                };
+                while !self.cursor.eat("]") {
+                    if self.cursor.at_end() {
+                        return Err(ImportError::new(FrontendError::ExpectedCloseBracket, line, offset));
+                    }
+                    let position = self.cursor.position();
+                    let from = self.character()?;
+                    if self.cursor.peek() != Some('-') || self.cursor.peek_at(1) == Some(']') {
+                        class.items.push(ClassItem::Char(from));
+                        continue;
+                    }
+                    self.cursor.bump();
+                    let to = self.character()?;
+                    if from > to {
+                        return Err(ImportError::new(FrontendError::InvalidRange, position.0, position.1));
+                    }
+                    //every character, as \p{Any} is exported
+                    if from == '\0' && to == ::std::char::MAX {
+                        class.items.push(ClassItem::Property("Any".to_string(), false));
+                    } else {
+                        class.items.push(ClassItem::Range(from, to));
+                    }
+                }
+                if class.items.is_empty() {
+                    return Err(ImportError::new(FrontendError::InvalidCharClass, line, offset));
+                }
+                Ok(Item::new(Symbol::Class(class)))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => Ok(Item::new(Symbol::Name(self.name()?))),
+            _ => Err(self.cursor.error(FrontendError::ExpectedPart)),
+        }
+    }
+
+    //A character, or #xN for the one with code point N
+    fn character(&mut self) -> ImportResult<char> {
+        let (line, offset) = self.cursor.position();
+        if !self.cursor.eat("#x") {
+            return self.cursor.bump().ok_or_else(|| self.cursor.error(FrontendError::EOI));
+        }
+        let hex = self.cursor.take_while(|c| c.is_ascii_hexdigit());
+        match u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32) {
+            Some(c) => Ok(c),
+            None => Err(ImportError::new(FrontendError::ExpectedEscapeSequence, line, offset)),
+        }
+    }
+}
+
+fn single_character(item: &Item) -> bool {
+    if item.quantifier != Quantifier::One {
+        return false;
+    }
+    match item.symbol {
+        Symbol::Class(_) => true,
+        Symbol::Literal(ref value) => value.chars().count() == 1,
+        _ => false,
+    }
+}
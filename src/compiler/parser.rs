@@ -1,24 +1,67 @@
-#![allow(dead_code)]
 //See gideon.gideon for language grammar
 use super::lexer::*;
 use super::syntax_tree::*;
 use super::token::*;
 use super::frontend_error::*;
+use super::visitor::first_error;
 
 use std::cell::Cell;
 
 pub type SyntaxResult<T> = Result<T, FrontendError>;
 
+//Contextual keywords, only special in lexical rule heads and skip declarations
+const IN: &[char; 2] = &['i', 'n'];
+const PUSH: &[char; 4] = &['p', 'u', 's', 'h'];
+const POP: &[char; 3] = &['p', 'o', 'p'];
+
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
     cache: Cell<Option<LexicalResult<'a>>>,
+    //the first error of the lexer and where the token it failed on starts
+    lexical_error: Cell<Option<(FrontendError, usize, usize)>>,
 }
 impl<'a> Parser<'a> {
     pub fn new(input: &'a [char]) -> Self {
+        Parser::with_trivia(input, Trivia::Skip)
+    }
+
+    //Trivia::Attach keeps comments and whitespace on the tokens of the tree
+    pub fn with_trivia(input: &'a [char], trivia: Trivia) -> Self {
         Parser {
-            lexer: Lexer::new(input),
+            lexer: Lexer::with_trivia(input, trivia),
             cache: Cell::new(None),
+            lexical_error: Cell::new(None),
+        }
+    }
+
+    pub fn trailing_trivia(&'a self) -> &'a [char] {
+        self.lexer.trailing_trivia()
+    }
+
+    //Position the parser has reached, where a failed parse stopped
+    pub fn line(&self) -> usize {
+        self.lexer.line()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.lexer.offset()
+    }
+
+    //The first error in the parsed `tree` and where it is, see
+    //visitor::first_error. Input ending too early fails at its end. A token
+    //the lexer failed on fails at its start, the errors after it follow from
+    //the token missing.
+    pub fn first_error(&'a self, tree: &Grammar<'a>) -> Option<(FrontendError, usize, usize)> {
+        let found = first_error(tree).map(|(what, line, offset)| match what {
+            FrontendError::EOI => (what, self.line(), self.offset()),
+            _ => (what, line, offset),
+        });
+        if let (Some(found), Some(lexical)) = (found, self.lexical_error.get()) {
+            if found.0 == lexical.0 || (found.1, found.2) >= (lexical.1, lexical.2) {
+                return Some(lexical);
+            }
         }
+        found
     }
 
     pub fn parse(&'a self) -> SyntaxResult<Grammar<'a>> {
@@ -31,7 +74,14 @@ impl<'a> Parser<'a> {
             self.cache.set(None);
             cached
         } else {
-            self.lexer.next()
+            let token = self.lexer.next();
+            match token {
+                Err(FrontendError::EOI) | Ok(_) => {}
+                Err(what) => if self.lexical_error.get().is_none() {
+                    self.lexical_error.set(Some((what, self.lexer.line(), self.lexer.offset())));
+                },
+            }
+            token
         }
     }
 
@@ -39,12 +89,43 @@ impl<'a> Parser<'a> {
         self.cache.set(Some(self.lexer.current_out()));
     }
 
-    //Grammar -> Prod Grammar? | Path Grammar? ;
+    //Grammar -> Prod Grammar? | Path Grammar? | Skip Grammar? | Layout Grammar?
+    //         | Start Grammar? | Extends Grammar? | Override Grammar? ;
     fn parse_grammar(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Grammar<'a>> {
         match current? {
-            Token::Name(data) => {
+            Token::Name(_) | Token::OpenBrace(_) => {
                 Ok(Grammar::ProdDecl(
-                    self.parse_production(Ok(Token::Name(data))),
+                    self.parse_production(current),
+                    self.parse_rgrammar(self.next()),
+                ))
+            }
+            Token::Skip(data) => {
+                Ok(Grammar::SkipDecl(
+                    self.parse_skip(Ok(Token::Skip(data))),
+                    self.parse_rgrammar(self.next()),
+                ))
+            }
+            Token::Layout(data) => {
+                Ok(Grammar::LayoutDecl(
+                    self.parse_layout(Ok(Token::Layout(data))),
+                    self.parse_rgrammar(self.next()),
+                ))
+            }
+            Token::Start(data) => {
+                Ok(Grammar::StartDecl(
+                    self.parse_start(Ok(Token::Start(data))),
+                    self.parse_rgrammar(self.next()),
+                ))
+            }
+            Token::Extends(data) => {
+                Ok(Grammar::ExtendsDecl(
+                    self.parse_extends(Ok(Token::Extends(data))),
+                    self.parse_rgrammar(self.next()),
+                ))
+            }
+            Token::Override(data) => {
+                Ok(Grammar::OverrideDecl(
+                    self.parse_override(Ok(Token::Override(data))),
                     self.parse_rgrammar(self.next()),
                 ))
             }
@@ -64,7 +145,14 @@ impl<'a> Parser<'a> {
     ) -> Recursive<SyntaxResult<Grammar<'a>>> {
         match current {
             Ok(data) => match data {
-                Token::Name(_) | Token::Use(_) => {
+                Token::Name(_)
+                | Token::Use(_)
+                | Token::Skip(_)
+                | Token::Layout(_)
+                | Token::Start(_)
+                | Token::Extends(_)
+                | Token::Override(_)
+                | Token::OpenBrace(_) => {
                     self.cache_last();
                     Box::new(Some(self.parse_grammar(self.next())))
                 },
@@ -76,17 +164,24 @@ impl<'a> Parser<'a> {
         }
     }
 
-    //Prod -> {NAME} Nullable "->" Union ";" ;
+    //Prod -> ProdName Modifiers Nullable ResultType ProdArrow Union ";" ;
+    //ProdArrow -> "->" | "+->" ;
     fn parse_production(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Prod<'a>> {
-        let name = match current? {
-            Token::Name(data) => Ok(Token::Name(data)),
-            _ => Err(FrontendError::ExpectedName),
+        let name = self.parse_prod_name(current);
+
+        //only lexical rules take modifiers
+        let modifiers = match name {
+            Ok(ProdName::LexicalRuleName(..)) => self.parse_modifiers(self.next()),
+            _ => Box::new(None),
         };
 
         let nullable = self.parse_nullable(self.next());
 
+        let rtype = self.parse_result_type(self.next());
+
         let arrow = match self.next()? {
             Token::Arrow(data) => Ok(Token::Arrow(data)),
+            Token::AppendArrow(data) => Ok(Token::AppendArrow(data)),
             _ => Err(FrontendError::ExpectedArrow),
         };
 
@@ -97,7 +192,162 @@ impl<'a> Parser<'a> {
             _ => Err(FrontendError::ExpectedEndl),
         };
 
-        Ok(Prod::new(name, nullable, arrow, u, endl))
+        Ok(Prod::new(name, modifiers, nullable, rtype, arrow, u, endl))
+    }
+
+    //Modifiers? -> Modifier Modifiers ;
+    fn parse_modifiers(
+        &'a self,
+        current: LexicalResult<'a>,
+    ) -> Recursive<SyntaxResult<Modifiers<'a>>> {
+        let modifier = match current {
+            Ok(Token::Name(data)) if is_modifier(data.value()) => {
+                self.parse_modifier(Ok(Token::Name(data)))
+            }
+            _ => {
+                self.cache_last();
+                return Box::new(None);
+            }
+        };
+        let rest = self.parse_modifiers(self.next());
+        Box::new(Some(Ok(Modifiers::new(modifier, rest))))
+    }
+
+    //Modifier -> "in" {NAME} ModeList
+    //          | "push" {NAME}
+    //          | "pop" ;
+    fn parse_modifier(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Modifier<'a>> {
+        let keyword = match current? {
+            Token::Name(data) => data,
+            _ => return Err(FrontendError::ExpectedModifier),
+        };
+        if keyword.value() == POP {
+            return Ok(Modifier::Pop(current));
+        }
+        let name = match self.next()? {
+            Token::Name(data) => Ok(Token::Name(data)),
+            _ => Err(FrontendError::ExpectedName),
+        };
+        if keyword.value() == IN {
+            Ok(Modifier::In(current, name, self.parse_mode_list(self.next())))
+        } else if keyword.value() == PUSH {
+            Ok(Modifier::Push(current, name))
+        } else {
+            Err(FrontendError::ExpectedModifier)
+        }
+    }
+
+    //ModeList? -> "|" {NAME} ModeList ;
+    fn parse_mode_list(
+        &'a self,
+        current: LexicalResult<'a>,
+    ) -> Recursive<SyntaxResult<ModeList<'a>>> {
+        let or = match current {
+            Ok(Token::Or(data)) => Ok(Token::Or(data)),
+            _ => {
+                self.cache_last();
+                return Box::new(None);
+            }
+        };
+        let name = match self.next() {
+            Ok(Token::Name(data)) => Ok(Token::Name(data)),
+            Ok(_) => Err(FrontendError::ExpectedName),
+            Err(what) => Err(what),
+        };
+        let list = self.parse_mode_list(self.next());
+        Box::new(Some(Ok(ModeList::new(or, name, list))))
+    }
+
+    //ProdName -> {NAME} Params | "{" {NAME} "}" ;
+    fn parse_prod_name(&'a self, current: LexicalResult<'a>) -> SyntaxResult<ProdName<'a>> {
+        match current? {
+            Token::Name(data) => {
+                let params = self.parse_params(self.next());
+                Ok(ProdName::Name(Ok(Token::Name(data)), params))
+            }
+            Token::OpenBrace(brace) => {
+                let (obrace, name, cbrace) = self.parse_lexical_rule_name(brace)?;
+                Ok(ProdName::LexicalRuleName(obrace, name, cbrace))
+            }
+            _ => Err(FrontendError::ExpectedName),
+        }
+    }
+
+    //Params? -> "<" {NAME} ParamList ">" ;
+    fn parse_params(&'a self, current: LexicalResult<'a>) -> Recursive<SyntaxResult<Params<'a>>> {
+        let oangle = match current {
+            Ok(Token::OpenAngle(data)) => Ok(Token::OpenAngle(data)),
+            _ => {
+                self.cache_last();
+                return Box::new(None);
+            }
+        };
+        let name = match self.next() {
+            Ok(Token::Name(data)) => Ok(Token::Name(data)),
+            Ok(_) => Err(FrontendError::ExpectedName),
+            Err(what) => Err(what),
+        };
+        let list = self.parse_param_list(self.next());
+        let cangle = match self.next() {
+            Ok(Token::CloseAngle(data)) => Ok(Token::CloseAngle(data)),
+            Ok(_) => Err(FrontendError::ExpectedCloseAngle),
+            Err(what) => Err(what),
+        };
+        Box::new(Some(Ok(Params::new(oangle, name, list, cangle))))
+    }
+
+    //ParamList? -> "," {NAME} ParamList ;
+    fn parse_param_list(
+        &'a self,
+        current: LexicalResult<'a>,
+    ) -> Recursive<SyntaxResult<ParamList<'a>>> {
+        let comma = match current {
+            Ok(Token::Comma(data)) => Ok(Token::Comma(data)),
+            _ => {
+                self.cache_last();
+                return Box::new(None);
+            }
+        };
+        let name = match self.next() {
+            Ok(Token::Name(data)) => Ok(Token::Name(data)),
+            Ok(_) => Err(FrontendError::ExpectedName),
+            Err(what) => Err(what),
+        };
+        let list = self.parse_param_list(self.next());
+        Box::new(Some(Ok(ParamList::new(comma, name, list))))
+    }
+
+    //Args? -> "<" Union ArgList ">" ;
+    fn parse_args(&'a self, current: LexicalResult<'a>) -> Recursive<SyntaxResult<Args<'a>>> {
+        let oangle = match current {
+            Ok(Token::OpenAngle(data)) => Ok(Token::OpenAngle(data)),
+            _ => {
+                self.cache_last();
+                return Box::new(None);
+            }
+        };
+        let union = self.parse_union(self.next());
+        let list = self.parse_arg_list(self.next());
+        let cangle = match self.next() {
+            Ok(Token::CloseAngle(data)) => Ok(Token::CloseAngle(data)),
+            Ok(_) => Err(FrontendError::ExpectedCloseAngle),
+            Err(what) => Err(what),
+        };
+        Box::new(Some(Ok(Args::new(oangle, union, list, cangle))))
+    }
+
+    //ArgList? -> "," Union ArgList ;
+    fn parse_arg_list(&'a self, current: LexicalResult<'a>) -> Recursive<SyntaxResult<ArgList<'a>>> {
+        let comma = match current {
+            Ok(Token::Comma(data)) => Ok(Token::Comma(data)),
+            _ => {
+                self.cache_last();
+                return Box::new(None);
+            }
+        };
+        let union = self.parse_union(self.next());
+        let list = self.parse_arg_list(self.next());
+        Box::new(Some(Ok(ArgList::new(comma, union, list))))
     }
 
     //Nullable -> "?" ? ;
@@ -114,37 +364,91 @@ impl<'a> Parser<'a> {
         }
     }
 
-    //Union -> Body OBody ;
+    //ResultType? -> ":" {TYPE} ;
+    fn parse_result_type(&'a self, current: LexicalResult<'a>) -> SyntaxResult<OResultType<'a>> {
+        match current? {
+            Token::Colon(data) => {
+                let rtype = self.lexer.next_type();
+                Ok(Some(Ok(ResultType::new(Ok(Token::Colon(data)), rtype))))
+            }
+            _ => {
+                self.cache_last();
+                Ok(None)
+            }
+        }
+    }
+
+    //Union -> Body Action OBody ;
     fn parse_union(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Union<'a>> {
         let body = self.parse_body(current);
+        let action = self.parse_action(self.next());
         let obody = self.parse_obody(self.next());
-        Ok(Union::new(body, obody))
+        Ok(Union::new(body, action, obody))
+    }
+
+    //Action? -> "=>" {CODE} ;
+    fn parse_action(&'a self, current: LexicalResult<'a>) -> SyntaxResult<OAction<'a>> {
+        match current {
+            Ok(Token::FatArrow(data)) => {
+                let code = self.lexer.next_code();
+                Ok(Some(Ok(Action::new(Ok(Token::FatArrow(data)), code))))
+            }
+            _ => {
+                self.cache_last();
+                Ok(None)
+            }
+        }
+    }
+
+    //Quantifier? -> "?" | "*" | "+" ;
+    fn parse_quantifier(&'a self, current: LexicalResult<'a>) -> SyntaxResult<OQuantifier<'a>> {
+        match current {
+            Ok(Token::QMark(data)) => Ok(Some(Ok(Quantifier::new(Ok(Token::QMark(data)))))),
+            Ok(Token::Star(data)) => Ok(Some(Ok(Quantifier::new(Ok(Token::Star(data)))))),
+            Ok(Token::Plus(data)) => Ok(Some(Ok(Quantifier::new(Ok(Token::Plus(data)))))),
+            _ => {
+                self.cache_last();
+                Ok(None)
+            }
+        }
+    }
+
+    //Separator? -> "%" Part | "%%" Part ;
+    fn parse_separator(&'a self, current: LexicalResult<'a>) -> SyntaxResult<OSeparator<'a>> {
+        let op = match current {
+            Ok(Token::Percent(data)) => Ok(Token::Percent(data)),
+            Ok(Token::DoublePercent(data)) => Ok(Token::DoublePercent(data)),
+            _ => {
+                self.cache_last();
+                return Ok(None);
+            }
+        };
+        let part = self.parse_part(self.next());
+        Ok(Some(Ok(Separator::new(op, part))))
     }
 
-    //Body -> Part Nullable Body? ;
+    //Body -> Part Quantifier Separator Body? ;
     fn parse_body(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Body<'a>> {
         let part = self.parse_part(current);
-        let nullable = self.parse_nullable(self.next());
+        let quantifier = self.parse_quantifier(self.next());
+        let separator = self.parse_separator(self.next());
         let rbody = self.parse_rbody(self.next());
-        Ok(Body::new(part, nullable, rbody))
+        Ok(Body::new(part, quantifier, separator, rbody))
     }
 
     fn parse_rbody(&'a self, current: LexicalResult<'a>) -> Recursive<SyntaxResult<Body<'a>>>{
         match current {
-            Ok(token) => {
-                match token {
-                     Token::Literal(_) 
-                     | Token::Name(_) 
-                     | Token::Epsilon(_) 
-                     | Token::OpenBrace(_) => {
-                         self.cache_last();
-                         Box::new(Some(self.parse_body(self.next())))
-                     }
-                     _ => {
-                         self.cache_last();
-                         Box::new(None)
-                     }
-                }
+            Ok(Token::Literal(_))
+            | Ok(Token::Class(_))
+            | Ok(Token::Property(_))
+            | Ok(Token::Name(_))
+            | Ok(Token::Epsilon(_))
+            | Ok(Token::OpenBrace(_))
+            | Ok(Token::OpenParen(_))
+            | Ok(Token::Amp(_))
+            | Ok(Token::Bang(_)) => {
+                self.cache_last();
+                Box::new(Some(self.parse_body(self.next())))
             }
             _ => {
                 self.cache_last();
@@ -156,10 +460,7 @@ impl<'a> Parser<'a> {
     //OBody? -> "|" Union ;
     fn parse_obody(&'a self, current: LexicalResult<'a>) -> Recursive<SyntaxResult<OBody<'a>>> {
         let or = match current {
-            Ok(data) => match data {
-                Token::Or(data) => Ok(Token::Or(data)),
-                _ => {self.cache_last(); return Box::new(None)}
-            },
+            Ok(Token::Or(data)) => Ok(Token::Or(data)),
             _ => {self.cache_last(); return Box::new(None)}
         };
         let union = self.parse_union(self.next());
@@ -167,31 +468,184 @@ impl<'a> Parser<'a> {
         Box::new(Some(Ok(OBody::new(or, union))))
     }
 
-    //Part -> {LITERAL} 
-    //      | "{" {NAME} "}" 
-    //      | {NAME} 
-    //      | {EPSILON};  
+    //Part -> {LITERAL} Range
+    //      | {CLASS}
+    //      | {PROPERTY}
+    //      | "{" {NAME} "}"
+    //      | {NAME} Args
+    //      | {EPSILON}
+    //      | "(" Union ")"
+    //      | {NAME} ":" Part
+    //      | "&" Part
+    //      | "!" Part ;
     fn parse_part(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Part<'a>> {
         match current? {
-            Token::Literal(data) => Ok(Part::Literal(Ok(Token::Literal(data)))),
-            Token::Name(data) => Ok(Part::Name(Ok(Token::Name(data)))),
+            Token::Literal(data) => {
+                let from = Ok(Token::Literal(data));
+                match self.next() {
+                    Ok(Token::Range(dots)) => {
+                        let to = match self.next()? {
+                            Token::Literal(data) => Ok(Token::Literal(data)),
+                            _ => Err(FrontendError::ExpectedLiteral),
+                        };
+                        Ok(Part::Range(from, Ok(Token::Range(dots)), to))
+                    }
+                    _ => {
+                        self.cache_last();
+                        Ok(Part::Literal(from))
+                    }
+                }
+            }
+            Token::Class(data) => Ok(Part::Class(Ok(Token::Class(data)))),
+            Token::Property(data) => Ok(Part::Property(Ok(Token::Property(data)))),
+            Token::Name(data) => match self.next() {
+                Ok(Token::Colon(colon)) => {
+                    let part = self.parse_part(self.next());
+                    Ok(Part::Labeled(Ok(Token::Name(data)), Ok(Token::Colon(colon)), Box::new(part)))
+                }
+                next => {
+                    let args = self.parse_args(next);
+                    Ok(Part::Name(Ok(Token::Name(data)), args))
+                }
+            },
             Token::Epsilon(data) => Ok(Part::Epsilon(Ok(Token::Epsilon(data)))),
             Token::OpenBrace(brace) => {
-                let obrace = Ok(Token::OpenBrace(brace));
-                let name = match self.next()? {
-                    Token::Name(data) => Ok(Token::Name(data)),
-                    _ => Err(FrontendError::ExpectedName)
-                };
-                let cbrace = match self.next()? {
-                    Token::CloseBrace(brace) => Ok(Token::CloseBrace(brace)),
-                    _=>Err(FrontendError::ExpectedCloseCurlyBrace)
-                };
+                let (obrace, name, cbrace) = self.parse_lexical_rule_name(brace)?;
                 Ok(Part::LexicalRuleName(obrace, name, cbrace))
             }
+            Token::OpenParen(data) => {
+                let union = self.parse_union(self.next());
+                let cparen = match self.next()? {
+                    Token::CloseParen(data) => Ok(Token::CloseParen(data)),
+                    _ => Err(FrontendError::ExpectedCloseParen),
+                };
+                Ok(Part::Group(Ok(Token::OpenParen(data)), Box::new(union), cparen))
+            }
+            Token::Amp(data) => {
+                let part = self.parse_part(self.next());
+                Ok(Part::And(Ok(Token::Amp(data)), Box::new(part)))
+            }
+            Token::Bang(data) => {
+                let part = self.parse_part(self.next());
+                Ok(Part::Not(Ok(Token::Bang(data)), Box::new(part)))
+            }
             _ => Err(FrontendError::ExpectedPart)
         }
     }
 
+    //"{" {NAME} "}" with the open brace already consumed
+    #[allow(clippy::type_complexity)]
+    fn parse_lexical_rule_name(
+        &'a self,
+        brace: TokenData<'a>,
+    ) -> SyntaxResult<(LexicalResult<'a>, LexicalResult<'a>, LexicalResult<'a>)> {
+        let obrace = Ok(Token::OpenBrace(brace));
+        let name = match self.next()? {
+            Token::Name(data) => Ok(Token::Name(data)),
+            _ => Err(FrontendError::ExpectedName)
+        };
+        let cbrace = match self.next()? {
+            Token::CloseBrace(brace) => Ok(Token::CloseBrace(brace)),
+            _=>Err(FrontendError::ExpectedCloseCurlyBrace)
+        };
+        Ok((obrace, name, cbrace))
+    }
+
+    //Skip -> "skip" Modifiers Union ";" ;
+    fn parse_skip(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Skip<'a>> {
+        let kskip = match current? {
+            Token::Skip(data) => Ok(Token::Skip(data)),
+            _ => Err(FrontendError::ExpectedSkip),
+        };
+        let modifiers = self.parse_modifiers(self.next());
+        let u = self.parse_union(self.next());
+        let endl = match self.next()? {
+            Token::Endl(data) => Ok(Token::Endl(data)),
+            _ => Err(FrontendError::ExpectedEndl),
+        };
+        Ok(Skip::new(kskip, modifiers, u, endl))
+    }
+
+    //Layout -> "layout" ";" ;
+    fn parse_layout(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Layout<'a>> {
+        let klayout = match current? {
+            Token::Layout(data) => Ok(Token::Layout(data)),
+            _ => Err(FrontendError::ExpectedLayout),
+        };
+        let endl = match self.next()? {
+            Token::Endl(data) => Ok(Token::Endl(data)),
+            _ => Err(FrontendError::ExpectedEndl),
+        };
+        Ok(Layout::new(klayout, endl))
+    }
+
+    //Extends -> "extends" {NAME} PathItemList ";" ;
+    fn parse_extends(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Extends<'a>> {
+        let kextends = match current? {
+            Token::Extends(data) => Ok(Token::Extends(data)),
+            _ => Err(FrontendError::ExpectedExtends),
+        };
+        let name = match self.next()? {
+            Token::Name(data) => Ok(Token::Name(data)),
+            _ => Err(FrontendError::ExpectedName),
+        };
+        let list = self.parse_path_item_list(self.next());
+        let endl = match self.next()? {
+            Token::Endl(data) => Ok(Token::Endl(data)),
+            _ => Err(FrontendError::ExpectedEndl),
+        };
+        Ok(Extends::new(kextends, name, list, endl))
+    }
+
+    //Override -> "override" Prod ;
+    fn parse_override(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Override<'a>> {
+        let koverride = match current? {
+            Token::Override(data) => Ok(Token::Override(data)),
+            _ => Err(FrontendError::ExpectedOverride),
+        };
+        let prod = self.parse_production(self.next());
+        Ok(Override::new(koverride, prod))
+    }
+
+    //Start -> "start" {NAME} StartList ";" ;
+    fn parse_start(&'a self, current: LexicalResult<'a>) -> SyntaxResult<Start<'a>> {
+        let kstart = match current? {
+            Token::Start(data) => Ok(Token::Start(data)),
+            _ => Err(FrontendError::ExpectedStart),
+        };
+        let name = match self.next()? {
+            Token::Name(data) => Ok(Token::Name(data)),
+            _ => Err(FrontendError::ExpectedName),
+        };
+        let list = self.parse_start_list(self.next());
+        let endl = match self.next()? {
+            Token::Endl(data) => Ok(Token::Endl(data)),
+            _ => Err(FrontendError::ExpectedEndl),
+        };
+        Ok(Start::new(kstart, name, list, endl))
+    }
+
+    //StartList? -> "," {NAME} StartList ;
+    fn parse_start_list(
+        &'a self,
+        current: LexicalResult<'a>,
+    ) -> Recursive<SyntaxResult<StartList<'a>>> {
+        let comma = match current {
+            Ok(Token::Comma(data)) => Ok(Token::Comma(data)),
+            _ => {
+                self.cache_last();
+                return Box::new(None);
+            }
+        };
+        let name = match self.next() {
+            Ok(Token::Name(data)) => Ok(Token::Name(data)),
+            Ok(_) => Err(FrontendError::ExpectedName),
+            Err(what) => Err(what),
+        };
+        let list = self.parse_start_list(self.next());
+        Box::new(Some(Ok(StartList::new(comma, name, list))))
+    }
+
 
 
     //Path -> "use" {NAME} PathItemList ";" ;
@@ -251,3 +705,7 @@ impl<'a> Parser<'a> {
         Box::new(Some(Ok(PathItemList::new(path_seperator, name, path_item_list))))
     }
 }
+
+fn is_modifier(value: &[char]) -> bool {
+    value == IN || value == PUSH || value == POP
+}
@@ -0,0 +1,439 @@
+//PEG grammars of pest, read by `gideon import`, see import.rs.
+//
+//  WHITESPACE = _{ " " | "\t" }
+//  ident = @{ ASCII_ALPHA ~ ASCII_ALPHANUMERIC* }
+//  file = { SOI ~ (ident ~ ";")* ~ EOI }
+//
+//pest reads characters: normal rules skip WHITESPACE and COMMENT between
+//their parts, atomic rules ('@' and '$') do not. So atomic rules, the rules
+//they use, WHITESPACE and COMMENT become lexical rules, the latter two used
+//by a skip declaration, and all other rules productions. The silent and
+//non-atomic modifiers only shape the tree and are dropped, tags become
+//labels.
+//
+//Sequences, choices, lookaheads and '?', '*', '+' carry over, repetitions
+//like {2,4} are written out and ^"..." becomes a class for every letter.
+//Built-in rules become classes or literals. SOI and EOI are dropped, the
+//rules using EOI are the entry points. The stack of pest matches text seen
+//before, an error since Gideon has no equivalent.
+use super::char_class::{CharClass, ClassItem};
+use super::frontend_error::FrontendError;
+use super::grammar::*;
+use super::import::*;
+
+const COMMENTS: &[(&str, &str)] = &[("//", "\n"), ("/*", "*/")];
+
+//Rules that are skipped between the parts of normal rules
+const SKIPPED: &[&str] = &["WHITESPACE", "COMMENT"];
+
+const STACK: &[&str] = &["PUSH", "POP", "POP_ALL", "PEEK", "PEEK_ALL", "DROP"];
+
+pub fn import(source: &str) -> ImportResult<Imported> {
+    let mut importer = Importer {
+        cursor: Cursor::new(source, COMMENTS),
+        warnings: Vec::new(),
+        end: false,
+        read_end: false,
+    };
+    let mut grammar = Grammar::default();
+    let mut lexical = Vec::new();
+    loop {
+        importer.cursor.skip_space();
+        if importer.cursor.at_end() {
+            break;
+        }
+        let (line, offset) = importer.cursor.position();
+        let name = importer.identifier()?;
+        importer.cursor.expect("=", FrontendError::ExpectedEquals)?;
+        importer.cursor.skip_space();
+        let modifier = match importer.cursor.peek() {
+            Some(c) if "_@$!".contains(c) => importer.cursor.bump(),
+            _ => None,
+        };
+        importer.cursor.expect("{", FrontendError::ExpectedOpenCurlyBrace)?;
+        importer.end = false;
+        let alternatives = importer.alternatives()?;
+        importer.cursor.expect("}", FrontendError::ExpectedCloseCurlyBrace)?;
+        if importer.end {
+            grammar.starts.push(EntryPoint {
+                name: name.clone(),
+                line,
+                offset,
+            });
+        }
+        if modifier == Some('@') || modifier == Some('$') || SKIPPED.contains(&name.as_str()) {
+            lexical.push(name.clone());
+        }
+        grammar.productions.push(rule(&name, alternatives, line, offset));
+    }
+    let skipped: Vec<Alternative> = SKIPPED
+        .iter()
+        .filter(|name| lexical.contains(&name.to_string()))
+        .map(|name| Alternative {
+            items: vec![Item::new(Symbol::Name(name.to_string()))],
+            action: None,
+        })
+        .collect();
+    if !skipped.is_empty() {
+        grammar.skip.push(Skip {
+            modes: Vec::new(),
+            alternatives: skipped,
+        });
+    }
+    let lexical: Vec<&str> = lexical.iter().map(String::as_str).collect();
+    Ok(Imported {
+        grammar: finish(grammar, &lexical),
+        warnings: importer.warnings,
+    })
+}
+
+struct Importer {
+    cursor: Cursor,
+    warnings: Vec<ImportError>,
+    //the rule being read uses EOI
+    end: bool,
+    //the last atom read is EOI
+    read_end: bool,
+}
+
+impl Importer {
+    fn warn(&mut self, what: FrontendError, position: (usize, usize)) {
+        self.warnings.push(ImportError::new(what, position.0, position.1));
+    }
+
+    fn identifier(&mut self) -> ImportResult<String> {
+        self.cursor.skip_space();
+        match self.cursor.peek() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return Err(self.cursor.error(FrontendError::ExpectedName)),
+        }
+        Ok(self.cursor.take_while(|c| c.is_alphanumeric() || c == '_'))
+    }
+
+    //Choices up to a closing brace or parenthesis, a leading '|' is allowed
+    fn alternatives(&mut self) -> ImportResult<Vec<Alternative>> {
+        self.cursor.skip_space();
+        self.cursor.eat("|");
+        let mut alternatives = vec![self.sequence()?];
+        loop {
+            self.cursor.skip_space();
+            if !self.cursor.eat("|") {
+                return Ok(alternatives);
+            }
+            alternatives.push(self.sequence()?);
+        }
+    }
+
+    fn sequence(&mut self) -> ImportResult<Alternative> {
+        let mut items = vec![self.term()?];
+        loop {
+            self.cursor.skip_space();
+            if !self.cursor.eat("~") {
+                break;
+            }
+            items.push(self.term()?);
+        }
+        items.retain(|item| item.symbol != Symbol::Epsilon || item.label.is_some());
+        Ok(Alternative { items, action: None })
+    }
+
+    fn term(&mut self) -> ImportResult<Item> {
+        self.cursor.skip_space();
+        let label = if self.cursor.eat("#") {
+            let label = self.identifier()?;
+            self.cursor.expect("=", FrontendError::ExpectedEquals)?;
+            Some(label)
+        } else {
+            None
+        };
+        let mut prefixes = Vec::new();
+        loop {
+            self.cursor.skip_space();
+            match self.cursor.peek() {
+                Some(c @ '&') | Some(c @ '!') => {
+                    self.cursor.bump();
+                    prefixes.push(c);
+                }
+                _ => break,
+            }
+        }
+        let position = self.cursor.position();
+        self.read_end = false;
+        let mut item = self.atom()?;
+        let read_end = self.read_end;
+        let mut repeated = false;
+        loop {
+            self.cursor.skip_space();
+            let quantifier = match self.cursor.peek() {
+                Some('?') => Quantifier::Optional,
+                Some('*') => Quantifier::Star,
+                Some('+') => Quantifier::Plus,
+                Some('{') => {
+                    item = self.repetition(item)?;
+                    repeated = true;
+                    continue;
+                }
+                _ => break,
+            };
+            self.cursor.bump();
+            item = quantified(item, quantifier);
+            repeated = true;
+        }
+        if read_end {
+            if repeated || !prefixes.is_empty() || !self.at_sequence_end() {
+                self.warn(FrontendError::DroppedEndOfInput, position);
+            }
+            return Ok(Item::new(Symbol::Epsilon));
+        }
+        for prefix in prefixes.into_iter().rev() {
+            let symbol = Box::new(symbol(item));
+            item = Item::new(if prefix == '&' { Symbol::And(symbol) } else { Symbol::Not(symbol) });
+        }
+        if label.is_some() {
+            item = sequence(vec![item]);
+            item.label = label;
+        }
+        Ok(item)
+    }
+
+    //Whether nothing follows in the sequence being read
+    fn at_sequence_end(&mut self) -> bool {
+        self.cursor.skip_space();
+        matches!(self.cursor.peek(), Some('}') | Some(')') | Some('|') | None)
+    }
+
+    //`{n}`, `{n,}`, `{,m}` or `{n,m}` after `item`
+    fn repetition(&mut self, item: Item) -> ImportResult<Item> {
+        let position = self.cursor.position();
+        self.cursor.eat("{");
+        self.cursor.skip_space();
+        let min = self.cursor.take_while(|c| c.is_ascii_digit());
+        self.cursor.skip_space();
+        let comma = self.cursor.eat(",");
+        self.cursor.skip_space();
+        let max = self.cursor.take_while(|c| c.is_ascii_digit());
+        self.cursor.skip_space();
+        let fail = ImportError::new(FrontendError::ExpectedRepetition, position.0, position.1);
+        if !self.cursor.eat("}") || (min.is_empty() && max.is_empty()) {
+            return Err(fail);
+        }
+        let min: usize = if min.is_empty() { 0 } else { min.parse().map_err(|_| fail)? };
+        let max: Option<usize> = match (comma, max.is_empty()) {
+            (false, _) => Some(min),
+            (true, true) => None,
+            (true, false) => Some(max.parse().map_err(|_| fail)?),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(fail);
+        }
+        let mut items = vec![item.clone(); min];
+        match max {
+            None if min == 0 => return Ok(quantified(item, Quantifier::Star)),
+            None => {
+                items.pop();
+                items.push(quantified(item, Quantifier::Plus));
+            }
+            Some(max) => {
+                for _ in min..max {
+                    items.push(quantified(item.clone(), Quantifier::Optional));
+                }
+            }
+        }
+        Ok(sequence(items))
+    }
+
+    fn atom(&mut self) -> ImportResult<Item> {
+        self.cursor.skip_space();
+        let position = self.cursor.position();
+        match self.cursor.peek() {
+            Some('(') => {
+                self.cursor.bump();
+                let alternatives = self.alternatives()?;
+                self.cursor.expect(")", FrontendError::ExpectedCloseParen)?;
+                Ok(choice(alternatives))
+            }
+            Some('"') => {
+                let value = self.string('"')?;
+                Ok(Item::new(literal(value)))
+            }
+            Some('^') => {
+                self.cursor.bump();
+                if self.cursor.peek() != Some('"') {
+                    return Err(self.cursor.error(FrontendError::ExpectedLiteral));
+                }
+                let value = self.string('"')?;
+                Ok(insensitive(&value))
+            }
+            Some('\'') => {
+                let from = self.character()?;
+                self.cursor.skip_space();
+                if !self.cursor.eat("..") {
+                    return Ok(Item::new(Symbol::Literal(from.to_string())));
+                }
+                self.cursor.skip_space();
+                let to = self.character()?;
+                match CharClass::range(from, to) {
+                    Ok(class) => Ok(Item::new(Symbol::Class(class))),
+                    Err(error) => Err(ImportError::new(error, position.0, position.1)),
+                }
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.identifier()?;
+                if STACK.contains(&name.as_str()) {
+                    return Err(ImportError::new(FrontendError::UnsupportedStackOperation, position.0, position.1));
+                }
+                if name == "EOI" {
+                    self.end = true;
+                    self.read_end = true;
+                }
+                Ok(Item::new(builtin(&name).unwrap_or(Symbol::Name(name))))
+            }
+            _ => Err(self.cursor.error(FrontendError::ExpectedPart)),
+        }
+    }
+
+    //A single character literal
+    fn character(&mut self) -> ImportResult<char> {
+        let position = self.cursor.position();
+        let value = self.string('\'')?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(ImportError::new(FrontendError::ExpectedLiteral, position.0, position.1)),
+        }
+    }
+
+    //The text of a literal between `quote`s, with its escapes resolved
+    fn string(&mut self, quote: char) -> ImportResult<String> {
+        self.cursor.bump();
+        let mut out = String::new();
+        loop {
+            let position = self.cursor.position();
+            let c = match self.cursor.bump() {
+                Some(c) if c == quote => return Ok(out),
+                Some(c) => c,
+                None => return Err(self.cursor.error(FrontendError::EOI)),
+            };
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            let escaped = match self.cursor.bump() {
+                Some('n') => Some('\n'),
+                Some('r') => Some('\r'),
+                Some('t') => Some('\t'),
+                Some('0') => Some('\0'),
+                Some('x') => {
+                    let hex: String = (0..2).filter_map(|_| self.cursor.bump()).collect();
+                    u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32)
+                }
+                Some('u') => {
+                    let hex = if self.cursor.eat("{") {
+                        let hex = self.cursor.take_while(|c| c.is_ascii_hexdigit());
+                        self.cursor.eat("}");
+                        hex
+                    } else {
+                        String::new()
+                    };
+                    u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32)
+                }
+                Some(c @ '\\') | Some(c @ '"') | Some(c @ '\'') => Some(c),
+                _ => None,
+            };
+            match escaped {
+                Some(c) => out.push(c),
+                None => {
+                    let what = FrontendError::ExpectedEscapeSequence;
+                    return Err(ImportError::new(what, position.0, position.1));
+                }
+            }
+        }
+    }
+}
+
+fn literal(value: String) -> Symbol {
+    if value.is_empty() {
+        Symbol::Epsilon
+    } else {
+        Symbol::Literal(value)
+    }
+}
+
+//^"..." as a class for every letter with two cases and literals for the
+//rest
+fn insensitive(value: &str) -> Item {
+    let mut items = Vec::new();
+    let mut text = String::new();
+    for c in value.chars() {
+        let (mut lower, mut upper) = (c.to_lowercase(), c.to_uppercase());
+        match (lower.next(), lower.next(), upper.next(), upper.next()) {
+            (Some(lower), None, Some(upper), None) if lower != upper => {
+                if !text.is_empty() {
+                    items.push(Item::new(Symbol::Literal(text.clone())));
+                    text.clear();
+                }
+                let class = CharClass {
+                    negated: false,
+                    items: vec![ClassItem::Char(lower), ClassItem::Char(upper)],
+                };
+                items.push(Item::new(Symbol::Class(class)));
+            }
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() || items.is_empty() {
+        items.push(Item::new(literal(text)));
+    }
+    sequence(items)
+}
+
+//The built-in rules of pest Gideon has an equivalent for, SOI and EOI match
+//the empty string
+fn builtin(name: &str) -> Option<Symbol> {
+    let ranges = |ranges: &[(char, char)]| {
+        Symbol::Class(CharClass {
+            negated: false,
+            items: ranges
+                .iter()
+                .map(|&(from, to)| if from == to { ClassItem::Char(from) } else { ClassItem::Range(from, to) })
+                .collect(),
+        })
+    };
+    let property = |name: &str| {
+        Symbol::Class(CharClass {
+            negated: false,
+            items: vec![ClassItem::Property(name.to_string(), false)],
+        })
+    };
+    let symbol = match name {
+        "SOI" | "EOI" => Symbol::Epsilon,
+        "ANY" => property("Any"),
+        "ASCII" => property("ASCII"),
+        "ASCII_DIGIT" => ranges(&[('0', '9')]),
+        "ASCII_NONZERO_DIGIT" => ranges(&[('1', '9')]),
+        "ASCII_BIN_DIGIT" => ranges(&[('0', '1')]),
+        "ASCII_OCT_DIGIT" => ranges(&[('0', '7')]),
+        "ASCII_HEX_DIGIT" => ranges(&[('0', '9'), ('a', 'f'), ('A', 'F')]),
+        "ASCII_ALPHA_LOWER" => ranges(&[('a', 'z')]),
+        "ASCII_ALPHA_UPPER" => ranges(&[('A', 'Z')]),
+        "ASCII_ALPHA" => ranges(&[('a', 'z'), ('A', 'Z')]),
+        "ASCII_ALPHANUMERIC" => ranges(&[('a', 'z'), ('A', 'Z'), ('0', '9')]),
+        "NEWLINE" => Symbol::Group(
+            ["\n", "\r\n", "\r"]
+                .iter()
+                .map(|newline| Alternative {
+                    items: vec![Item::new(Symbol::Literal(newline.to_string()))],
+                    action: None,
+                })
+                .collect(),
+        ),
+        "ALPHABETIC" => property("Alphabetic"),
+        "LOWERCASE" => property("Lowercase"),
+        "UPPERCASE" => property("Uppercase"),
+        "WHITE_SPACE" => property("White_Space"),
+        "NUMBER" => property("N"),
+        "CONTROL" => property("Cc"),
+        _ => return None,
+    };
+    Some(symbol)
+}
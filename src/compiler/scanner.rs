@@ -0,0 +1,489 @@
+//Interpreted lexer for grammars written in Gideon.
+//
+//Terminals are the literals used by the productions and the lexical rules
+//they reference. At each position the longest terminal wins, ties prefer
+//literals over lexical rules and then declaration order. Text matched by a
+//skip declaration is trivia: dropped by default, attached to the following
+//lexeme with Trivia::Attach.
+//
+//The scanner keeps a stack of modes starting with the default mode. Only
+//the terminals and skip declarations of the mode on top are active, literals
+//belong to the default mode, and lexical rules with 'push' or 'pop' change
+//the mode after they match.
+//
+//A grammar with a layout declaration is indentation sensitive. In the
+//default mode spaces, tabs and line breaks are always trivia, and the
+//scanner keeps a stack of indentation columns to synthesize the virtual
+//lexical rules {NEWLINE} at the end of every non blank line, {INDENT} when
+//a line is indented deeper than the previous one and one {DEDENT} per
+//level closed. Tabs count as a single column.
+use super::grammar::*;
+use super::frontend_error::*;
+use super::lexer::Trivia;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
+
+pub type ScanResult<'a> = Result<Lexeme<'a>, FrontendError>;
+
+#[derive(Debug, Clone)]
+pub struct Lexeme<'a> {
+    symbol: Symbol,
+    value: &'a [char],
+    line: usize,
+    offset: usize,
+    trivia: &'a [char],
+}
+
+impl<'a> Lexeme<'a> {
+    pub fn symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    pub fn value(&self) -> &'a [char] {
+        self.value
+    }
+
+    pub fn text(&self) -> String {
+        self.value.iter().collect()
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn trivia(&self) -> &'a [char] {
+        self.trivia
+    }
+}
+
+pub struct Scanner<'a> {
+    grammar: &'a Grammar,
+    input: &'a [char],
+    current: Cell<usize>,
+    line: Cell<usize>,
+    offset: Cell<usize>,
+    mode: Trivia,
+    modes: Vec<String>,
+    //terminals of each mode, indexed like `modes`
+    terminals: Vec<Vec<Symbol>>,
+    stack: RefCell<Vec<usize>>,
+    //end of the last lexeme taken from the input, trivia starts here
+    last_end: Cell<usize>,
+    trailing: Cell<&'a [char]>,
+    //layout state: indentation columns, whether the current line produced
+    //a lexeme, and the position the layout was last computed for
+    indents: RefCell<Vec<usize>>,
+    line_open: Cell<bool>,
+    layout_checked: Cell<Option<usize>>,
+    pending: RefCell<VecDeque<Lexeme<'a>>>,
+    //(lexical rule, position) pairs being matched, guards against left recursion
+    active: RefCell<Vec<(&'a str, usize)>>,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(grammar: &'a Grammar, input: &'a [char]) -> Scanner<'a> {
+        Scanner::with_trivia(grammar, input, Trivia::Skip)
+    }
+
+    pub fn with_trivia(grammar: &'a Grammar, input: &'a [char], mode: Trivia) -> Scanner<'a> {
+        let modes = grammar.modes();
+        let terminals = modes.iter().map(|m| terminals(grammar, m)).collect();
+        Scanner {
+            grammar,
+            input,
+            current: Cell::new(0),
+            line: Cell::new(1),
+            offset: Cell::new(0),
+            mode,
+            modes,
+            terminals,
+            stack: RefCell::new(vec![0]),
+            last_end: Cell::new(0),
+            trailing: Cell::new(&input[..0]),
+            indents: RefCell::new(vec![0]),
+            line_open: Cell::new(false),
+            layout_checked: Cell::new(None),
+            pending: RefCell::new(VecDeque::new()),
+            active: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn current_mode(&self) -> usize {
+        *self.stack.borrow().last().unwrap_or(&0)
+    }
+
+    pub fn mode(&self) -> &str {
+        &self.modes[self.current_mode()]
+    }
+
+    //Terminals of the current mode
+    pub fn terminals(&self) -> &[Symbol] {
+        &self.terminals[self.current_mode()]
+    }
+
+    //Trivia left at the end of input, only kept in Trivia::Attach mode
+    pub fn trailing_trivia(&self) -> &'a [char] {
+        self.trailing.get()
+    }
+
+    //Position of the scanner, used to locate errors
+    pub fn line(&self) -> usize {
+        self.line.get()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset.get()
+    }
+
+    fn layout_active(&self) -> bool {
+        self.grammar.layout && self.current_mode() == 0
+    }
+
+    pub fn next(&self) -> ScanResult<'a> {
+        if let Some(lexeme) = self.pending.borrow_mut().pop_front() {
+            return Ok(lexeme);
+        }
+        self.skip_trivia();
+        let start = self.current.get();
+        let trivia = &self.input[self.last_end.get()..start];
+        if self.layout_active() {
+            self.layout(trivia, start)?;
+            if let Some(lexeme) = self.pending.borrow_mut().pop_front() {
+                return Ok(lexeme);
+            }
+        }
+        if start >= self.input.len() {
+            if self.mode == Trivia::Attach {
+                self.trailing.set(trivia);
+            }
+            return Err(FrontendError::EOI);
+        }
+        let mut best: Option<(usize, &Symbol)> = None;
+        for terminal in self.terminals() {
+            if let Some(end) = self.longest(terminal, start) {
+                let better = match best {
+                    Some((best_end, _)) => end > best_end,
+                    None => end > start,
+                };
+                if better {
+                    best = Some((end, terminal));
+                }
+            }
+        }
+        match best {
+            Some((end, symbol)) => {
+                let lexeme = Lexeme {
+                    symbol: symbol.clone(),
+                    value: &self.input[start..end],
+                    line: self.line.get(),
+                    offset: self.offset.get(),
+                    trivia: match self.mode {
+                        Trivia::Skip => &self.input[..0],
+                        Trivia::Attach => trivia,
+                    },
+                };
+                self.advance(end);
+                self.last_end.set(end);
+                self.line_open.set(true);
+                self.change_mode(symbol)?;
+                Ok(lexeme)
+            }
+            None => Err(FrontendError::UnrecognizedInput),
+        }
+    }
+
+    //Scans the whole input, stopping at the first error
+    pub fn tokenize(&self) -> Result<Vec<Lexeme<'a>>, FrontendError> {
+        let mut out = Vec::new();
+        loop {
+            match self.next() {
+                Ok(lexeme) => out.push(lexeme),
+                Err(FrontendError::EOI) => return Ok(out),
+                Err(what) => return Err(what),
+            }
+        }
+    }
+
+    //Queues the virtual lexemes due before the lexeme at `start`
+    fn layout(&self, trivia: &[char], start: usize) -> Result<(), FrontendError> {
+        if self.layout_checked.get() == Some(start) {
+            return Ok(());
+        }
+        self.layout_checked.set(Some(start));
+        let eof = start >= self.input.len();
+        let first = self.last_end.get() == 0 && !self.line_open.get();
+        if !(first || eof || trivia.contains(&'\n')) {
+            return Ok(());
+        }
+        if self.line_open.get() {
+            self.virtual_lexeme(NEWLINE, start);
+            self.line_open.set(false);
+        }
+        let column = if eof { 0 } else { self.offset.get() };
+        let mut indents = self.indents.borrow_mut();
+        if column > *indents.last().unwrap_or(&0) {
+            indents.push(column);
+            self.virtual_lexeme(INDENT, start);
+        }
+        while column < *indents.last().unwrap_or(&0) {
+            indents.pop();
+            self.virtual_lexeme(DEDENT, start);
+        }
+        if column != *indents.last().unwrap_or(&0) {
+            return Err(FrontendError::InconsistentDedent);
+        }
+        Ok(())
+    }
+
+    fn virtual_lexeme(&self, name: &str, pos: usize) {
+        self.pending.borrow_mut().push_back(Lexeme {
+            symbol: Symbol::Lexical(name.to_string()),
+            value: &self.input[pos..pos],
+            line: self.line.get(),
+            offset: self.offset.get(),
+            trivia: &self.input[..0],
+        });
+    }
+
+    fn change_mode(&self, symbol: &Symbol) -> Result<(), FrontendError> {
+        let rule = match *symbol {
+            Symbol::Lexical(ref name) => self.grammar.lexical_rule(name),
+            _ => None,
+        };
+        match rule.and_then(|rule| rule.action.as_ref()) {
+            Some(ModeAction::Push(mode)) => {
+                let index = self.modes.iter().position(|m| m == mode).unwrap_or(0);
+                self.stack.borrow_mut().push(index);
+            }
+            Some(ModeAction::Pop) => {
+                let mut stack = self.stack.borrow_mut();
+                if stack.len() <= 1 {
+                    return Err(FrontendError::UnbalancedModePop);
+                }
+                stack.pop();
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn skip_trivia(&self) {
+        let mode = self.mode();
+        let layout = self.layout_active();
+        loop {
+            let pos = self.current.get();
+            if layout && pos < self.input.len() {
+                if let ' ' | '\t' | '\r' | '\n' = self.input[pos] {
+                    self.advance(pos + 1);
+                    continue;
+                }
+            }
+            let end = self.grammar
+                .skip
+                .iter()
+                .filter(|skip| skip.in_mode(mode))
+                .flat_map(|skip| skip.alternatives.iter())
+                .filter_map(|alternative| self.longest_sequence(&alternative.items, pos))
+                .max();
+            match end {
+                Some(end) if end > pos => self.advance(end),
+                _ => break,
+            }
+        }
+    }
+
+    fn advance(&self, end: usize) {
+        for c in &self.input[self.current.get()..end] {
+            if *c == '\n' {
+                self.line.set(self.line.get() + 1);
+                self.offset.set(0);
+            } else {
+                self.offset.set(self.offset.get() + 1);
+            }
+        }
+        self.current.set(end);
+    }
+
+    fn longest(&self, symbol: &Symbol, pos: usize) -> Option<usize> {
+        self.match_symbol(symbol, pos).into_iter().max()
+    }
+
+    fn longest_sequence(&self, items: &[Item], pos: usize) -> Option<usize> {
+        self.match_sequence(items, pos).into_iter().max()
+    }
+
+    //Every end position reachable by matching `symbol` at `pos`
+    fn match_symbol(&self, symbol: &Symbol, pos: usize) -> Vec<usize> {
+        match *symbol {
+            Symbol::Literal(ref value) => {
+                let mut end = pos;
+                for c in value.chars() {
+                    if end < self.input.len() && self.input[end] == c {
+                        end += 1;
+                    } else {
+                        return Vec::new();
+                    }
+                }
+                vec![end]
+            }
+            Symbol::Lexical(ref name) => {
+                let rule = match self.grammar.lexical_rule(name) {
+                    Some(rule) => rule,
+                    None => return Vec::new(),
+                };
+                if self.active.borrow().contains(&(rule.name.as_str(), pos)) {
+                    return Vec::new();
+                }
+                self.active.borrow_mut().push((rule.name.as_str(), pos));
+                let mut ends = Vec::new();
+                if rule.nullable {
+                    ends.push(pos);
+                }
+                for alternative in &rule.alternatives {
+                    ends.extend(self.match_sequence(&alternative.items, pos));
+                }
+                self.active.borrow_mut().pop();
+                ends.sort();
+                ends.dedup();
+                ends
+            }
+            Symbol::Class(ref class) => {
+                if pos < self.input.len() && class.matches(self.input[pos]) {
+                    vec![pos + 1]
+                } else {
+                    Vec::new()
+                }
+            }
+            Symbol::Epsilon => vec![pos],
+            Symbol::Group(ref alternatives) => {
+                let mut ends: Vec<usize> = alternatives
+                    .iter()
+                    .flat_map(|alternative| self.match_sequence(&alternative.items, pos))
+                    .collect();
+                ends.sort();
+                ends.dedup();
+                ends
+            }
+            Symbol::And(ref symbol) if !self.match_symbol(symbol, pos).is_empty() => vec![pos],
+            Symbol::Not(ref symbol) if self.match_symbol(symbol, pos).is_empty() => vec![pos],
+            Symbol::And(_) | Symbol::Not(_) => Vec::new(),
+            //productions are not part of the lexical layer
+            Symbol::Name(_) => Vec::new(),
+        }
+    }
+
+    fn match_sequence(&self, items: &[Item], pos: usize) -> Vec<usize> {
+        let mut ends = vec![pos];
+        for item in items {
+            let mut next = Vec::new();
+            for &end in &ends {
+                next.extend(self.match_item(item, end));
+            }
+            next.sort();
+            next.dedup();
+            if next.is_empty() {
+                return next;
+            }
+            ends = next;
+        }
+        ends
+    }
+
+    fn match_item(&self, item: &Item, pos: usize) -> Vec<usize> {
+        let mut ends = self.match_symbol(&item.symbol, pos);
+        if item.quantifier.repeated() {
+            //only the ends not seen before are matched from again
+            let mut seen: HashSet<usize> = ends.iter().cloned().collect();
+            let mut frontier = ends.clone();
+            while !frontier.is_empty() {
+                let mut next = Vec::new();
+                for &end in &frontier {
+                    let starts = match item.separator {
+                        Some(ref separator) => {
+                            let starts = self.match_symbol(&separator.symbol, end);
+                            if separator.trailing {
+                                ends.extend(starts.iter().cloned());
+                            }
+                            starts
+                        }
+                        None => vec![end],
+                    };
+                    for start in starts {
+                        for end in self.match_symbol(&item.symbol, start) {
+                            if seen.insert(end) {
+                                ends.push(end);
+                                next.push(end);
+                            }
+                        }
+                    }
+                }
+                frontier = next;
+            }
+        }
+        if item.nullable() {
+            ends.push(pos);
+        }
+        ends.sort();
+        ends.dedup();
+        ends
+    }
+}
+
+//Literals and character classes of the reachable productions and the
+//lexical rules they reference that are active in `mode`, literals and classes first so
+//they win ties against lexical rules.
+//Lexical rules that name their modes or change mode are terminals even
+//when no production references them.
+fn terminals(grammar: &Grammar, mode: &str) -> Vec<Symbol> {
+    let active = |symbol: &Symbol| match *symbol {
+        Symbol::Literal(_) | Symbol::Class(_) => mode == DEFAULT_MODE,
+        Symbol::Lexical(ref name) => grammar.lexical_rule(name).map_or(
+            mode == DEFAULT_MODE,
+            |rule| rule.in_mode(mode),
+        ),
+        _ => false,
+    };
+    let mut literals = Vec::new();
+    let mut lexical = Vec::new();
+    let mut pending: Vec<&Symbol> = Vec::new();
+    let reachable = grammar.reachable();
+    for prod in grammar.productions.iter().rev() {
+        if reachable.contains(&Symbol::Name(prod.name.clone())) {
+            push_symbols(&mut pending, &prod.alternatives);
+        }
+    }
+    while let Some(symbol) = pending.pop() {
+        let list = match *symbol {
+            Symbol::Literal(ref value) if !value.is_empty() => &mut literals,
+            Symbol::Class(_) => &mut literals,
+            Symbol::Lexical(_) => &mut lexical,
+            //visited next, keeping the order of first use
+            Symbol::Group(ref alternatives) => {
+                push_symbols(&mut pending, alternatives);
+                continue;
+            }
+            Symbol::And(ref symbol) | Symbol::Not(ref symbol) => {
+                pending.push(symbol);
+                continue;
+            }
+            _ => continue,
+        };
+        if active(symbol) && !list.contains(symbol) {
+            list.push(symbol.clone());
+        }
+    }
+    for rule in &grammar.lexical_rules {
+        let symbol = Symbol::Lexical(rule.name.clone());
+        let explicit = !rule.modes.is_empty() || rule.action.is_some();
+        if explicit && rule.in_mode(mode) && !lexical.contains(&symbol) {
+            lexical.push(symbol);
+        }
+    }
+    literals.extend(lexical);
+    literals
+}
@@ -0,0 +1,649 @@
+//Lossless syntax tree of grammar source, in the red/green style.
+//
+//Green nodes and tokens are immutable and know only their kind, their text
+//and their children, so unchanged subtrees can be shared between versions
+//of a document. Red nodes (SyntaxNode, SyntaxToken) are cheap handles on
+//top that add the parent and the absolute position.
+//
+//Every character of the input is owned by exactly one token: the tokens of
+//the concrete syntax tree, Whitespace and Comment tokens for the trivia
+//between them and Error tokens for input the parser dropped. Concatenating
+//the tokens gives back the input, also for input with syntax errors. A
+//zero width Error token marks where the parser recorded an error.
+//
+//Trivia belongs to the innermost node open at the token following it, so
+//the comments ahead of a declaration are part of it. Positions and widths
+//count characters, like the lexer.
+use super::syntax_tree as cst;
+use super::frontend_error::FrontendError;
+use super::parser::{Parser, SyntaxResult};
+use super::lexer::Trivia;
+use super::token::Token;
+use super::visitor::*;
+
+use std::fmt::{Debug, Display, Formatter};
+use std::fmt::Result as FormatResult;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    //nodes
+    Root,
+    Prod,
+    Path,
+    Skip,
+    Layout,
+    Start,
+    Extends,
+    Override,
+    Union,
+    Body,
+    Part,
+
+    //tokens, named after token::Token
+    Name,
+    Arrow,
+    Or,
+    Endl,
+    Epsilon,
+    Literal,
+    Use,
+    PathSeperator,
+    OpenBrace,
+    CloseBrace,
+    QMark,
+    SkipKeyword,
+    LayoutKeyword,
+    StartKeyword,
+    ExtendsKeyword,
+    OverrideKeyword,
+    AppendArrow,
+    Class,
+    Property,
+    Range,
+    OpenParen,
+    CloseParen,
+    Star,
+    Plus,
+    Percent,
+    DoublePercent,
+    OpenAngle,
+    CloseAngle,
+    Comma,
+    Colon,
+    FatArrow,
+    Code,
+    Type,
+    Amp,
+    Bang,
+
+    //trivia and text the parser could not use
+    Whitespace,
+    Comment,
+    Error,
+}
+
+impl SyntaxKind {
+    pub fn is_trivia(self) -> bool {
+        self == SyntaxKind::Whitespace || self == SyntaxKind::Comment
+    }
+}
+
+impl<'a> From<Token<'a>> for SyntaxKind {
+    fn from(token: Token<'a>) -> SyntaxKind {
+        match token {
+            Token::Name(_) => SyntaxKind::Name,
+            Token::Arrow(_) => SyntaxKind::Arrow,
+            Token::Or(_) => SyntaxKind::Or,
+            Token::Endl(_) => SyntaxKind::Endl,
+            Token::Epsilon(_) => SyntaxKind::Epsilon,
+            Token::Literal(_) => SyntaxKind::Literal,
+            Token::Use(_) => SyntaxKind::Use,
+            Token::PathSeperator(_) => SyntaxKind::PathSeperator,
+            Token::OpenBrace(_) => SyntaxKind::OpenBrace,
+            Token::CloseBrace(_) => SyntaxKind::CloseBrace,
+            Token::QMark(_) => SyntaxKind::QMark,
+            Token::Skip(_) => SyntaxKind::SkipKeyword,
+            Token::Layout(_) => SyntaxKind::LayoutKeyword,
+            Token::Start(_) => SyntaxKind::StartKeyword,
+            Token::Extends(_) => SyntaxKind::ExtendsKeyword,
+            Token::Override(_) => SyntaxKind::OverrideKeyword,
+            Token::AppendArrow(_) => SyntaxKind::AppendArrow,
+            Token::Class(_) => SyntaxKind::Class,
+            Token::Property(_) => SyntaxKind::Property,
+            Token::Range(_) => SyntaxKind::Range,
+            Token::OpenParen(_) => SyntaxKind::OpenParen,
+            Token::CloseParen(_) => SyntaxKind::CloseParen,
+            Token::Star(_) => SyntaxKind::Star,
+            Token::Plus(_) => SyntaxKind::Plus,
+            Token::Percent(_) => SyntaxKind::Percent,
+            Token::DoublePercent(_) => SyntaxKind::DoublePercent,
+            Token::OpenAngle(_) => SyntaxKind::OpenAngle,
+            Token::CloseAngle(_) => SyntaxKind::CloseAngle,
+            Token::Comma(_) => SyntaxKind::Comma,
+            Token::Colon(_) => SyntaxKind::Colon,
+            Token::FatArrow(_) => SyntaxKind::FatArrow,
+            Token::Code(_) => SyntaxKind::Code,
+            Token::Type(_) => SyntaxKind::Type,
+            Token::Amp(_) => SyntaxKind::Amp,
+            Token::Bang(_) => SyntaxKind::Bang,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+    width: usize,
+    //what the parser reported, Error tokens only
+    error: Option<FrontendError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    width: usize,
+    children: Vec<GreenElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> GreenToken {
+        GreenToken {
+            kind,
+            text: text.to_string(),
+            width: text.chars().count(),
+            error: None,
+        }
+    }
+
+    pub fn error(what: FrontendError, text: &str) -> GreenToken {
+        GreenToken {
+            error: Some(what),
+            ..GreenToken::new(SyntaxKind::Error, text)
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn reported(&self) -> Option<FrontendError> {
+        self.error
+    }
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        GreenNode {
+            kind,
+            width: children.iter().map(GreenElement::width).sum(),
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    //A copy with the child at `index` replaced, the other children are
+    //shared
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> GreenNode {
+        let mut children = self.children.clone();
+        children[index] = child;
+        GreenNode::new(self.kind, children)
+    }
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match *self {
+            GreenElement::Node(ref node) => node.kind,
+            GreenElement::Token(ref token) => token.kind,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match *self {
+            GreenElement::Node(ref node) => node.width,
+            GreenElement::Token(ref token) => token.width,
+        }
+    }
+}
+
+impl Display for GreenNode {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        for child in &self.children {
+            match *child {
+                GreenElement::Node(ref node) => write!(f, "{}", node)?,
+                GreenElement::Token(ref token) => write!(f, "{}", token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    //position among the children of the parent
+    index: usize,
+    offset: usize,
+}
+
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+
+#[derive(Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> SyntaxNode {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    pub fn index(&self) -> usize {
+        self.0.index
+    }
+
+    //Start and end in the input
+    pub fn range(&self) -> (usize, usize) {
+        (self.0.offset, self.0.offset + self.0.green.width)
+    }
+
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    pub fn children(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::new();
+        for (index, child) in self.0.green.children.iter().enumerate() {
+            children.push(match *child {
+                GreenElement::Node(ref green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    parent: Some(self.clone()),
+                    index,
+                    offset,
+                }))),
+                GreenElement::Token(ref green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    index,
+                    offset,
+                }),
+            });
+            offset += child.width();
+        }
+        children
+    }
+
+    pub fn child_nodes(&self) -> Vec<SyntaxNode> {
+        self.children()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    //The node and the nodes below it, in source order
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut out = Vec::new();
+        let mut pending = vec![self.clone()];
+        while let Some(node) = pending.pop() {
+            let mut children = node.child_nodes();
+            children.reverse();
+            out.push(node);
+            pending.extend(children);
+        }
+        out
+    }
+
+    //Every token below the node, in source order
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut out = Vec::new();
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => out.extend(node.tokens()),
+                SyntaxElement::Token(token) => out.push(token),
+            }
+        }
+        out
+    }
+
+    //The token containing the character at `offset`, the last token when
+    //`offset` is the end of the input
+    pub fn token_at(&self, offset: usize) -> Option<SyntaxToken> {
+        let mut node = self.clone();
+        'descend: loop {
+            let children = node.children();
+            let count = children.len();
+            for (i, child) in children.into_iter().enumerate() {
+                let (start, end) = child.range();
+                let last = i + 1 == count;
+                if start <= offset && (offset < end || last && offset == end) {
+                    match child {
+                        SyntaxElement::Node(child) => {
+                            node = child;
+                            continue 'descend;
+                        }
+                        SyntaxElement::Token(token) => return Some(token),
+                    }
+                }
+            }
+            return None;
+        }
+    }
+
+    //The innermost node covering `start..end`
+    pub fn covering(&self, start: usize, end: usize) -> SyntaxNode {
+        let mut node = self.clone();
+        'descend: loop {
+            for child in node.child_nodes() {
+                let range = child.range();
+                if range.0 <= start && end <= range.1 {
+                    node = child;
+                    continue 'descend;
+                }
+            }
+            return node;
+        }
+    }
+
+    //The nodes from the parent up to the root
+    pub fn ancestors(&self) -> Vec<SyntaxNode> {
+        let mut out = Vec::new();
+        let mut current = self.parent().cloned();
+        while let Some(node) = current {
+            current = node.parent().cloned();
+            out.push(node);
+        }
+        out
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+
+    pub fn green(&self) -> &Rc<GreenToken> {
+        &self.green
+    }
+
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn range(&self) -> (usize, usize) {
+        (self.offset, self.offset + self.green.width)
+    }
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match *self {
+            SyntaxElement::Node(ref node) => node.kind(),
+            SyntaxElement::Token(ref token) => token.kind(),
+        }
+    }
+
+    pub fn range(&self) -> (usize, usize) {
+        match *self {
+            SyntaxElement::Node(ref node) => node.range(),
+            SyntaxElement::Token(ref token) => token.range(),
+        }
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+//One line per node and token with its range, tokens with their text
+impl Debug for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        let mut pending = vec![(0, SyntaxElement::Node(self.clone()))];
+        while let Some((depth, element)) = pending.pop() {
+            let (start, end) = element.range();
+            write!(f, "{:indent$}{:?}@{}..{}", "", element.kind(), start, end, indent = depth * 2)?;
+            match element {
+                SyntaxElement::Node(node) => {
+                    writeln!(f)?;
+                    let children = node.children().into_iter().rev();
+                    pending.extend(children.map(|child| (depth + 1, child)));
+                }
+                SyntaxElement::Token(token) => writeln!(f, " {:?}", token.text())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Debug for SyntaxToken {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        write!(f, "{:?}@{}..{} {:?}", self.kind(), self.offset, self.range().1, self.text())
+    }
+}
+
+//Parses `input` into a lossless tree
+pub fn parse(input: &[char]) -> SyntaxNode {
+    let parser = Parser::with_trivia(input, Trivia::Attach);
+    let tree = parser.parse();
+    SyntaxNode::new_root(Rc::new(build(input, &tree)))
+}
+
+//The lossless tree of `input`, from its concrete syntax tree
+pub fn build(input: &[char], tree: &SyntaxResult<cst::Grammar>) -> GreenNode {
+    let mut builder = Builder {
+        input,
+        cursor: 0,
+        stack: vec![(SyntaxKind::Root, Vec::new())],
+    };
+    match *tree {
+        Ok(ref tree) => builder.visit_grammar(tree),
+        Err(what) => builder.visit_error(what),
+    }
+    builder.gap(input.len());
+    let (kind, children) = builder.stack.pop().expect("root is never finished early");
+    GreenNode::new(kind, children)
+}
+
+struct Builder<'i> {
+    input: &'i [char],
+    //start of the input not owned by a token yet
+    cursor: usize,
+    //open nodes and their children so far
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>,
+}
+
+impl<'i> Builder<'i> {
+    fn start(&mut self, kind: SyntaxKind) {
+        self.stack.push((kind, Vec::new()));
+    }
+
+    fn finish(&mut self) {
+        let (kind, children) = self.stack.pop().expect("finish without start");
+        let node = GreenElement::Node(Rc::new(GreenNode::new(kind, children)));
+        self.push(node);
+    }
+
+    fn push(&mut self, element: GreenElement) {
+        self.stack.last_mut().expect("root is open").1.push(element);
+    }
+
+    fn token(&mut self, kind: SyntaxKind, start: usize, end: usize) {
+        let text: String = self.input[start..end].iter().collect();
+        self.push(GreenElement::Token(Rc::new(GreenToken::new(kind, &text))));
+    }
+
+    //Splits the text up to `end` into whitespace, comments and runs of
+    //other characters, which the parser dropped
+    fn gap(&mut self, end: usize) {
+        while self.cursor < end {
+            let start = self.cursor;
+            let first = self.input[start];
+            let kind = if first.is_whitespace() {
+                SyntaxKind::Whitespace
+            } else if first == '#' {
+                SyntaxKind::Comment
+            } else {
+                SyntaxKind::Error
+            };
+            let mut current = start + 1;
+            while current < end {
+                let c = self.input[current];
+                let more = match kind {
+                    SyntaxKind::Whitespace => c.is_whitespace(),
+                    SyntaxKind::Comment => c != '\n',
+                    _ => !c.is_whitespace() && c != '#',
+                };
+                if !more {
+                    break;
+                }
+                current += 1;
+            }
+            self.token(kind, start, current);
+            self.cursor = current;
+        }
+    }
+}
+
+impl<'a, 'i> Visitor<'a> for Builder<'i> {
+    fn visit_prod(&mut self, prod: &cst::Prod<'a>) {
+        self.start(SyntaxKind::Prod);
+        walk_prod(self, prod);
+        self.finish();
+    }
+
+    fn visit_path(&mut self, path: &cst::Path<'a>) {
+        self.start(SyntaxKind::Path);
+        walk_path(self, path);
+        self.finish();
+    }
+
+    fn visit_skip(&mut self, skip: &cst::Skip<'a>) {
+        self.start(SyntaxKind::Skip);
+        walk_skip(self, skip);
+        self.finish();
+    }
+
+    fn visit_layout(&mut self, layout: &cst::Layout<'a>) {
+        self.start(SyntaxKind::Layout);
+        walk_layout(self, layout);
+        self.finish();
+    }
+
+    fn visit_start(&mut self, start: &cst::Start<'a>) {
+        self.start(SyntaxKind::Start);
+        walk_start(self, start);
+        self.finish();
+    }
+
+    fn visit_extends(&mut self, extends: &cst::Extends<'a>) {
+        self.start(SyntaxKind::Extends);
+        walk_extends(self, extends);
+        self.finish();
+    }
+
+    fn visit_override(&mut self, decl: &cst::Override<'a>) {
+        self.start(SyntaxKind::Override);
+        walk_override(self, decl);
+        self.finish();
+    }
+
+    fn visit_union(&mut self, union: &cst::Union<'a>) {
+        self.start(SyntaxKind::Union);
+        walk_union(self, union);
+        self.finish();
+    }
+
+    fn visit_body(&mut self, body: &cst::Body<'a>) {
+        self.start(SyntaxKind::Body);
+        walk_body(self, body);
+        self.finish();
+    }
+
+    fn visit_part(&mut self, part: &cst::Part<'a>) {
+        self.start(SyntaxKind::Part);
+        walk_part(self, part);
+        self.finish();
+    }
+
+    fn visit_token(&mut self, token: Token<'a>) {
+        let data = token.data();
+        //a token the parser put back and took again is owned already
+        if data.start() < self.cursor {
+            return;
+        }
+        self.gap(data.start());
+        self.token(SyntaxKind::from(token), data.start(), data.end());
+        self.cursor = data.end();
+    }
+
+    fn visit_error(&mut self, error: FrontendError) {
+        self.push(GreenElement::Token(Rc::new(GreenToken::error(error, ""))));
+    }
+}
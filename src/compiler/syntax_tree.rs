@@ -1,21 +1,27 @@
-#![allow(dead_code)]
-
 use super::parser::SyntaxResult;
 use super::lexer::LexicalResult;
 
 pub type Recursive<T> = Box<Option<T>>;
 
 
+#[allow(clippy::large_enum_variant, clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum Grammar<'a> {
     ProdDecl(SyntaxResult<Prod<'a>>, Recursive<SyntaxResult<Grammar<'a>>>),
     PathDecl(SyntaxResult<Path<'a>>, Recursive<SyntaxResult<Grammar<'a>>>),
+    SkipDecl(SyntaxResult<Skip<'a>>, Recursive<SyntaxResult<Grammar<'a>>>),
+    LayoutDecl(SyntaxResult<Layout<'a>>, Recursive<SyntaxResult<Grammar<'a>>>),
+    StartDecl(SyntaxResult<Start<'a>>, Recursive<SyntaxResult<Grammar<'a>>>),
+    ExtendsDecl(SyntaxResult<Extends<'a>>, Recursive<SyntaxResult<Grammar<'a>>>),
+    OverrideDecl(SyntaxResult<Override<'a>>, Recursive<SyntaxResult<Grammar<'a>>>),
 }
 
 #[derive(Debug)]
 pub struct Prod<'a> {
-    name: LexicalResult<'a>,
+    name: SyntaxResult<ProdName<'a>>,
+    modifiers: Recursive<SyntaxResult<Modifiers<'a>>>,
     nullable: SyntaxResult<ONullable<'a>>,
+    rtype: SyntaxResult<OResultType<'a>>,
     arrow: LexicalResult<'a>,
     union: SyntaxResult<Union<'a>>,
     endl: LexicalResult<'a>,
@@ -23,37 +29,315 @@ pub struct Prod<'a> {
 
 impl<'a> Prod<'a> {
     pub fn new(
-        name: LexicalResult<'a>,
+        name: SyntaxResult<ProdName<'a>>,
+        modifiers: Recursive<SyntaxResult<Modifiers<'a>>>,
         nullable: SyntaxResult<ONullable<'a>>,
+        rtype: SyntaxResult<OResultType<'a>>,
         arrow: LexicalResult<'a>,
         union: SyntaxResult<Union<'a>>,
         endl: LexicalResult<'a>,
     ) -> Self {
         Prod {
-            name: name,
-            nullable: nullable,
-            arrow: arrow,
-            union: union,
-            endl: endl,
+            name,
+            modifiers,
+            nullable,
+            rtype,
+            arrow,
+            union,
+            endl,
         }
     }
+
+    pub fn name(&self) -> &SyntaxResult<ProdName<'a>> {
+        &self.name
+    }
+
+    pub fn modifiers(&self) -> Option<&SyntaxResult<Modifiers<'a>>> {
+        self.modifiers.as_ref().as_ref()
+    }
+
+    pub fn nullable(&self) -> &SyntaxResult<ONullable<'a>> {
+        &self.nullable
+    }
+
+    pub fn rtype(&self) -> &SyntaxResult<OResultType<'a>> {
+        &self.rtype
+    }
+
+    pub fn arrow(&self) -> LexicalResult<'a> {
+        self.arrow
+    }
+
+    pub fn union(&self) -> &SyntaxResult<Union<'a>> {
+        &self.union
+    }
+
+    pub fn endl(&self) -> LexicalResult<'a> {
+        self.endl
+    }
+}
+
+#[derive(Debug)]
+pub enum ProdName<'a> {
+    Name(LexicalResult<'a>, Recursive<SyntaxResult<Params<'a>>>),
+    LexicalRuleName(LexicalResult<'a>, LexicalResult<'a>, LexicalResult<'a>),
+}
+
+//"<" {NAME} ParamList ">" after the name of a parameterized production
+#[derive(Debug)]
+pub struct Params<'a> {
+    oangle: LexicalResult<'a>,
+    name: LexicalResult<'a>,
+    list: Recursive<SyntaxResult<ParamList<'a>>>,
+    cangle: LexicalResult<'a>,
+}
+
+impl<'a> Params<'a> {
+    pub fn new(
+        oangle: LexicalResult<'a>,
+        name: LexicalResult<'a>,
+        list: Recursive<SyntaxResult<ParamList<'a>>>,
+        cangle: LexicalResult<'a>,
+    ) -> Self {
+        Params {
+            oangle,
+            name,
+            list,
+            cangle,
+        }
+    }
+
+    pub fn oangle(&self) -> LexicalResult<'a> {
+        self.oangle
+    }
+
+    pub fn name(&self) -> LexicalResult<'a> {
+        self.name
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<ParamList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
+
+    pub fn cangle(&self) -> LexicalResult<'a> {
+        self.cangle
+    }
+}
+
+#[derive(Debug)]
+pub struct ParamList<'a> {
+    comma: LexicalResult<'a>,
+    name: LexicalResult<'a>,
+    list: Recursive<SyntaxResult<ParamList<'a>>>,
+}
+
+impl<'a> ParamList<'a> {
+    pub fn new(
+        comma: LexicalResult<'a>,
+        name: LexicalResult<'a>,
+        list: Recursive<SyntaxResult<ParamList<'a>>>,
+    ) -> Self {
+        ParamList { comma, name, list }
+    }
+
+    pub fn comma(&self) -> LexicalResult<'a> {
+        self.comma
+    }
+
+    pub fn name(&self) -> LexicalResult<'a> {
+        self.name
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<ParamList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
+}
+
+//"<" Union ArgList ">" after a use of a parameterized production
+#[derive(Debug)]
+pub struct Args<'a> {
+    oangle: LexicalResult<'a>,
+    union: SyntaxResult<Union<'a>>,
+    list: Recursive<SyntaxResult<ArgList<'a>>>,
+    cangle: LexicalResult<'a>,
+}
+
+impl<'a> Args<'a> {
+    pub fn new(
+        oangle: LexicalResult<'a>,
+        union: SyntaxResult<Union<'a>>,
+        list: Recursive<SyntaxResult<ArgList<'a>>>,
+        cangle: LexicalResult<'a>,
+    ) -> Self {
+        Args {
+            oangle,
+            union,
+            list,
+            cangle,
+        }
+    }
+
+    pub fn oangle(&self) -> LexicalResult<'a> {
+        self.oangle
+    }
+
+    pub fn union(&self) -> &SyntaxResult<Union<'a>> {
+        &self.union
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<ArgList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
+
+    pub fn cangle(&self) -> LexicalResult<'a> {
+        self.cangle
+    }
+}
+
+#[derive(Debug)]
+pub struct ArgList<'a> {
+    comma: LexicalResult<'a>,
+    union: SyntaxResult<Union<'a>>,
+    list: Recursive<SyntaxResult<ArgList<'a>>>,
+}
+
+impl<'a> ArgList<'a> {
+    pub fn new(
+        comma: LexicalResult<'a>,
+        union: SyntaxResult<Union<'a>>,
+        list: Recursive<SyntaxResult<ArgList<'a>>>,
+    ) -> Self {
+        ArgList { comma, union, list }
+    }
+
+    pub fn comma(&self) -> LexicalResult<'a> {
+        self.comma
+    }
+
+    pub fn union(&self) -> &SyntaxResult<Union<'a>> {
+        &self.union
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<ArgList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
+}
+
+#[derive(Debug)]
+pub struct Modifiers<'a> {
+    modifier: SyntaxResult<Modifier<'a>>,
+    rest: Recursive<SyntaxResult<Modifiers<'a>>>,
+}
+
+impl<'a> Modifiers<'a> {
+    pub fn new(
+        modifier: SyntaxResult<Modifier<'a>>,
+        rest: Recursive<SyntaxResult<Modifiers<'a>>>,
+    ) -> Self {
+        Modifiers { modifier, rest }
+    }
+
+    pub fn modifier(&self) -> &SyntaxResult<Modifier<'a>> {
+        &self.modifier
+    }
+
+    pub fn rest(&self) -> Option<&SyntaxResult<Modifiers<'a>>> {
+        self.rest.as_ref().as_ref()
+    }
+}
+
+#[derive(Debug)]
+pub enum Modifier<'a> {
+    In(LexicalResult<'a>, LexicalResult<'a>, Recursive<SyntaxResult<ModeList<'a>>>),
+    Push(LexicalResult<'a>, LexicalResult<'a>),
+    Pop(LexicalResult<'a>),
+}
+
+#[derive(Debug)]
+pub struct ModeList<'a> {
+    or: LexicalResult<'a>,
+    name: LexicalResult<'a>,
+    list: Recursive<SyntaxResult<ModeList<'a>>>,
+}
+
+impl<'a> ModeList<'a> {
+    pub fn new(
+        or: LexicalResult<'a>,
+        name: LexicalResult<'a>,
+        list: Recursive<SyntaxResult<ModeList<'a>>>,
+    ) -> Self {
+        ModeList { or, name, list }
+    }
+
+    pub fn or(&self) -> LexicalResult<'a> {
+        self.or
+    }
+
+    pub fn name(&self) -> LexicalResult<'a> {
+        self.name
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<ModeList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
 }
 
 #[derive(Debug)]
 pub struct Union<'a> {
     body: SyntaxResult<Body<'a>>,
+    action: SyntaxResult<OAction<'a>>,
     obody: Recursive<SyntaxResult<OBody<'a>>>,
 }
 
 impl<'a> Union<'a> {
-    pub fn new(body: SyntaxResult<Body<'a>>, obody: Recursive<SyntaxResult<OBody<'a>>>) -> Self {
+    pub fn new(
+        body: SyntaxResult<Body<'a>>,
+        action: SyntaxResult<OAction<'a>>,
+        obody: Recursive<SyntaxResult<OBody<'a>>>,
+    ) -> Self {
         Union {
-            body: body,
-            obody: obody,
+            body,
+            action,
+            obody,
         }
     }
+
+    pub fn body(&self) -> &SyntaxResult<Body<'a>> {
+        &self.body
+    }
+
+    pub fn action(&self) -> &SyntaxResult<OAction<'a>> {
+        &self.action
+    }
+
+    pub fn obody(&self) -> Option<&SyntaxResult<OBody<'a>>> {
+        self.obody.as_ref().as_ref()
+    }
+}
+
+//"=>" followed by a block of Rust code
+#[derive(Debug)]
+pub struct Action<'a> {
+    arrow: LexicalResult<'a>,
+    code: LexicalResult<'a>,
+}
+
+impl<'a> Action<'a> {
+    pub fn new(arrow: LexicalResult<'a>, code: LexicalResult<'a>) -> Self {
+        Action { arrow, code }
+    }
+
+    pub fn arrow(&self) -> LexicalResult<'a> {
+        self.arrow
+    }
+
+    pub fn code(&self) -> LexicalResult<'a> {
+        self.code
+    }
 }
 
+pub type OAction<'a> = Option<SyntaxResult<Action<'a>>>;
+
 #[derive(Debug)]
 pub struct OBody<'a> {
     or: LexicalResult<'a>,
@@ -63,39 +347,74 @@ pub struct OBody<'a> {
 impl<'a> OBody<'a> {
     pub fn new(or: LexicalResult<'a>, union: SyntaxResult<Union<'a>>) -> Self {
         OBody {
-            or: or,
-            union: union,
+            or,
+            union,
         }
     }
+
+    pub fn or(&self) -> LexicalResult<'a> {
+        self.or
+    }
+
+    pub fn union(&self) -> &SyntaxResult<Union<'a>> {
+        &self.union
+    }
 }
 
 #[derive(Debug)]
 pub struct Body<'a> {
     part: SyntaxResult<Part<'a>>,
-    nullable: SyntaxResult<ONullable<'a>>,
+    quantifier: SyntaxResult<OQuantifier<'a>>,
+    separator: SyntaxResult<OSeparator<'a>>,
     rbody: Recursive<SyntaxResult<Body<'a>>>,
 }
 
 impl<'a> Body<'a> {
     pub fn new(
         part: SyntaxResult<Part<'a>>,
-        nullable: SyntaxResult<ONullable<'a>>,
+        quantifier: SyntaxResult<OQuantifier<'a>>,
+        separator: SyntaxResult<OSeparator<'a>>,
         rbody: Recursive<SyntaxResult<Body<'a>>>,
     ) -> Self {
         Body {
-            part: part,
-            nullable: nullable,
-            rbody: rbody,
+            part,
+            quantifier,
+            separator,
+            rbody,
         }
     }
+
+    pub fn part(&self) -> &SyntaxResult<Part<'a>> {
+        &self.part
+    }
+
+    pub fn quantifier(&self) -> &SyntaxResult<OQuantifier<'a>> {
+        &self.quantifier
+    }
+
+    pub fn separator(&self) -> &SyntaxResult<OSeparator<'a>> {
+        &self.separator
+    }
+
+    pub fn rbody(&self) -> Option<&SyntaxResult<Body<'a>>> {
+        self.rbody.as_ref().as_ref()
+    }
 }
 
 #[derive(Debug)]
 pub enum Part<'a> {
     Literal(LexicalResult<'a>),
+    Range(LexicalResult<'a>, LexicalResult<'a>, LexicalResult<'a>),
+    Class(LexicalResult<'a>),
+    Property(LexicalResult<'a>),
     LexicalRuleName(LexicalResult<'a>, LexicalResult<'a>, LexicalResult<'a>),
-    Name(LexicalResult<'a>),
+    Name(LexicalResult<'a>, Recursive<SyntaxResult<Args<'a>>>),
     Epsilon(LexicalResult<'a>),
+    Group(LexicalResult<'a>, Box<SyntaxResult<Union<'a>>>, LexicalResult<'a>),
+    Labeled(LexicalResult<'a>, LexicalResult<'a>, Box<SyntaxResult<Part<'a>>>),
+    //lookahead predicates, they match without consuming input
+    And(LexicalResult<'a>, Box<SyntaxResult<Part<'a>>>),
+    Not(LexicalResult<'a>, Box<SyntaxResult<Part<'a>>>),
 }
 
 #[derive(Debug)]
@@ -105,12 +424,80 @@ pub struct Nullable<'a> {
 
 impl<'a> Nullable<'a> {
     pub fn new(qmark: LexicalResult<'a>) -> Self {
-        Nullable { qmark: qmark }
+        Nullable { qmark }
+    }
+
+    pub fn qmark(&self) -> LexicalResult<'a> {
+        self.qmark
     }
 }
 
 pub type ONullable<'a> = Option<SyntaxResult<Nullable<'a>>>;
 
+//":" and the type of the values built by the actions of a production
+#[derive(Debug)]
+pub struct ResultType<'a> {
+    colon: LexicalResult<'a>,
+    rtype: LexicalResult<'a>,
+}
+
+impl<'a> ResultType<'a> {
+    pub fn new(colon: LexicalResult<'a>, rtype: LexicalResult<'a>) -> Self {
+        ResultType { colon, rtype }
+    }
+
+    pub fn colon(&self) -> LexicalResult<'a> {
+        self.colon
+    }
+
+    pub fn rtype(&self) -> LexicalResult<'a> {
+        self.rtype
+    }
+}
+
+pub type OResultType<'a> = Option<SyntaxResult<ResultType<'a>>>;
+
+//"?", "*" or "+" after a part
+#[derive(Debug)]
+pub struct Quantifier<'a> {
+    op: LexicalResult<'a>,
+}
+
+impl<'a> Quantifier<'a> {
+    pub fn new(op: LexicalResult<'a>) -> Self {
+        Quantifier { op }
+    }
+
+    pub fn op(&self) -> LexicalResult<'a> {
+        self.op
+    }
+}
+
+pub type OQuantifier<'a> = Option<SyntaxResult<Quantifier<'a>>>;
+
+//"%" or "%%" and the separator part of a separated list
+#[derive(Debug)]
+pub struct Separator<'a> {
+    op: LexicalResult<'a>,
+    part: SyntaxResult<Part<'a>>,
+}
+
+impl<'a> Separator<'a> {
+    pub fn new(op: LexicalResult<'a>, part: SyntaxResult<Part<'a>>) -> Self {
+        Separator { op, part }
+    }
+
+    pub fn op(&self) -> LexicalResult<'a> {
+        self.op
+    }
+
+    pub fn part(&self) -> &SyntaxResult<Part<'a>> {
+        &self.part
+    }
+}
+
+pub type OSeparator<'a> = Option<SyntaxResult<Separator<'a>>>;
+
 
 #[derive(Debug)]
 pub struct Path<'a> {
@@ -128,12 +515,28 @@ impl<'a> Path<'a> {
         endl: LexicalResult<'a>,
     ) -> Self {
         Path {
-            kuse: kuse,
-            name: name,
-            list: list,
-            endl: endl,
+            kuse,
+            name,
+            list,
+            endl,
         }
     }
+
+    pub fn kuse(&self) -> LexicalResult<'a> {
+        self.kuse
+    }
+
+    pub fn name(&self) -> LexicalResult<'a> {
+        self.name
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<PathItemList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
+
+    pub fn endl(&self) -> LexicalResult<'a> {
+        self.endl
+    }
 }
 
 #[derive(Debug)]
@@ -150,9 +553,210 @@ impl<'a> PathItemList<'a> {
         list: Recursive<SyntaxResult<PathItemList<'a>>>,
     ) -> Self {
         PathItemList {
-            pathsep: pathsep,
-            name: name,
-            list: list,
+            pathsep,
+            name,
+            list,
+        }
+    }
+
+    pub fn pathsep(&self) -> LexicalResult<'a> {
+        self.pathsep
+    }
+
+    pub fn name(&self) -> LexicalResult<'a> {
+        self.name
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<PathItemList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
+}
+
+#[derive(Debug)]
+pub struct Extends<'a> {
+    kextends: LexicalResult<'a>,
+    name: LexicalResult<'a>,
+    list: Recursive<SyntaxResult<PathItemList<'a>>>,
+    endl: LexicalResult<'a>,
+}
+
+impl<'a> Extends<'a> {
+    pub fn new(
+        kextends: LexicalResult<'a>,
+        name: LexicalResult<'a>,
+        list: Recursive<SyntaxResult<PathItemList<'a>>>,
+        endl: LexicalResult<'a>,
+    ) -> Self {
+        Extends {
+            kextends,
+            name,
+            list,
+            endl,
         }
     }
+
+    pub fn kextends(&self) -> LexicalResult<'a> {
+        self.kextends
+    }
+
+    pub fn name(&self) -> LexicalResult<'a> {
+        self.name
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<PathItemList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
+
+    pub fn endl(&self) -> LexicalResult<'a> {
+        self.endl
+    }
+}
+
+#[derive(Debug)]
+pub struct Override<'a> {
+    koverride: LexicalResult<'a>,
+    prod: SyntaxResult<Prod<'a>>,
+}
+
+impl<'a> Override<'a> {
+    pub fn new(koverride: LexicalResult<'a>, prod: SyntaxResult<Prod<'a>>) -> Self {
+        Override { koverride, prod }
+    }
+
+    pub fn koverride(&self) -> LexicalResult<'a> {
+        self.koverride
+    }
+
+    pub fn prod(&self) -> &SyntaxResult<Prod<'a>> {
+        &self.prod
+    }
+}
+
+#[derive(Debug)]
+pub struct Skip<'a> {
+    kskip: LexicalResult<'a>,
+    modifiers: Recursive<SyntaxResult<Modifiers<'a>>>,
+    union: SyntaxResult<Union<'a>>,
+    endl: LexicalResult<'a>,
+}
+
+impl<'a> Skip<'a> {
+    pub fn new(
+        kskip: LexicalResult<'a>,
+        modifiers: Recursive<SyntaxResult<Modifiers<'a>>>,
+        union: SyntaxResult<Union<'a>>,
+        endl: LexicalResult<'a>,
+    ) -> Self {
+        Skip {
+            kskip,
+            modifiers,
+            union,
+            endl,
+        }
+    }
+
+    pub fn kskip(&self) -> LexicalResult<'a> {
+        self.kskip
+    }
+
+    pub fn modifiers(&self) -> Option<&SyntaxResult<Modifiers<'a>>> {
+        self.modifiers.as_ref().as_ref()
+    }
+
+    pub fn union(&self) -> &SyntaxResult<Union<'a>> {
+        &self.union
+    }
+
+    pub fn endl(&self) -> LexicalResult<'a> {
+        self.endl
+    }
+}
+
+#[derive(Debug)]
+pub struct Layout<'a> {
+    klayout: LexicalResult<'a>,
+    endl: LexicalResult<'a>,
+}
+
+impl<'a> Layout<'a> {
+    pub fn new(klayout: LexicalResult<'a>, endl: LexicalResult<'a>) -> Self {
+        Layout { klayout, endl }
+    }
+
+    pub fn klayout(&self) -> LexicalResult<'a> {
+        self.klayout
+    }
+
+    pub fn endl(&self) -> LexicalResult<'a> {
+        self.endl
+    }
+}
+
+#[derive(Debug)]
+pub struct Start<'a> {
+    kstart: LexicalResult<'a>,
+    name: LexicalResult<'a>,
+    list: Recursive<SyntaxResult<StartList<'a>>>,
+    endl: LexicalResult<'a>,
+}
+
+impl<'a> Start<'a> {
+    pub fn new(
+        kstart: LexicalResult<'a>,
+        name: LexicalResult<'a>,
+        list: Recursive<SyntaxResult<StartList<'a>>>,
+        endl: LexicalResult<'a>,
+    ) -> Self {
+        Start {
+            kstart,
+            name,
+            list,
+            endl,
+        }
+    }
+
+    pub fn kstart(&self) -> LexicalResult<'a> {
+        self.kstart
+    }
+
+    pub fn name(&self) -> LexicalResult<'a> {
+        self.name
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<StartList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
+
+    pub fn endl(&self) -> LexicalResult<'a> {
+        self.endl
+    }
+}
+
+#[derive(Debug)]
+pub struct StartList<'a> {
+    comma: LexicalResult<'a>,
+    name: LexicalResult<'a>,
+    list: Recursive<SyntaxResult<StartList<'a>>>,
+}
+
+impl<'a> StartList<'a> {
+    pub fn new(
+        comma: LexicalResult<'a>,
+        name: LexicalResult<'a>,
+        list: Recursive<SyntaxResult<StartList<'a>>>,
+    ) -> Self {
+        StartList { comma, name, list }
+    }
+
+    pub fn comma(&self) -> LexicalResult<'a> {
+        self.comma
+    }
+
+    pub fn name(&self) -> LexicalResult<'a> {
+        self.name
+    }
+
+    pub fn list(&self) -> Option<&SyntaxResult<StartList<'a>>> {
+        self.list.as_ref().as_ref()
+    }
 }
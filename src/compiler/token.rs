@@ -14,21 +14,81 @@ pub enum Token<'a> {
     OpenBrace(TokenData<'a>),
     CloseBrace(TokenData<'a>),
     QMark(TokenData<'a>),
+    Skip(TokenData<'a>),
+    Layout(TokenData<'a>),
+    Start(TokenData<'a>),
+    Extends(TokenData<'a>),
+    Override(TokenData<'a>),
+    AppendArrow(TokenData<'a>),
+    Class(TokenData<'a>),
+    Property(TokenData<'a>),
+    Range(TokenData<'a>),
+    OpenParen(TokenData<'a>),
+    CloseParen(TokenData<'a>),
+    Star(TokenData<'a>),
+    Plus(TokenData<'a>),
+    Percent(TokenData<'a>),
+    DoublePercent(TokenData<'a>),
+    OpenAngle(TokenData<'a>),
+    CloseAngle(TokenData<'a>),
+    Comma(TokenData<'a>),
+    Colon(TokenData<'a>),
+    FatArrow(TokenData<'a>),
+    Code(TokenData<'a>),
+    Type(TokenData<'a>),
+    Amp(TokenData<'a>),
+    Bang(TokenData<'a>),
 }
 
 impl<'a> Token<'a> {
     pub fn clean(self) -> Self {
         match self {
-            Token::Literal(data) => {
-                Token::Literal(TokenData::new(
-                    &data.value[1..data.value.len() - 1],
-                    data.line,
-                    data.offset,
-                ))
-            }
+            Token::Literal(data) => Token::Literal(data.strip_delimiters()),
+            Token::Class(data) => Token::Class(data.strip_delimiters()),
+            Token::Code(data) => Token::Code(data.strip_delimiters()),
             _ => self,
         }
     }
+
+    pub fn data(&self) -> TokenData<'a> {
+        match *self {
+            Token::Name(data)
+            | Token::Arrow(data)
+            | Token::Or(data)
+            | Token::Endl(data)
+            | Token::Epsilon(data)
+            | Token::Literal(data)
+            | Token::Use(data)
+            | Token::PathSeperator(data)
+            | Token::OpenBrace(data)
+            | Token::CloseBrace(data)
+            | Token::QMark(data)
+            | Token::Skip(data)
+            | Token::Layout(data)
+            | Token::Start(data)
+            | Token::Extends(data)
+            | Token::Override(data)
+            | Token::AppendArrow(data)
+            | Token::Class(data)
+            | Token::Property(data)
+            | Token::Range(data)
+            | Token::OpenParen(data)
+            | Token::CloseParen(data)
+            | Token::Star(data)
+            | Token::Plus(data)
+            | Token::Percent(data)
+            | Token::DoublePercent(data)
+            | Token::OpenAngle(data)
+            | Token::CloseAngle(data)
+            | Token::Comma(data)
+            | Token::Colon(data)
+            | Token::FatArrow(data)
+            | Token::Code(data)
+            | Token::Type(data)
+            | Token::Amp(data)
+            | Token::Bang(data) => data,
+        }
+    }
 }
 
 impl<'a> Display for Token<'a> {
@@ -45,6 +105,30 @@ impl<'a> Display for Token<'a> {
             Token::OpenBrace(ref data) => write!(f, "Open Brace: {}", data),
             Token::CloseBrace(ref data) => write!(f, "Close Brace: {}", data),
             Token::QMark(ref data) => write!(f, "Question Mark: {}", data),
+            Token::Skip(ref data) => write!(f, "Skip: {}", data),
+            Token::Layout(ref data) => write!(f, "Layout: {}", data),
+            Token::Start(ref data) => write!(f, "Start: {}", data),
+            Token::Extends(ref data) => write!(f, "Extends: {}", data),
+            Token::Override(ref data) => write!(f, "Override: {}", data),
+            Token::AppendArrow(ref data) => write!(f, "AppendArrow: {}", data),
+            Token::Class(ref data) => write!(f, "Class: {}", data),
+            Token::Property(ref data) => write!(f, "Property: {}", data),
+            Token::Range(ref data) => write!(f, "Range: {}", data),
+            Token::OpenParen(ref data) => write!(f, "Open Paren: {}", data),
+            Token::CloseParen(ref data) => write!(f, "Close Paren: {}", data),
+            Token::Star(ref data) => write!(f, "Star: {}", data),
+            Token::Plus(ref data) => write!(f, "Plus: {}", data),
+            Token::Percent(ref data) => write!(f, "Percent: {}", data),
+            Token::DoublePercent(ref data) => write!(f, "Double Percent: {}", data),
+            Token::OpenAngle(ref data) => write!(f, "Open Angle: {}", data),
+            Token::CloseAngle(ref data) => write!(f, "Close Angle: {}", data),
+            Token::Comma(ref data) => write!(f, "Comma: {}", data),
+            Token::Colon(ref data) => write!(f, "Colon: {}", data),
+            Token::FatArrow(ref data) => write!(f, "Fat Arrow: {}", data),
+            Token::Code(ref data) => write!(f, "Code: {}", data),
+            Token::Type(ref data) => write!(f, "Type: {}", data),
+            Token::Amp(ref data) => write!(f, "Ampersand: {}", data),
+            Token::Bang(ref data) => write!(f, "Bang: {}", data),
         }
     }
 }
@@ -54,16 +138,68 @@ pub struct TokenData<'a> {
     value: &'a [char],
     line: usize,
     offset: usize,
+    //whitespace and comments preceding the token, only kept in Trivia::Attach mode
+    trivia: &'a [char],
+    //indices of the token in the input, including stripped delimiters
+    start: usize,
+    end: usize,
 }
 
 impl<'a> TokenData<'a> {
     pub fn new(value: &'a [char], line: usize, offset: usize) -> TokenData<'a> {
         TokenData {
-            value: value,
-            line: line,
-            offset: offset,
+            value,
+            line,
+            offset,
+            trivia: &[],
+            start: 0,
+            end: 0,
         }
     }
+
+    //Drops the opening and closing character
+    fn strip_delimiters(self) -> TokenData<'a> {
+        TokenData {
+            value: &self.value[1..self.value.len() - 1],
+            ..self
+        }
+    }
+
+    pub fn with_trivia(self, trivia: &'a [char]) -> TokenData<'a> {
+        TokenData { trivia, ..self }
+    }
+
+    pub fn with_span(self, start: usize, end: usize) -> TokenData<'a> {
+        TokenData { start, end, ..self }
+    }
+
+    pub fn value(&self) -> &'a [char] {
+        self.value
+    }
+
+    pub fn text(&self) -> String {
+        self.value.iter().collect()
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn trivia(&self) -> &'a [char] {
+        self.trivia
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
 }
 
 impl<'a> Display for TokenData<'a> {
@@ -0,0 +1,460 @@
+//Traversal of the concrete syntax tree.
+//
+//Every visit method defaults to the matching walk function, which visits
+//the children of the node in source order. Tokens reach visit_token and
+//the errors recorded in place of missing nodes or tokens reach
+//visit_error, so overriding those two sees the whole input:
+//
+//  struct Names(Vec<String>);
+//
+//  impl<'a> Visitor<'a> for Names {
+//      fn visit_token(&mut self, token: Token<'a>) {
+//          if let Token::Name(data) = token {
+//              self.0.push(data.text());
+//          }
+//      }
+//  }
+use super::syntax_tree::*;
+use super::lexer::LexicalResult;
+use super::parser::SyntaxResult;
+use super::token::Token;
+use super::frontend_error::FrontendError;
+
+pub trait Visitor<'a> {
+    fn visit_grammar(&mut self, grammar: &Grammar<'a>) {
+        walk_grammar(self, grammar)
+    }
+
+    fn visit_prod(&mut self, prod: &Prod<'a>) {
+        walk_prod(self, prod)
+    }
+
+    fn visit_path(&mut self, path: &Path<'a>) {
+        walk_path(self, path)
+    }
+
+    fn visit_skip(&mut self, skip: &Skip<'a>) {
+        walk_skip(self, skip)
+    }
+
+    fn visit_layout(&mut self, layout: &Layout<'a>) {
+        walk_layout(self, layout)
+    }
+
+    fn visit_start(&mut self, start: &Start<'a>) {
+        walk_start(self, start)
+    }
+
+    fn visit_extends(&mut self, extends: &Extends<'a>) {
+        walk_extends(self, extends)
+    }
+
+    fn visit_override(&mut self, decl: &Override<'a>) {
+        walk_override(self, decl)
+    }
+
+    fn visit_union(&mut self, union: &Union<'a>) {
+        walk_union(self, union)
+    }
+
+    fn visit_body(&mut self, body: &Body<'a>) {
+        walk_body(self, body)
+    }
+
+    fn visit_part(&mut self, part: &Part<'a>) {
+        walk_part(self, part)
+    }
+
+    fn visit_token(&mut self, _token: Token<'a>) {}
+
+    fn visit_error(&mut self, _error: FrontendError) {}
+}
+
+//The first error in the tree and the position right after the token
+//preceding it, the start of the input when there is none
+pub fn first_error(grammar: &Grammar) -> Option<(FrontendError, usize, usize)> {
+    struct FirstError {
+        end: (usize, usize),
+        found: Option<(FrontendError, usize, usize)>,
+    }
+
+    impl<'a> Visitor<'a> for FirstError {
+        fn visit_token(&mut self, token: Token<'a>) {
+            if self.found.is_none() {
+                let data = token.data();
+                let value = data.value();
+                self.end = match value.iter().rposition(|c| *c == '\n') {
+                    Some(newline) => {
+                        let lines = value.iter().filter(|c| **c == '\n').count();
+                        (data.line() + lines, value.len() - newline - 1)
+                    }
+                    None => (data.line(), data.offset() + value.len()),
+                };
+            }
+        }
+
+        fn visit_error(&mut self, error: FrontendError) {
+            if self.found.is_none() {
+                self.found = Some((error, self.end.0, self.end.1));
+            }
+        }
+    }
+
+    let mut visitor = FirstError {
+        end: (1, 0),
+        found: None,
+    };
+    visitor.visit_grammar(grammar);
+    visitor.found
+}
+
+fn token<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, token: LexicalResult<'a>) {
+    match token {
+        Ok(token) => visitor.visit_token(token),
+        Err(what) => visitor.visit_error(what),
+    }
+}
+
+//Calls `visit` with the node, or reports the error stored in its place
+fn node<'a, 'n, V, T, F>(visitor: &mut V, node: &'n SyntaxResult<T>, visit: F)
+where
+    V: Visitor<'a> + ?Sized,
+    F: FnOnce(&mut V, &'n T),
+{
+    match *node {
+        Ok(ref node) => visit(visitor, node),
+        Err(what) => visitor.visit_error(what),
+    }
+}
+
+pub fn walk_grammar<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, grammar: &Grammar<'a>) {
+    let mut current = Some(grammar);
+    while let Some(decl) = current {
+        let rest = match *decl {
+            Grammar::ProdDecl(ref prod, ref rest) => {
+                node(visitor, prod, |v, prod| v.visit_prod(prod));
+                rest
+            }
+            Grammar::PathDecl(ref path, ref rest) => {
+                node(visitor, path, |v, path| v.visit_path(path));
+                rest
+            }
+            Grammar::SkipDecl(ref skip, ref rest) => {
+                node(visitor, skip, |v, skip| v.visit_skip(skip));
+                rest
+            }
+            Grammar::LayoutDecl(ref layout, ref rest) => {
+                node(visitor, layout, |v, layout| v.visit_layout(layout));
+                rest
+            }
+            Grammar::StartDecl(ref start, ref rest) => {
+                node(visitor, start, |v, start| v.visit_start(start));
+                rest
+            }
+            Grammar::ExtendsDecl(ref extends, ref rest) => {
+                node(visitor, extends, |v, extends| v.visit_extends(extends));
+                rest
+            }
+            Grammar::OverrideDecl(ref decl, ref rest) => {
+                node(visitor, decl, |v, decl| v.visit_override(decl));
+                rest
+            }
+        };
+        current = match **rest {
+            Some(Ok(ref rest)) => Some(rest),
+            Some(Err(what)) => {
+                visitor.visit_error(what);
+                None
+            }
+            None => None,
+        };
+    }
+}
+
+pub fn walk_prod<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, prod: &Prod<'a>) {
+    node(visitor, prod.name(), |v, name| match *name {
+        ProdName::Name(name, ref params) => {
+            token(v, name);
+            if let Some(ref params) = **params {
+                node(v, params, walk_params);
+            }
+        }
+        ProdName::LexicalRuleName(obrace, name, cbrace) => {
+            token(v, obrace);
+            token(v, name);
+            token(v, cbrace);
+        }
+    });
+    let mut modifiers = prod.modifiers();
+    while let Some(current) = modifiers {
+        modifiers = match *current {
+            Ok(ref current) => {
+                node(visitor, current.modifier(), walk_modifier);
+                current.rest()
+            }
+            Err(what) => {
+                visitor.visit_error(what);
+                None
+            }
+        };
+    }
+    node(visitor, prod.nullable(), |v, nullable| if let Some(ref nullable) = *nullable {
+        node(v, nullable, |v, nullable| token(v, nullable.qmark()));
+    });
+    node(visitor, prod.rtype(), |v, rtype| if let Some(ref rtype) = *rtype {
+        node(v, rtype, |v, rtype| {
+            token(v, rtype.colon());
+            token(v, rtype.rtype());
+        });
+    });
+    token(visitor, prod.arrow());
+    node(visitor, prod.union(), |v, union| v.visit_union(union));
+    token(visitor, prod.endl());
+}
+
+fn walk_params<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, params: &Params<'a>) {
+    token(visitor, params.oangle());
+    token(visitor, params.name());
+    let mut list = params.list();
+    while let Some(current) = list {
+        list = match *current {
+            Ok(ref current) => {
+                token(visitor, current.comma());
+                token(visitor, current.name());
+                current.list()
+            }
+            Err(what) => {
+                visitor.visit_error(what);
+                None
+            }
+        };
+    }
+    token(visitor, params.cangle());
+}
+
+fn walk_modifier<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, modifier: &Modifier<'a>) {
+    match *modifier {
+        Modifier::In(kin, name, ref modes) => {
+            token(visitor, kin);
+            token(visitor, name);
+            let mut list = (**modes).as_ref();
+            while let Some(current) = list {
+                list = match *current {
+                    Ok(ref current) => {
+                        token(visitor, current.or());
+                        token(visitor, current.name());
+                        current.list()
+                    }
+                    Err(what) => {
+                        visitor.visit_error(what);
+                        None
+                    }
+                };
+            }
+        }
+        Modifier::Push(kpush, name) => {
+            token(visitor, kpush);
+            token(visitor, name);
+        }
+        Modifier::Pop(kpop) => token(visitor, kpop),
+    }
+}
+
+pub fn walk_path<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, path: &Path<'a>) {
+    token(visitor, path.kuse());
+    token(visitor, path.name());
+    walk_path_items(visitor, path.list());
+    token(visitor, path.endl());
+}
+
+fn walk_path_items<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    mut list: Option<&SyntaxResult<PathItemList<'a>>>,
+) {
+    while let Some(current) = list {
+        list = match *current {
+            Ok(ref current) => {
+                token(visitor, current.pathsep());
+                token(visitor, current.name());
+                current.list()
+            }
+            Err(what) => {
+                visitor.visit_error(what);
+                None
+            }
+        };
+    }
+}
+
+pub fn walk_skip<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, skip: &Skip<'a>) {
+    token(visitor, skip.kskip());
+    let mut modifiers = skip.modifiers();
+    while let Some(current) = modifiers {
+        modifiers = match *current {
+            Ok(ref current) => {
+                node(visitor, current.modifier(), walk_modifier);
+                current.rest()
+            }
+            Err(what) => {
+                visitor.visit_error(what);
+                None
+            }
+        };
+    }
+    node(visitor, skip.union(), |v, union| v.visit_union(union));
+    token(visitor, skip.endl());
+}
+
+pub fn walk_layout<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, layout: &Layout<'a>) {
+    token(visitor, layout.klayout());
+    token(visitor, layout.endl());
+}
+
+pub fn walk_start<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, start: &Start<'a>) {
+    token(visitor, start.kstart());
+    token(visitor, start.name());
+    let mut list = start.list();
+    while let Some(current) = list {
+        list = match *current {
+            Ok(ref current) => {
+                token(visitor, current.comma());
+                token(visitor, current.name());
+                current.list()
+            }
+            Err(what) => {
+                visitor.visit_error(what);
+                None
+            }
+        };
+    }
+    token(visitor, start.endl());
+}
+
+pub fn walk_extends<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, extends: &Extends<'a>) {
+    token(visitor, extends.kextends());
+    token(visitor, extends.name());
+    walk_path_items(visitor, extends.list());
+    token(visitor, extends.endl());
+}
+
+pub fn walk_override<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, decl: &Override<'a>) {
+    token(visitor, decl.koverride());
+    node(visitor, decl.prod(), |v, prod| v.visit_prod(prod));
+}
+
+pub fn walk_union<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, union: &Union<'a>) {
+    let mut current = Some(union);
+    while let Some(union) = current {
+        node(visitor, union.body(), |v, body| v.visit_body(body));
+        node(visitor, union.action(), |v, action| if let Some(ref action) = *action {
+            node(v, action, |v, action| {
+                token(v, action.arrow());
+                token(v, action.code());
+            });
+        });
+        current = match union.obody() {
+            Some(Ok(obody)) => {
+                token(visitor, obody.or());
+                match *obody.union() {
+                    Ok(ref union) => Some(union),
+                    Err(what) => {
+                        visitor.visit_error(what);
+                        None
+                    }
+                }
+            }
+            Some(&Err(what)) => {
+                visitor.visit_error(what);
+                None
+            }
+            None => None,
+        };
+    }
+}
+
+pub fn walk_body<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, body: &Body<'a>) {
+    let mut current = Some(body);
+    while let Some(body) = current {
+        node(visitor, body.part(), |v, part| v.visit_part(part));
+        node(visitor, body.quantifier(), |v, quantifier| {
+            if let Some(ref quantifier) = *quantifier {
+                node(v, quantifier, |v, quantifier| token(v, quantifier.op()));
+            }
+        });
+        node(visitor, body.separator(), |v, separator| {
+            if let Some(ref separator) = *separator {
+                node(v, separator, |v, separator| {
+                    token(v, separator.op());
+                    node(v, separator.part(), |v, part| v.visit_part(part));
+                });
+            }
+        });
+        current = match body.rbody() {
+            Some(Ok(body)) => Some(body),
+            Some(&Err(what)) => {
+                visitor.visit_error(what);
+                None
+            }
+            None => None,
+        };
+    }
+}
+
+pub fn walk_part<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, part: &Part<'a>) {
+    match *part {
+        Part::Literal(literal) => token(visitor, literal),
+        Part::Range(from, range, to) => {
+            token(visitor, from);
+            token(visitor, range);
+            token(visitor, to);
+        }
+        Part::Class(class) => token(visitor, class),
+        Part::Property(property) => token(visitor, property),
+        Part::LexicalRuleName(obrace, name, cbrace) => {
+            token(visitor, obrace);
+            token(visitor, name);
+            token(visitor, cbrace);
+        }
+        Part::Name(name, ref args) => {
+            token(visitor, name);
+            if let Some(ref args) = **args {
+                node(visitor, args, walk_args);
+            }
+        }
+        Part::Epsilon(epsilon) => token(visitor, epsilon),
+        Part::Group(oparen, ref union, cparen) => {
+            token(visitor, oparen);
+            node(visitor, union, |v, union| v.visit_union(union));
+            token(visitor, cparen);
+        }
+        Part::Labeled(name, colon, ref part) => {
+            token(visitor, name);
+            token(visitor, colon);
+            node(visitor, part, |v, part| v.visit_part(part));
+        }
+        Part::And(op, ref part) | Part::Not(op, ref part) => {
+            token(visitor, op);
+            node(visitor, part, |v, part| v.visit_part(part));
+        }
+    }
+}
+
+fn walk_args<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, args: &Args<'a>) {
+    token(visitor, args.oangle());
+    node(visitor, args.union(), |v, union| v.visit_union(union));
+    let mut list = args.list();
+    while let Some(current) = list {
+        list = match *current {
+            Ok(ref current) => {
+                token(visitor, current.comma());
+                node(visitor, current.union(), |v, union| v.visit_union(union));
+                current.list()
+            }
+            Err(what) => {
+                visitor.visit_error(what);
+                None
+            }
+        };
+    }
+    token(visitor, args.cangle());
+}
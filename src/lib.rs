@@ -0,0 +1,74 @@
+//Gideon, a parser generator front end.
+//
+//The stages are usable on their own:
+//  parsing:      Parser turns grammar source into the concrete syntax tree,
+//                see syntax_tree.rs for its accessors and visitor.rs for
+//                walking it, formatter.rs prints it as canonical source.
+//                syntax.rs builds a lossless tree from it that keeps
+//                every character, comments and whitespace included.
+//                Document keeps the tokens and that tree up to date
+//                while the source is edited, lsp.rs serves it to editors.
+//                highlight.rs classifies its tokens for highlighting.
+//  lowering:     Grammar::lower turns the tree into the grammar model the
+//                later stages work on, Loader does the same for a file
+//                together with everything it uses or extends.
+//  analysis:     Grammar::analyze checks the rules and computes their
+//                FIRST and FOLLOW sets, Grammar::desugar rewrites the
+//                grammar to plain BNF.
+//  generation:   Backend prints a grammar in the supported output formats.
+//  interpreting: Scanner tokenizes input with a grammar, Interpreter parses
+//                it into a Node tree.
+//
+//Every stage reports failures as a FrontendError, the component that failed
+//knows where, see the line and offset accessors of Parser, Loader, Scanner
+//and Interpreter.
+//
+//The stages live in the private compiler module, what is exported below is
+//the public interface: the types of the stages and the grammar model, and
+//the modules whose functions are used as a whole, like formatter::format.
+mod compiler;
+
+pub use compiler::analysis::{Analysis, Diagnostic, Lookahead, Severity};
+pub use compiler::backend::{Backend, BACKENDS};
+pub use compiler::char_class::{CharClass, ClassItem};
+pub use compiler::document::{Document, LexedToken};
+pub use compiler::frontend_error::FrontendError;
+pub use compiler::grammar::{Alternative, EntryPoint, Grammar, Helper, Item, ModeAction, Override,
+                            OverrideKind, Production, Quantifier, Separator, Skip, Symbol, UsePath};
+pub use compiler::highlight::TokenClass;
+pub use compiler::import::{Import, ImportError, Imported, IMPORTS};
+pub use compiler::interpreter::{Interpreter, Level, Node};
+pub use compiler::lexer::{Lexer, Trivia};
+pub use compiler::loader::Loader;
+pub use compiler::parser::Parser;
+pub use compiler::scanner::{Lexeme, Scanner};
+pub use compiler::syntax::{SyntaxKind, SyntaxNode, SyntaxToken};
+pub use compiler::syntax_tree as cst;
+pub use compiler::token::{Token, TokenData};
+pub use compiler::visitor::Visitor;
+pub use compiler::{formatter, highlight, json, lsp, syntax, visitor};
+
+use std::path::Path;
+
+//Parses and lowers a single grammar, `use` and `extends` are not resolved
+pub fn lower(source: &str) -> Result<Grammar, FrontendError> {
+    let chars: Vec<char> = source.chars().collect();
+    let parser = Parser::new(&chars);
+    let tree = parser.parse()?;
+    Grammar::lower(&tree)
+}
+
+//Loads the grammar file `file`, resolving its paths relative to the
+//directory it is in
+pub fn load<P: AsRef<Path>>(file: P) -> Result<Grammar, FrontendError> {
+    let file = file.as_ref();
+    let root = file.parent().unwrap_or_else(|| Path::new(""));
+    let name = file.file_name().map(Path::new).unwrap_or(file);
+    Loader::new(root).load(name)
+}
+
+//Parses `input` with the production `start` of `grammar`
+pub fn parse(grammar: &Grammar, level: Level, start: &str, input: &str) -> Result<Node, FrontendError> {
+    let chars: Vec<char> = input.chars().collect();
+    Interpreter::new(grammar, level)?.parse(start, &chars)
+}
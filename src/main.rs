@@ -1,28 +1,536 @@
-mod compiler;
-use compiler::parser::Parser;
+extern crate gideon;
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::io;
+use gideon::{Backend, FrontendError, Grammar, Import, Interpreter, Level, Lexer, Loader, Lookahead,
+             Parser, Scanner, Severity, Trivia, Visitor, BACKENDS, IMPORTS};
+use gideon::{cst, formatter, highlight, lsp};
+use gideon::visitor::walk_prod;
 
-fn load_source(filename: &str) -> Result<Vec<char>, io::Error> {
-    let mut input = String::new();
-    match File::open(filename) {
-        Ok(mut file) => {
-            file.read_to_string(&mut input).expect(
-                "Unable to read from source",
-            );
-            Ok(input.chars().collect())
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const USAGE: &str = "usage: gideon <command> [options] [files]
+
+commands:
+  check [files]                       report errors and warnings in grammars
+  fmt [files]                         rewrite grammars in canonical form, stdin to stdout
+      --check                         only report the files that are not formatted
+  analyze [file]                      print the FIRST and FOLLOW sets of the productions
+  generate --backend <name> [file]    print a grammar in another format
+  import --from <format> [file]       print a grammar written in another format as Gideon
+  parse --grammar <file> [input]      parse input with a grammar and print the tree
+      --start <name>                  production to start from, the first entry point by default
+      --scannerless                   match characters instead of scanned tokens
+  tokens [file]                       print the tokens of a grammar
+  tokens --grammar <file> [input]     print the tokens the grammar scans input into
+  highlight [files]                   print grammars with their tokens colored for the terminal
+      --html                          print <pre> elements with a class per token instead
+      --css                           print the default stylesheet of --html
+  lsp                                 run a language server on stdin and stdout
+
+A missing file or '-' reads from stdin.
+
+exit codes:
+  0  success
+  1  the grammar or input has errors, or fmt --check found unformatted files
+  2  invalid arguments
+  3  a file could not be read or written";
+
+//Name of stdin in messages
+const STDIN: &str = "<stdin>";
+
+enum Failure {
+    //bad arguments, reported together with the usage
+    Usage(String),
+    //a file could not be read or written
+    Io(String, io::Error),
+    //errors in a grammar or input, already reported
+    Invalid,
+}
+
+impl Failure {
+    fn code(&self) -> i32 {
+        match *self {
+            Failure::Invalid => 1,
+            Failure::Usage(_) => 2,
+            Failure::Io(..) => 3,
+        }
+    }
+}
+
+type CommandResult = Result<(), Failure>;
+
+//Options and file arguments of a command
+struct Arguments {
+    files: Vec<String>,
+    values: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Arguments {
+    //`valued` options take the following argument, `flags` stand alone
+    fn parse(args: &[String], valued: &[&str], flags: &[&str]) -> Result<Arguments, Failure> {
+        let mut arguments = Arguments {
+            files: Vec::new(),
+            values: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = arg.as_str();
+            if name == "--" {
+                arguments.files.extend(args.cloned());
+                break;
+            } else if valued.contains(&name) {
+                match args.next() {
+                    Some(value) => arguments.values.push((arg.clone(), value.clone())),
+                    None => return Err(Failure::Usage(format!("{} needs a value", name))),
+                }
+            } else if flags.contains(&name) {
+                arguments.flags.push(arg.clone());
+            } else if name.starts_with('-') && name != "-" {
+                return Err(Failure::Usage(format!("unknown option {}", name)));
+            } else {
+                arguments.files.push(arg.clone());
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values.iter().rev().find(|v| v.0 == name).map(|v| v.1.as_str())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    //The files, stdin when there are none
+    fn files(&self) -> Vec<&str> {
+        if self.files.is_empty() {
+            vec!["-"]
+        } else {
+            self.files.iter().map(String::as_str).collect()
+        }
+    }
+
+    //The only file, stdin when there is none
+    fn file(&self) -> Result<&str, Failure> {
+        match self.files.len() {
+            0 => Ok("-"),
+            1 => Ok(&self.files[0]),
+            _ => Err(Failure::Usage("expected a single file".to_string())),
         }
-        Err(what) => Err(what),
     }
 }
+
 fn main() {
-    let filename = "language/json.gideon";
-    if let Ok(mut chars) = load_source(filename) {
-        let parser = Parser::new(chars.as_mut_slice());
-        let cst = parser.parse();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("check") => check(&args[1..]),
+        Some("fmt") => fmt(&args[1..]),
+        Some("analyze") => analyze(&args[1..]),
+        Some("generate") => generate(&args[1..]),
+        Some("import") => import(&args[1..]),
+        Some("parse") => parse(&args[1..]),
+        Some("tokens") => tokens(&args[1..]),
+        Some("highlight") => highlight(&args[1..]),
+        Some("lsp") => lsp(&args[1..]),
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(Failure::Usage(format!("unknown command '{}'", command))),
+        None => Err(Failure::Usage("missing command".to_string())),
+    };
+    if let Err(failure) = result {
+        match failure {
+            Failure::Usage(ref message) => eprintln!("gideon: {}\n\n{}", message, USAGE),
+            Failure::Io(ref file, ref what) => eprintln!("gideon: {}: {}", file, what),
+            Failure::Invalid => {}
+        }
+        process::exit(failure.code());
+    }
+}
+
+fn check(args: &[String]) -> CommandResult {
+    let arguments = Arguments::parse(args, &[], &[])?;
+    let mut valid = true;
+    for file in arguments.files() {
+        let source = read(file)?;
+        let grammar = match load(file, &source) {
+            Some(grammar) => grammar,
+            None => {
+                valid = false;
+                continue;
+            }
+        };
+        //rules of other files are reported when those are checked
+        let declared = declared_rules(&source);
+        let analysis = grammar.analyze();
+        for diagnostic in analysis.diagnostics() {
+            if diagnostic.rule.is_empty() || declared.contains(&diagnostic.rule) {
+                let message = format!("{}: {}", diagnostic.error.message(), diagnostic.name);
+                let location = (diagnostic.line, diagnostic.offset);
+                report(&name(file), location, diagnostic.severity, &message);
+                valid &= diagnostic.severity != Severity::Error;
+            }
+        }
+    }
+    if valid { Ok(()) } else { Err(Failure::Invalid) }
+}
+
+fn fmt(args: &[String]) -> CommandResult {
+    let arguments = Arguments::parse(args, &[], &["--check"])?;
+    let check = arguments.flag("--check");
+    let mut valid = true;
+    for file in arguments.files() {
+        let source = read(file)?;
+        let chars: Vec<char> = source.chars().collect();
+        let parser = Parser::with_trivia(&chars, Trivia::Attach);
+        let failure = match parser.parse() {
+            Ok(ref tree) => match parser.first_error(tree) {
+                Some((what, line, offset)) => Some((what, line, offset)),
+                None => {
+                    let formatted = formatter::format(tree, parser.trailing_trivia());
+                    if !check && file == "-" {
+                        write(&formatted)?;
+                    } else if !check {
+                        if formatted != source {
+                            fs::write(file, formatted).map_err(|what| Failure::Io(name(file), what))?;
+                        }
+                    } else if formatted != source {
+                        eprintln!("{}: not formatted", name(file));
+                        valid = false;
+                    }
+                    None
+                }
+            },
+            Err(what) => Some((what, parser.line(), parser.offset())),
+        };
+        if let Some((what, line, offset)) = failure {
+            report(&name(file), (line, offset), Severity::Error, what.message());
+            valid = false;
+        }
+    }
+    if valid { Ok(()) } else { Err(Failure::Invalid) }
+}
+
+fn analyze(args: &[String]) -> CommandResult {
+    let arguments = Arguments::parse(args, &[], &[])?;
+    let file = arguments.file()?;
+    let grammar = load_grammar(file)?;
+    let analysis = grammar.analyze();
+    let mut out = format!("entry points: {}\n", grammar.entry_points().join(", "));
+    for prod in &grammar.productions {
+        let set = |set: Vec<&Lookahead>| {
+            set.iter().map(|l| l.to_string()).collect::<Vec<String>>().join(" ")
+        };
+        out.push_str(&format!("\n{}\n", prod.name));
+        let nullable = if analysis.nullable(&prod.name) { "yes" } else { "no" };
+        out.push_str(&format!("  nullable: {}\n", nullable));
+        out.push_str(&format!("  first:    {}\n", set(analysis.first(&prod.name))));
+        out.push_str(&format!("  follow:   {}\n", set(analysis.follow(&prod.name))));
+    }
+    write(&out)?;
+    for diagnostic in analysis.diagnostics() {
+        let message = format!("{}: {}", diagnostic.error.message(), diagnostic.name);
+        let location = (diagnostic.line, diagnostic.offset);
+        report(&name(file), location, diagnostic.severity, &message);
+    }
+    if analysis.has_errors() { Err(Failure::Invalid) } else { Ok(()) }
+}
+
+fn generate(args: &[String]) -> CommandResult {
+    let arguments = Arguments::parse(args, &["--backend"], &[])?;
+    let names: Vec<&str> = BACKENDS.iter().map(|b| b.name()).collect();
+    let backend = match arguments.value("--backend") {
+        Some(name) => match Backend::from_name(name) {
+            Some(backend) => backend,
+            None => {
+                let message = format!("unknown backend '{}', use one of: {}", name, names.join(", "));
+                return Err(Failure::Usage(message));
+            }
+        },
+        None => {
+            let message = format!("--backend is required, use one of: {}", names.join(", "));
+            return Err(Failure::Usage(message));
+        }
+    };
+    let file = arguments.file()?;
+    let grammar = load_grammar(file)?;
+    write(&backend.generate(&grammar))?;
+    for note in backend.left_out(&grammar) {
+        report_file(&name(file), Severity::Warning, note);
+    }
+    Ok(())
+}
+
+fn import(args: &[String]) -> CommandResult {
+    let arguments = Arguments::parse(args, &["--from"], &[])?;
+    let names: Vec<&str> = IMPORTS.iter().map(|i| i.name()).collect();
+    let import = match arguments.value("--from") {
+        Some(name) => match Import::from_name(name) {
+            Some(import) => import,
+            None => {
+                let message = format!("unknown format '{}', use one of: {}", name, names.join(", "));
+                return Err(Failure::Usage(message));
+            }
+        },
+        None => {
+            let message = format!("--from is required, use one of: {}", names.join(", "));
+            return Err(Failure::Usage(message));
+        }
+    };
+    let file = arguments.file()?;
+    match import.import(&read(file)?) {
+        Ok(imported) => {
+            for warning in &imported.warnings {
+                let location = (warning.line, warning.offset);
+                report(&name(file), location, Severity::Warning, warning.what.message());
+            }
+            write(&imported.grammar.to_string())
+        }
+        Err(error) => {
+            report(&name(file), (error.line, error.offset), Severity::Error, error.what.message());
+            Err(Failure::Invalid)
+        }
+    }
+}
 
-        println!("{:?}", cst);
+fn parse(args: &[String]) -> CommandResult {
+    let arguments = Arguments::parse(args, &["--grammar", "--start"], &["--scannerless"])?;
+    let grammar_file = match arguments.value("--grammar") {
+        Some(file) => file,
+        None => return Err(Failure::Usage("--grammar is required".to_string())),
+    };
+    let file = arguments.file()?;
+    if grammar_file == "-" && file == "-" {
+        return Err(Failure::Usage("grammar and input cannot both come from stdin".to_string()));
     }
+    let grammar = load_grammar(grammar_file)?;
+    let level = if arguments.flag("--scannerless") {
+        Level::Scannerless
+    } else {
+        Level::Tokens
+    };
+    let start = match arguments.value("--start") {
+        Some(start) => start.to_string(),
+        None => match grammar.entry_points().first() {
+            Some(start) => start.to_string(),
+            None => {
+                report_file(&name(grammar_file), Severity::Error, "grammar has no productions");
+                return Err(Failure::Invalid);
+            }
+        },
+    };
+    let interpreter = match Interpreter::new(&grammar, level) {
+        Ok(interpreter) => interpreter,
+        Err(what) => {
+            report_file(&name(grammar_file), Severity::Error, what.message());
+            return Err(Failure::Invalid);
+        }
+    };
+    let input: Vec<char> = read(file)?.chars().collect();
+    match interpreter.parse(&start, &input) {
+        //every line of the tree ends with a newline already
+        Ok(tree) => write(&tree.to_string()),
+        //the start production is wrong, not the input
+        Err(what @ FrontendError::UnknownProduction) | Err(what @ FrontendError::NotAnEntryPoint) => {
+            let message = format!("{}: {}", what.message(), start);
+            report_file(&name(grammar_file), Severity::Error, &message);
+            Err(Failure::Invalid)
+        }
+        Err(what) => {
+            let location = (interpreter.line(), interpreter.offset());
+            report(&name(file), location, Severity::Error, what.message());
+            Err(Failure::Invalid)
+        }
+    }
+}
+
+fn tokens(args: &[String]) -> CommandResult {
+    let arguments = Arguments::parse(args, &["--grammar"], &[])?;
+    let file = arguments.file()?;
+    let grammar_file = match arguments.value("--grammar") {
+        Some(grammar_file) => grammar_file,
+        None => return grammar_tokens(file),
+    };
+    if grammar_file == "-" && file == "-" {
+        return Err(Failure::Usage("grammar and input cannot both come from stdin".to_string()));
+    }
+    let grammar = load_grammar(grammar_file)?;
+    let input: Vec<char> = read(file)?.chars().collect();
+    let scanner = Scanner::new(&grammar, &input);
+    match scanner.tokenize() {
+        Ok(lexemes) => {
+            let mut out = String::new();
+            for lexeme in lexemes {
+                let (line, offset) = (lexeme.line(), lexeme.offset());
+                out.push_str(&format!("{}:{}\t{}\t{:?}\n", line, offset, lexeme.symbol(), lexeme.text()));
+            }
+            write(&out)
+        }
+        Err(what) => {
+            let location = (scanner.line(), scanner.offset());
+            report(&name(file), location, Severity::Error, what.message());
+            Err(Failure::Invalid)
+        }
+    }
+}
+
+//The tokens of a grammar file itself
+fn grammar_tokens(file: &str) -> CommandResult {
+    let chars: Vec<char> = read(file)?.chars().collect();
+    let lexer = Lexer::new(&chars);
+    let mut out = String::new();
+    loop {
+        match lexer.next() {
+            Ok(token) => out.push_str(&format!("{}\n", token)),
+            Err(FrontendError::EOI) => break,
+            Err(what) => {
+                write(&out)?;
+                let location = (lexer.line(), lexer.offset());
+                report(&name(file), location, Severity::Error, what.message());
+                return Err(Failure::Invalid);
+            }
+        }
+    }
+    write(&out)
+}
+
+fn highlight(args: &[String]) -> CommandResult {
+    let arguments = Arguments::parse(args, &[], &["--html", "--css"])?;
+    if arguments.flag("--css") {
+        return write(highlight::CSS);
+    }
+    for file in arguments.files() {
+        let source = read(file)?;
+        if arguments.flag("--html") {
+            write(&highlight::html(&source))?;
+        } else {
+            write(&highlight::ansi(&source))?;
+        }
+    }
+    Ok(())
+}
+
+//Exits with 1 when the client did not shut the server down first
+fn lsp(args: &[String]) -> CommandResult {
+    //editors pass --stdio, the only transport
+    Arguments::parse(args, &[], &["--stdio"])?;
+    let stdin = io::stdin();
+    match lsp::serve(stdin.lock(), io::stdout()) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Failure::Invalid),
+        Err(what) => Err(Failure::Io("stdio".to_string(), what)),
+    }
+}
+
+//Loads the grammar in `file`, whose source is `source`, and reports the
+//error when it fails
+fn load(file: &str, source: &str) -> Option<Grammar> {
+    let (root, name) = if file == "-" {
+        (PathBuf::new(), PathBuf::from(STDIN))
+    } else {
+        let path = Path::new(file);
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        (root, path.file_name().map(PathBuf::from).unwrap_or_default())
+    };
+    let mut loader = Loader::new(root);
+    match loader.load_source(&name, source) {
+        Ok(grammar) => Some(grammar),
+        Err(what) => {
+            let failed = relative(loader.file());
+            let location = (loader.line(), loader.offset());
+            report(&failed, location, Severity::Error, what.message());
+            None
+        }
+    }
+}
+
+fn load_grammar(file: &str) -> Result<Grammar, Failure> {
+    let source = read(file)?;
+    load(file, &source).ok_or(Failure::Invalid)
+}
+
+//Names of the rules declared in `source`, including overrides
+fn declared_rules(source: &str) -> HashSet<String> {
+    struct Declared(HashSet<String>);
+
+    impl<'a> Visitor<'a> for Declared {
+        fn visit_prod(&mut self, prod: &cst::Prod<'a>) {
+            match *prod.name() {
+                Ok(cst::ProdName::Name(Ok(name), _))
+                | Ok(cst::ProdName::LexicalRuleName(_, Ok(name), _)) => {
+                    self.0.insert(name.data().text());
+                }
+                _ => {}
+            }
+            walk_prod(self, prod)
+        }
+    }
+
+    let chars: Vec<char> = source.chars().collect();
+    let parser = Parser::new(&chars);
+    let mut declared = Declared(HashSet::new());
+    if let Ok(tree) = parser.parse() {
+        declared.visit_grammar(&tree);
+    }
+    declared.0
+}
+
+fn read(file: &str) -> Result<String, Failure> {
+    let mut source = String::new();
+    let result = if file == "-" {
+        io::stdin().read_to_string(&mut source).map(|_| ())
+    } else {
+        fs::File::open(file).and_then(|mut f| f.read_to_string(&mut source)).map(|_| ())
+    };
+    match result {
+        Ok(()) => Ok(source),
+        Err(what) => Err(Failure::Io(name(file), what)),
+    }
+}
+
+fn write(text: &str) -> CommandResult {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    match stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush()) {
+        Ok(()) => Ok(()),
+        //the reader went away, like `gideon tokens | head`
+        Err(ref what) if what.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        Err(what) => Err(Failure::Io("stdout".to_string(), what)),
+    }
+}
+
+//`file:line:offset: severity: message`, without the position when it is 0:0,
+//the position the loader and the analysis give what has none
+fn report(file: &str, location: (usize, usize), severity: Severity, message: &str) {
+    match location {
+        (0, 0) => report_file(file, severity, message),
+        (line, offset) => eprintln!("{}:{}:{}: {}: {}", file, line, offset, severity, message),
+    }
+}
+
+//`file: severity: message` for what has no position in the file
+fn report_file(file: &str, severity: Severity, message: &str) {
+    eprintln!("{}: {}: {}", file, severity, message);
+}
+
+fn name(file: &str) -> String {
+    if file == "-" {
+        STDIN.to_string()
+    } else {
+        file.to_string()
+    }
+}
+
+//`file` relative to the working directory when it is below it
+fn relative(file: &Path) -> String {
+    let relative = env::current_dir().ok().and_then(|dir| file.strip_prefix(dir).ok().map(PathBuf::from));
+    relative.unwrap_or_else(|| file.to_path_buf()).display().to_string()
 }
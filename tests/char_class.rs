@@ -0,0 +1,67 @@
+extern crate gideon;
+
+use gideon::{FrontendError, Level};
+
+//Whether the lexical rule `{c} -> class` matches all of `input`
+fn matches(class: &str, input: &str) -> bool {
+    let source = format!("start S ;\nS -> {{c}} ;\n{{c}} -> {} ;\n", class);
+    let grammar = gideon::lower(&source).expect("grammar");
+    gideon::parse(&grammar, Level::Tokens, "S", input).is_ok()
+}
+
+fn error(class: &str) -> FrontendError {
+    let source = format!("start S ;\nS -> {{c}} ;\n{{c}} -> {} ;\n", class);
+    gideon::lower(&source).expect_err("an error")
+}
+
+#[test]
+fn classes_match_their_characters_and_ranges() {
+    assert!(matches("[a-cx_]", "b") && matches("[a-cx_]", "x") && matches("[a-cx_]", "_"));
+    assert!(!matches("[a-cx_]", "d"));
+    assert!(matches("[^a-c]", "d") && !matches("[^a-c]", "a"));
+    assert!(matches("\"0\"..\"9\"", "5") && !matches("\"0\"..\"9\"", "a"));
+    //a dash at either end is itself
+    assert!(matches("[-a]", "-") && matches("[a-]", "-"));
+}
+
+#[test]
+fn escapes_stand_for_their_characters() {
+    assert!(matches("[\\]\\\\]", "]") && matches("[\\]\\\\]", "\\"));
+    assert!(matches("[\\n\\t]", "\t") && !matches("[\\n\\t]", "n"));
+    assert!(matches("[\\^]", "^") && !matches("[\\^]", "a"));
+    assert!(matches("[\\u{0}-\\u{1F}]", "\u{1b}") && !matches("[\\u{0}-\\u{1F}]", "\u{7f}"));
+    assert!(matches("[\\u{1F600}]", "\u{1F600}") && matches("[\\u]", "u"));
+}
+
+#[test]
+fn malformed_classes_are_rejected() {
+    assert_eq!(error("[z-a]"), FrontendError::InvalidRange);
+    assert_eq!(error("\"9\"..\"0\""), FrontendError::InvalidRange);
+    assert_eq!(error("[a-\\p{L}]"), FrontendError::InvalidRange);
+    assert_eq!(error("[\\u{D800}]"), FrontendError::InvalidCharClass);
+    assert_eq!(error("[\\u{x}]"), FrontendError::InvalidCharClass);
+    assert_eq!(error("[\\p{Nope}]"), FrontendError::UnknownCharacterProperty);
+    assert_eq!(error("\\p{Letters}"), FrontendError::UnknownCharacterProperty);
+}
+
+#[test]
+fn general_categories_match_their_characters() {
+    assert!(matches("\\p{Lu}", "Ä") && !matches("\\p{Lu}", "ä"));
+    assert!(matches("\\p{Ll}", "ß"));
+    assert!(matches("\\p{Nd}", "٣") && !matches("\\p{Nd}", "Ⅷ"));
+    assert!(matches("\\p{N}", "Ⅷ") && matches("\\p{Number}", "½"));
+    assert!(matches("\\p{P}", "¿") && matches("\\p{Pd}", "—") && !matches("\\p{P}", "+"));
+    assert!(matches("\\p{Zs}", "\u{3000}") && !matches("\\p{Zs}", "\n"));
+    assert!(matches("\\p{L}", "中") && matches("\\p{Letter}", "ǅ"));
+    assert!(matches("\\p{LC}", "ǅ") && !matches("\\p{LC}", "中"));
+    assert!(matches("\\p{Cc}", "\u{7f}") && matches("\\p{Control}", "\u{85}"));
+    assert!(matches("\\p{Cn}", "\u{378}") && !matches("\\p{C}", "a"));
+}
+
+#[test]
+fn properties_negate_inside_and_outside_classes() {
+    assert!(matches("\\P{Lu}", "a") && !matches("\\P{Lu}", "A"));
+    assert!(matches("[\\P{L}]", "1") && !matches("[\\P{L}]", "a"));
+    assert!(matches("[^\\p{L}\\p{Nd}]", "-") && !matches("[^\\p{L}\\p{Nd}]", "7"));
+    assert!(matches("[\\p{Alphabetic}_]", "_") && matches("[\\p{White_Space}]", "\u{2029}"));
+}
@@ -0,0 +1,89 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn gideon(args: &[&str], input: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gideon"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("gideon");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let text = |bytes: Vec<u8>| String::from_utf8(bytes).unwrap();
+    (output.status.code().unwrap_or(-1), text(output.stdout), text(output.stderr))
+}
+
+#[test]
+fn parse_prints_the_tree_once() {
+    let (code, out, _) = gideon(&["parse", "--grammar", "language/json.gideon"], "[true]");
+    assert_eq!(code, 0);
+    assert!(out.starts_with("Value\n  Array\n"), "{}", out);
+    assert!(out.ends_with("\"]\" \"]\" 1:5\n"), "{}", out);
+}
+
+#[test]
+fn parse_locates_the_failing_unit() {
+    let tokens = ["parse", "--grammar", "language/json.gideon"];
+    let scannerless = ["parse", "--grammar", "language/json.gideon", "--scannerless"];
+    for args in &[&tokens[..], &scannerless[..]] {
+        let (code, out, err) = gideon(args, "{\"a\": 1}\n$");
+        assert_eq!((code, out.as_str()), (1, ""));
+        assert!(err.starts_with("<stdin>:2:0: error:"), "{}", err);
+    }
+}
+
+#[test]
+fn bundled_grammars_check_and_are_formatted() {
+    let grammars = [
+        "src/compiler/gideon.gideon",
+        "language/example.gideon",
+        "language/json.gideon",
+        "language/jsonc.gideon",
+    ];
+    for grammar in &grammars {
+        let (code, _, err) = gideon(&["check", grammar], "");
+        assert_eq!(code, 0, "{}", err);
+        let (code, _, err) = gideon(&["fmt", "--check", grammar], "");
+        assert_eq!(code, 0, "{}", err);
+    }
+    //gideon.grammophone is generated from gideon.gideon
+    let (_, out, _) = gideon(&["generate", "--backend", "grammophone", grammars[0]], "");
+    assert_eq!(out, include_str!("../src/compiler/gideon.grammophone"));
+}
+
+#[test]
+fn gideon_grammar_parses_grammars() {
+    for grammar in &["src/compiler/gideon.gideon", "src/compiler/std/json.gideon"] {
+        let args = ["parse", "--grammar", "src/compiler/gideon.gideon", "--scannerless", grammar];
+        let (code, out, err) = gideon(&args, "");
+        assert_eq!(code, 0, "{}", err);
+        assert!(out.starts_with("Grammar\n"));
+    }
+}
+
+#[test]
+fn fmt_rewrites_files_and_prints_stdin() {
+    let dir = std::env::temp_dir().join(format!("gideon-fmt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (a, b) = (dir.join("a.gideon"), dir.join("b.gideon"));
+    std::fs::write(&a, "start A ;\nA->\"a\"  ;\n").unwrap();
+    std::fs::write(&b, "B -> \"b\" ;\n").unwrap();
+    let (code, out, err) = gideon(&["fmt", a.to_str().unwrap(), b.to_str().unwrap()], "");
+    let (formatted, kept) = (std::fs::read_to_string(&a).unwrap(), std::fs::read_to_string(&b).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!((code, out.as_str()), (0, ""), "{}", err);
+    assert_eq!(formatted, "start A ;\nA -> \"a\" ;\n");
+    assert_eq!(kept, "B -> \"b\" ;\n");
+    let (code, out, _) = gideon(&["fmt"], "A->\"a\";");
+    assert_eq!((code, out.as_str()), (0, "A -> \"a\" ;\n"));
+}
+
+#[test]
+fn generate_notes_have_no_position() {
+    let grammar = "start S ;\nS : u8 -> \"s\" => { 1 } ;\n";
+    let (code, _, err) = gideon(&["generate", "--backend", "grammophone"], grammar);
+    assert_eq!(code, 0);
+    assert!(err.starts_with("<stdin>: warning: actions"), "{}", err);
+}
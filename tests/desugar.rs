@@ -0,0 +1,53 @@
+extern crate gideon;
+
+fn desugar(source: &str) -> String {
+    gideon::lower(source).unwrap().desugar().to_string()
+}
+
+#[test]
+fn quantifiers_and_groups_become_helper_rules() {
+    let source = "start S ;\nS -> A? (B | \"c\")* {n} % \",\" \"x\"+ ;\nA -> \"a\" ;\nB -> \"b\" ;\n{n} -> [0-9] ;\n";
+    let expected = "start S ;
+S -> SOpt1 SList1 SList2 SList4 ;
+SOpt1? -> A ;
+SList1? -> B SList1 | \"c\" SList1 ;
+SList2 -> {n} SList3 ;
+SList3? -> \",\" {n} SList3 ;
+SList4 -> \"x\" SList4 | \"x\" ;
+A -> \"a\" ;
+B -> \"b\" ;
+{n} -> [0-9] ;
+";
+    assert_eq!(desugar(source), expected);
+}
+
+#[test]
+fn trailing_separators_and_optional_sequences() {
+    let source = "start S ;\nS -> {n} %% \",\" | (\"a\" \"b\")? ;\n{n} -> [0-9] ;\n";
+    let expected = "start S ;
+S -> SList1 | SOpt1 ;
+SList1 -> {n} SList2 ;
+SList2? -> \",\" SList3 ;
+SList3? -> {n} SList2 ;
+SOpt1? -> \"a\" \"b\" ;
+{n} -> [0-9] ;
+";
+    assert_eq!(desugar(source), expected);
+}
+
+#[test]
+fn helpers_stay_on_their_layer_and_predicates_stay() {
+    let source = "start S ;\nskip {ws} ;\n{ws} -> \" \"+ ;\n{id} -> [a-z] ([a-z] | [0-9])* ;\nS -> !(\"a\" | \"b\") {id} ;\n";
+    let grammar = gideon::lower(source).unwrap().desugar();
+    let productions: Vec<&str> = grammar.productions.iter().map(|prod| prod.name.as_str()).collect();
+    let lexical: Vec<&str> = grammar.lexical_rules.iter().map(|rule| rule.name.as_str()).collect();
+    assert_eq!(productions, ["S", "SGroup1"]);
+    assert_eq!(lexical, ["ws", "wsList1", "id", "idList1"]);
+    assert!(grammar.to_string().contains("S -> !SGroup1 {id} ;\nSGroup1 -> \"a\" | \"b\" ;\n"));
+    //the desugared grammar accepts the same inputs
+    let original = gideon::lower(source).unwrap();
+    for input in &["c1", "c 1", "ab", "b", "zz9"] {
+        let accepts = |grammar| gideon::parse(grammar, gideon::Level::Scannerless, "S", input).is_ok();
+        assert_eq!(accepts(&grammar), accepts(&original), "{}", input);
+    }
+}
@@ -0,0 +1,44 @@
+extern crate gideon;
+
+use gideon::Document;
+
+use std::rc::Rc;
+
+const SOURCE: &str = "start S ;\nskip {ws} ;\n{ws} -> \" \"+ ;\n# items\nS -> Item* ;\nItem -> \"a\" | \"b\" ;\n";
+
+//The edited document holds what parsing its text from scratch gives
+fn assert_fresh(document: &Document) {
+    let fresh = Document::new(&document.text());
+    assert_eq!(document.tokens(), fresh.tokens(), "{:?}", document.text());
+    assert_eq!(document.tree().green(), fresh.tree().green(), "{:?}", document.text());
+    assert_eq!(document.tree().text(), document.text());
+}
+
+#[test]
+fn edits_match_parsing_from_scratch() {
+    let mut document = Document::new(SOURCE);
+    let edits: &[(usize, usize, &str)] = &[
+        (0, 0, "# top\n"),
+        (20, 21, "{"),
+        (40, 40, ";;"),
+        (33, 45, ""),
+        (10, 10, "S -> \"unterminated"),
+        (10, 28, ""),
+        (0, 1000, SOURCE),
+    ];
+    for &(start, end, text) in edits {
+        document.edit(start, end, text);
+        assert_fresh(&document);
+    }
+    assert_eq!(document.text(), SOURCE);
+}
+
+#[test]
+fn declarations_past_the_edit_are_kept() {
+    let mut document = Document::new(SOURCE);
+    let last = |document: &Document| document.tree().child_nodes().last().unwrap().green().clone();
+    let before = last(&document);
+    document.edit(6, 7, "Start");
+    assert_fresh(&document);
+    assert!(Rc::ptr_eq(&before, &last(&document)));
+}
@@ -0,0 +1,184 @@
+extern crate gideon;
+
+use gideon::{lower, Backend, Import, Level, Node, Symbol};
+
+const UNSUPPORTED: &str = "start expr ;\nskip {ws} ;\n{ws} -> \" \"+ ;\nexpr -> Skip \\p{Alphabetic} &\"x\" \"x\" ;\nSkip -> \"s\" ;\n";
+
+#[test]
+fn w3c_keeps_unsupported_constructs_as_rules_matching_nothing() {
+    let grammar = lower(UNSUPPORTED).unwrap();
+    let out = Backend::from_name("w3c-ebnf").unwrap().generate(&grammar);
+    assert!(out.contains("expr ::= Skip UNSUPPORTED1 UNSUPPORTED2 \"x\"\n"));
+    assert!(out.contains("UNSUPPORTED1 ::= [^#x0-#x10FFFF] /* [\\p{Alphabetic}] */\n"));
+    assert!(out.contains("UNSUPPORTED2 ::= [^#x0-#x10FFFF] /* &\"x\" */\n"));
+}
+
+#[test]
+fn w3c_skip_rule_keeps_apart_from_productions() {
+    let grammar = lower(UNSUPPORTED).unwrap();
+    let out = Backend::from_name("w3c-ebnf").unwrap().generate(&grammar);
+    assert!(out.contains("Skip ::= \"s\"\n"));
+    assert!(out.contains("Skip_TOKEN ::= ws\n"));
+}
+
+#[test]
+fn w3c_placeholders_import_back() {
+    let grammar = lower(UNSUPPORTED).unwrap();
+    let out = Backend::from_name("w3c-ebnf").unwrap().generate(&grammar);
+    let imported = Import::W3cEbnf.import(&out).unwrap().grammar;
+    let text = imported.to_string();
+    assert!(text.contains("{UNSUPPORTED1} -> [^\\p{Any}] ;"));
+    let reimported = lower(&text).unwrap();
+    assert!(!reimported.analyze().has_errors());
+}
+
+fn json() -> gideon::Grammar {
+    gideon::load(concat!(env!("CARGO_MANIFEST_DIR"), "/language/json.gideon")).unwrap()
+}
+
+#[test]
+fn bison_tokens_keep_apart_from_c_macros() {
+    let source = "start V ;\nV -> \"null\" | \"TRUE\" | \"->\" | {EOF} | [a-z] ;\n{EOF} -> \"$\" ;\n";
+    let out = Backend::from_name("bison").unwrap().generate(&lower(source).unwrap());
+    for token in &["TOK_NULL \"null\"", "TOK_TRUE \"TRUE\"", "TOK_ARROW \"->\"", "TOK_EOF", "TOK_CLASS1"] {
+        assert!(out.contains(&format!("%token {}\n", token)), "{} in\n{}", token, out);
+    }
+    assert!(!out.contains("%token NULL") && !out.contains("%token TRUE") && !out.contains("%token EOF"));
+}
+
+//The rules of a Bison file with the symbols of their alternatives, none for
+//%empty and TOK_CLASS for all classes
+fn bison_rules(out: &str) -> Vec<(String, Vec<Vec<String>>)> {
+    let mut rules: Vec<(String, Vec<Vec<String>>)> = Vec::new();
+    for line in out[out.find("\n%%\n").unwrap() + 4..].lines() {
+        let alternative = line.trim_start().strip_prefix(':').or_else(|| line.trim_start().strip_prefix('|'));
+        match (alternative, rules.last_mut()) {
+            (Some(symbols), Some(rule)) => {
+                let symbols = symbols.split_whitespace().filter(|symbol| *symbol != "%empty");
+                //the classes are numbered in the order they are met
+                let symbols = symbols.map(|symbol| {
+                    if symbol.starts_with("TOK_CLASS") { "TOK_CLASS" } else { symbol }.to_string()
+                });
+                rule.1.push(symbols.collect());
+            }
+            _ if line.is_empty() || line.starts_with(' ') => {}
+            _ => rules.push((line.to_string(), Vec::new())),
+        }
+    }
+    rules
+}
+
+//The Bison symbol of a desugared item
+fn bison_symbol(symbol: &Symbol) -> Option<String> {
+    match *symbol {
+        Symbol::Name(ref name) => Some(name.clone()),
+        Symbol::Lexical(ref name) => Some(format!("TOK_{}", name.to_uppercase())),
+        //the test grammars have no quotes or backslashes
+        Symbol::Literal(ref value) if value.len() == 1 => Some(format!("'{}'", value)),
+        Symbol::Literal(ref value) => Some(format!("{:?}", value)),
+        Symbol::Class(_) => Some("TOK_CLASS".to_string()),
+        _ => None,
+    }
+}
+
+#[test]
+fn bison_rules_follow_the_desugared_grammar() {
+    let source = "start S ;\nS -> (A | \"(\" S \")\")* \"end\" ;\nA? -> {id} % \",\" | [0-9] &\"x\" ;\n{id} -> [a-z]+ ;\n";
+    for grammar in &[lower(source).unwrap(), json()] {
+        let out = Backend::from_name("bison").unwrap().generate(grammar);
+        let rules = bison_rules(&out);
+        let bnf = grammar.desugar().share_helpers();
+        let names: Vec<&str> = rules.iter().map(|rule| rule.0.as_str()).collect();
+        let expected: Vec<&str> = bnf.productions.iter().map(|prod| prod.name.as_str()).collect();
+        assert_eq!(names, expected, "{}", out);
+        for (prod, rule) in bnf.productions.iter().zip(&rules) {
+            let mut expected: Vec<Vec<String>> = prod
+                .alternatives
+                .iter()
+                .map(|alternative| alternative.items.iter().filter_map(|item| bison_symbol(&item.symbol)).collect())
+                .collect();
+            if prod.nullable && !expected.iter().any(Vec::is_empty) {
+                expected.push(Vec::new());
+            }
+            assert_eq!(rule.1, expected, "{}", prod.name);
+            //tokens are declared, character tokens need not be
+            for symbol in rule.1.iter().flatten().filter(|symbol| symbol.starts_with("TOK_")) {
+                assert!(out.contains(&format!("%token {}", symbol)), "{} in\n{}", symbol, out);
+            }
+        }
+    }
+}
+
+//Needs bison, run with `cargo test -- --ignored`
+#[test]
+#[ignore]
+fn bison_accepts_exported_grammars() {
+    use std::process::Command;
+    let file = ::std::env::temp_dir().join(format!("gideon-export-{}.y", ::std::process::id()));
+    ::std::fs::write(&file, Backend::from_name("bison").unwrap().generate(&json())).unwrap();
+    let output = Command::new("bison").arg("-o").arg(file.with_extension("c")).arg(&file).output();
+    let _ = ::std::fs::remove_file(file.with_extension("c"));
+    let _ = ::std::fs::remove_file(&file);
+    let output = output.expect("bison");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn antlr_export_imports_back() {
+    let out = Backend::from_name("antlr").unwrap().generate(&json());
+    let imported = Import::Antlr.import(&out).unwrap().grammar;
+    let grammar = lower(&imported.to_string()).unwrap();
+    assert!(!grammar.analyze().has_errors());
+    let input = "{\"a\": [1, -2.5e3, true, null], \"b\": {}}";
+    let tree = gideon::parse(&grammar, Level::Tokens, "value", input).unwrap();
+    let original = gideon::parse(&json(), Level::Tokens, "Value", input).unwrap();
+    assert_eq!(text(&tree), text(&original));
+    assert!(gideon::parse(&grammar, Level::Tokens, "value", "{\"a\" 1}").is_err());
+}
+
+//The tokens of a tree joined by spaces
+fn text(node: &Node) -> String {
+    match *node {
+        Node::Token { ref text, .. } => text.clone(),
+        Node::Production { ref children, .. } => {
+            let texts: Vec<String> = children.iter().map(text).collect();
+            texts.join(" ")
+        }
+    }
+}
+
+#[test]
+fn grammophone_export_imports_back() {
+    let grammophone = Backend::from_name("grammophone").unwrap();
+    let out = grammophone.generate(&json());
+    assert!(out.starts_with("Value -> "));
+    let imported = Import::Grammophone.import(&out).unwrap().grammar;
+    assert_eq!(imported.entry_points(), ["Value"]);
+    assert_eq!(grammophone.generate(&imported), out);
+}
+
+const NOTATIONS: &str = "start Expr ;\nExpr -> Term (\"+\" Term)* ;\nTerm? -> \"x\" [0-9] | &\"y\" Expr ;\n";
+
+#[test]
+fn iso_and_abnf_keep_unsupported_constructs_as_text() {
+    let grammar = lower(NOTATIONS).unwrap();
+    let iso = Backend::from_name("iso-ebnf").unwrap().generate(&grammar);
+    assert!(iso.contains("Expr = Term, { ( \"+\", Term ) } ;\n"));
+    assert!(iso.contains("( \"0\" | \"1\" |"));
+    assert!(iso.contains("| ? &\"y\" ?, Expr ] ;\n"));
+    let abnf = Backend::from_name("abnf").unwrap().generate(&grammar);
+    assert!(abnf.contains("Expr = Term *( \"+\" Term )\n"));
+    assert!(abnf.contains("Term = [ %s\"x\" %x30-39\n"));
+    assert!(abnf.contains("/ <&\"y\"> Expr ]\n"));
+}
+
+#[test]
+fn every_backend_keeps_or_notes_actions() {
+    let source = "start S ;\nS : i32 -> n:{num} => { n.parse().unwrap() } ;\n{num} -> [0-9]+ ;\n";
+    let grammar = lower(source).unwrap();
+    for backend in gideon::BACKENDS {
+        let out = backend.generate(&grammar);
+        let noted = out.contains("left out") || !backend.left_out(&grammar).is_empty();
+        assert!(out.contains("=> { n.parse().unwrap() }") || noted, "{}", backend.name());
+    }
+}
@@ -0,0 +1,25 @@
+extern crate gideon;
+
+use gideon::{formatter, Parser, Trivia};
+
+fn format(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let parser = Parser::with_trivia(&chars, Trivia::Attach);
+    let tree = parser.parse().expect("grammar");
+    assert!(parser.first_error(&tree).is_none());
+    formatter::format(&tree, parser.trailing_trivia())
+}
+
+const MESSY: &str = "# header\nstart   S ;\n\n\n\nS->'a'   B* |C\n  |  D ; # trailing\nB -> ( \"b\" )+ ;   \n# inside\nC->None;D -> 'x\"' ;\n";
+
+const FORMATTED: &str = "# header\nstart S ;\n\nS -> \"a\" B* | C\n   | D ; # trailing\nB -> (\"b\")+ ;\n# inside\nC -> None ;\nD -> 'x\"' ;\n";
+
+#[test]
+fn formatting_normalizes_layout_and_keeps_comments() {
+    assert_eq!(format(MESSY), FORMATTED);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    assert_eq!(format(FORMATTED), FORMATTED);
+}
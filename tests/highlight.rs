@@ -0,0 +1,164 @@
+extern crate gideon;
+
+use gideon::highlight::{self, classify};
+use gideon::syntax;
+use gideon::TokenClass::{self, *};
+
+//The tokens of `source` with their classes, whitespace left out
+fn classes(source: &str) -> Vec<(String, TokenClass)> {
+    let chars: Vec<char> = source.chars().collect();
+    classify(&syntax::parse(&chars))
+        .into_iter()
+        .filter(|&(_, class)| class != Whitespace)
+        .map(|(token, class)| (token.text().to_string(), class))
+        .collect()
+}
+
+fn assert_classes(source: &str, expected: &[(&str, TokenClass)]) {
+    let expected: Vec<(String, TokenClass)> = expected
+        .iter()
+        .map(|&(text, class)| (text.to_string(), class))
+        .collect();
+    assert_eq!(classes(source), expected, "{:?}", source);
+}
+
+#[test]
+fn declarations_and_references() {
+    assert_classes(
+        "# list\nS -> {num} Item | None ;\n",
+        &[
+            ("# list", Comment),
+            ("S", Definition),
+            ("->", Operator),
+            ("{", Lexical),
+            ("num", Lexical),
+            ("}", Lexical),
+            ("Item", Production),
+            ("|", Operator),
+            ("None", Epsilon),
+            (";", Operator),
+        ],
+    );
+    assert_classes(
+        "{num} -> [0-9]+ \\p{Nd} \"x\"..\"z\" ;\n",
+        &[
+            ("{", Definition),
+            ("num", Definition),
+            ("}", Definition),
+            ("->", Operator),
+            ("[0-9]", CharacterClass),
+            ("+", Operator),
+            ("\\p{Nd}", CharacterClass),
+            ("\"x\"", Literal),
+            ("..", Operator),
+            ("\"z\"", Literal),
+            (";", Operator),
+        ],
+    );
+}
+
+#[test]
+fn keywords_and_paths() {
+    assert_classes(
+        "use std::list ;\nextends base ;\nstart S ;\nskip {ws} ;\nlayout ;\n",
+        &[
+            ("use", Keyword),
+            ("std", Path),
+            ("::", Path),
+            ("list", Path),
+            (";", Operator),
+            ("extends", Keyword),
+            ("base", Path),
+            (";", Operator),
+            ("start", Keyword),
+            ("S", Production),
+            (";", Operator),
+            ("skip", Keyword),
+            ("{", Lexical),
+            ("ws", Lexical),
+            ("}", Lexical),
+            (";", Operator),
+            ("layout", Keyword),
+            (";", Operator),
+        ],
+    );
+}
+
+#[test]
+fn names_take_their_class_from_where_they_are() {
+    assert_classes(
+        "List<T> : Vec<u8> -> t:T % \",\" => { vec![t] } ;\n",
+        &[
+            ("List", Definition),
+            ("<", Operator),
+            ("T", Parameter),
+            (">", Operator),
+            (":", Operator),
+            ("Vec<u8>", Code),
+            ("->", Operator),
+            ("t", Label),
+            (":", Operator),
+            ("T", Parameter),
+            ("%", Operator),
+            ("\",\"", Literal),
+            ("=>", Operator),
+            ("{ vec![t] }", Code),
+            (";", Operator),
+        ],
+    );
+    assert_classes(
+        "{str} in quoted push raw -> \"a\" ;\n",
+        &[
+            ("{", Definition),
+            ("str", Definition),
+            ("}", Definition),
+            ("in", Keyword),
+            ("quoted", Mode),
+            ("push", Keyword),
+            ("raw", Mode),
+            ("->", Operator),
+            ("\"a\"", Literal),
+            (";", Operator),
+        ],
+    );
+    //the heads of overrides and appends refer to the rule they change
+    let overridden = classes("override S -> \"a\" ;\nT +-> \"b\" ;\noverride {n} -> \"1\" ;\n");
+    let heads: Vec<TokenClass> = overridden
+        .iter()
+        .filter(|&(text, _)| text == "S" || text == "T" || text == "n")
+        .map(|&(_, class)| class)
+        .collect();
+    assert_eq!(heads, [Production, Production, Lexical]);
+}
+
+#[test]
+fn unusable_input_is_an_error() {
+    let tokens = classes("S -> \"a\" ) ;\n");
+    assert!(tokens.contains(&(")".to_string(), Error)), "{:?}", tokens);
+    let tokens = classes("S -> \"unterminated\n");
+    assert!(
+        tokens.iter().any(|&(_, class)| class == Error),
+        "{:?}",
+        tokens
+    );
+}
+
+#[test]
+fn html_escapes_and_ansi_keeps_the_text() {
+    let source = "S -> \"<&>\" ;\n";
+    let html = highlight::html(source);
+    assert!(
+        html.contains("<span class=\"literal\">&quot;&lt;&amp;&gt;&quot;</span>"),
+        "{}",
+        html
+    );
+    let ansi = highlight::ansi(source);
+    let mut plain = String::new();
+    let mut rest = ansi.as_str();
+    while let Some(start) = rest.find('\x1b') {
+        plain.push_str(&rest[..start]);
+        rest = &rest[start + rest[start..].find('m').unwrap() + 1..];
+    }
+    plain.push_str(rest);
+    assert_eq!(plain, source);
+}
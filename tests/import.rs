@@ -0,0 +1,74 @@
+extern crate gideon;
+
+use gideon::{FrontendError, Grammar, Import, Imported, Level};
+
+//The grammar as Gideon source lowered again, and the warnings of the import
+fn import(format: Import, source: &str) -> (Grammar, Vec<(FrontendError, usize, usize)>) {
+    let Imported { grammar, warnings } = format.import(source).unwrap();
+    let grammar = gideon::lower(&grammar.to_string()).unwrap();
+    assert!(!grammar.analyze().has_errors());
+    let warnings = warnings.iter().map(|w| (w.what, w.line, w.offset)).collect();
+    (grammar, warnings)
+}
+
+fn accepts(grammar: &Grammar, input: &str) -> bool {
+    let start = grammar.entry_points()[0];
+    gideon::parse(grammar, Level::Tokens, start, input).is_ok()
+}
+
+const PEST: &str = r#"
+WHITESPACE = _{ " " | "\t" | "\n" }
+COMMENT = _{ "//" ~ (!"\n" ~ ANY)* }
+ident = @{ ASCII_ALPHA ~ ASCII_ALPHANUMERIC* }
+item = { ident ~ ";" }
+file = { SOI ~ item* ~ EOI }
+"#;
+
+#[test]
+fn pest_atomic_rules_become_lexical() {
+    let (grammar, warnings) = import(Import::Pest, PEST);
+    assert!(warnings.is_empty());
+    assert_eq!(grammar.entry_points(), ["file"]);
+    assert!(grammar.lexical_rule("ident").is_some() && grammar.production("item").is_some());
+    assert!(accepts(&grammar, "ab; c1 ; // note\nd;"));
+    assert!(!accepts(&grammar, "a b;"));
+    let stack = Import::Pest.import("tag = { PUSH(\"a\") ~ POP }\n");
+    assert_eq!(stack.unwrap_err().what, FrontendError::UnsupportedStackOperation);
+}
+
+const ANTLR: &str = r#"
+grammar Calc;
+prog : stat* EOF ;
+stat : e=expr ';' { System.out.println($e.text); } # print
+     ;
+expr : expr ('*'|'/') expr | INT | STR ;
+INT : [0-9]+ ;
+STR : '"' .*? '"' ;
+WS : [ \t\r\n]+ -> skip ;
+"#;
+
+#[test]
+fn antlr_rules_carry_over_with_warnings() {
+    let (grammar, warnings) = import(Import::Antlr, ANTLR);
+    assert_eq!(warnings, [(FrontendError::DroppedAction, 4, 19), (FrontendError::DroppedAlternativeLabel, 4, 52)]);
+    assert_eq!(grammar.entry_points(), ["prog"]);
+    assert!(accepts(&grammar, "1 * 2;\n\"a;b\";"));
+    assert!(!accepts(&grammar, "1 * ;"));
+}
+
+const W3C: &str = r#"
+[1] doc ::= item+
+[2] item ::= Name ";" [ wfc: Unique ]
+[3] Name ::= Char+ - "end"
+[4] Char ::= [a-z]
+"#;
+
+#[test]
+fn w3c_rules_import_with_numbers_and_constraints() {
+    let (grammar, warnings) = import(Import::W3cEbnf, W3C);
+    let expected = [(FrontendError::DroppedConstraint, 3, 23), (FrontendError::ApproximatedException, 4, 20)];
+    assert_eq!(warnings, expected);
+    assert_eq!(grammar.entry_points(), ["doc"]);
+    assert!(accepts(&grammar, "ab;cd;"));
+    assert!(!accepts(&grammar, "end;"));
+}
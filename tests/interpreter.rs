@@ -0,0 +1,156 @@
+extern crate gideon;
+
+use gideon::{Level, Node};
+
+const LIST: &str = r#"
+start List ;
+skip {ws} ;
+{ws} -> [ \t\n]+ ;
+{number} -> [0-9]+ ;
+{string} -> "\"" [^"]* "\"" ;
+List -> "[" Value* % "," "]" ;
+Value -> {number} | {string} | List ;
+"#;
+
+fn parse(source: &str, level: Level, input: &str) -> Node {
+    let grammar = gideon::lower(source).expect("grammar");
+    gideon::parse(&grammar, level, "List", input).expect("input")
+}
+
+fn children(node: &Node) -> &[Node] {
+    match *node {
+        Node::Production { ref children, .. } => children,
+        Node::Token { .. } => panic!("token {:?}", node),
+    }
+}
+
+fn text(node: &Node) -> &str {
+    match *node {
+        Node::Token { ref text, .. } => text,
+        Node::Production { ref name, .. } => panic!("production {}", name),
+    }
+}
+
+#[test]
+fn repetitions_are_spliced_into_the_production() {
+    for &level in &[Level::Tokens, Level::Scannerless] {
+        let tree = parse(LIST, level, "[1, \"a\", [2]]");
+        let texts: Vec<&str> = children(&tree)
+            .iter()
+            .map(|child| match *child {
+                Node::Production { ref children, .. } => match children[0] {
+                    Node::Production { ref name, .. } => name.as_str(),
+                    ref token => text(token),
+                },
+                ref token => text(token),
+            })
+            .collect();
+        assert_eq!(texts, ["[", "1", ",", "\"a\"", ",", "List", "]"]);
+    }
+}
+
+#[test]
+fn long_repetitions_do_not_overflow_the_stack() {
+    let input = format!("[{}]", vec!["1"; 20_000].join(","));
+    let tree = parse(LIST, Level::Tokens, &input);
+    assert_eq!(children(&tree).len(), 2 * 20_000 + 1);
+
+    let input = format!("[\"{}\"]", "a".repeat(20_000));
+    let tree = parse(LIST, Level::Scannerless, &input);
+    let value = &children(&tree)[1];
+    assert_eq!(text(&children(value)[0]).len(), 20_002);
+}
+
+#[test]
+fn tokens_keep_their_positions() {
+    let tree = parse(LIST, Level::Tokens, "[\n  1,\n  22]");
+    match children(&children(&tree)[3])[0] {
+        Node::Token { ref text, line, offset, .. } => {
+            assert_eq!((text.as_str(), line, offset), ("22", 3, 2));
+        }
+        ref node => panic!("{:?}", node),
+    }
+}
+
+#[test]
+fn predicates_select_alternatives() {
+    let source = r#"
+        start S ;
+        skip {ws} ;
+        {ws} -> " "+ ;
+        {id} -> [a-z]+ ![a-z] ;
+        S -> Word* ;
+        Word -> !"end" {id} | "end" ;
+    "#;
+    let grammar = gideon::lower(source).unwrap();
+    //scannerless, the operand skips the space ahead of it like {id} does
+    for &level in &[Level::Tokens, Level::Scannerless] {
+        let tree = gideon::parse(&grammar, level, "S", "ab end cd").unwrap();
+        let words: Vec<String> = children(&tree)
+            .iter()
+            .map(|word| match children(word)[0] {
+                Node::Token { ref symbol, .. } => symbol.to_string(),
+                ref node => panic!("{:?}", node),
+            })
+            .collect();
+        assert_eq!(words, ["{id}", "\"end\"", "{id}"], "{:?}", level);
+        assert!(gideon::parse(&grammar, level, "S", "").is_ok());
+    }
+}
+
+#[test]
+fn trees_through_cycles_fail_as_ambiguous() {
+    let grammar = gideon::lower("start S ;\nS -> A ;\nA -> B ;\nB -> A | C ;\nC -> A | \"a\" ;\n").unwrap();
+    let interpreter = gideon::Interpreter::new(&grammar, Level::Tokens).unwrap();
+    let error = interpreter.parse("S", &['a']).unwrap_err();
+    assert_eq!(error, gideon::FrontendError::CyclicAmbiguity);
+    assert_eq!((interpreter.line(), interpreter.offset()), (1, 0));
+}
+
+#[test]
+fn rejected_input_is_located() {
+    let grammar = gideon::lower(LIST).unwrap();
+    let interpreter = gideon::Interpreter::new(&grammar, Level::Tokens).unwrap();
+    let input: Vec<char> = "[1,\n 2 3]".chars().collect();
+    assert!(interpreter.parse("List", &input).is_err());
+    assert_eq!((interpreter.line(), interpreter.offset()), (2, 3));
+    assert!(interpreter.parse("Value", &input).is_err());
+
+    //a complete value followed by a unit nothing expects
+    let interpreter = gideon::Interpreter::new(&grammar, Level::Scannerless).unwrap();
+    let input: Vec<char> = "[1]\n $".chars().collect();
+    let error = interpreter.parse("List", &input).unwrap_err();
+    assert_eq!(error, gideon::FrontendError::UnexpectedInput);
+    assert_eq!((interpreter.line(), interpreter.offset()), (2, 1));
+}
+
+fn accepts(source: &str, start: &str, input: &str) -> bool {
+    let grammar = gideon::lower(source).expect("grammar");
+    gideon::parse(&grammar, Level::Tokens, start, input).is_ok()
+}
+
+#[test]
+fn separated_lists() {
+    let plain = "S -> \"(\" \"a\"* % \",\" \")\" ;";
+    for &(input, accepted) in &[("()", true), ("(a)", true), ("(a,a)", true), ("(a,)", false), ("(,a)", false)] {
+        assert_eq!(accepts(plain, "S", input), accepted, "{}", input);
+    }
+    let trailing = "S -> \"(\" \"a\"+ %% \",\" \")\" ;";
+    for &(input, accepted) in &[("()", false), ("(a)", true), ("(a,a,)", true), ("(a,,)", false), ("(,)", false)] {
+        assert_eq!(accepts(trailing, "S", input), accepted, "{}", input);
+    }
+    let optional = gideon::lower("S -> \"a\"? % \",\" ;");
+    assert_eq!(optional.unwrap_err(), gideon::FrontendError::InvalidSeparatedList);
+}
+
+#[test]
+fn parameterized_productions_are_instantiated() {
+    let source = "Pair<K, V> -> K \":\" V ;\nNest<T> -> \"(\" Nest<T> \")\" | T ;\nS -> Pair<\"a\", Nest<Pair<\"b\", \"c\">>> ;";
+    assert!(accepts(source, "S", "a:b:c"));
+    assert!(accepts(source, "S", "a:((b:c))"));
+    assert!(!accepts(source, "S", "a:(b:c"));
+    let grammar = gideon::lower(source).unwrap();
+    assert_eq!(grammar.productions.iter().filter(|prod| prod.name.starts_with("Pair")).count(), 2);
+    let mismatch = gideon::lower("Pair<K, V> -> K V ;\nS -> Pair<\"a\"> ;");
+    assert_eq!(mismatch.unwrap_err(), gideon::FrontendError::ArgumentCountMismatch);
+}
@@ -0,0 +1,136 @@
+extern crate gideon;
+
+use gideon::{FrontendError, Grammar, Level, Loader};
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn load(source: &str) -> (Result<Grammar, FrontendError>, Loader) {
+    let mut loader = Loader::new(env!("CARGO_MANIFEST_DIR"));
+    let grammar = loader.load_source("test.gideon", source);
+    (grammar, loader)
+}
+
+#[test]
+fn starts_may_name_imported_and_inherited_rules() {
+    let (grammar, _) = load("use std::json::Value ;\nstart Value ;\n");
+    assert_eq!(grammar.unwrap().entry_points(), ["Value"]);
+    let (grammar, _) = load("extends std::json ;\nstart Value, Pair ;\n");
+    assert_eq!(grammar.unwrap().entry_points(), ["Value", "Pair"]);
+}
+
+#[test]
+fn undefined_starts_are_located() {
+    let (grammar, loader) = load("use std::json::Value ;\nstart Value,\n  Missing ;\n");
+    assert_eq!(grammar.unwrap_err(), FrontendError::UnknownProduction);
+    assert_eq!((loader.line(), loader.offset()), (3, 2));
+}
+
+#[test]
+fn instances_of_imported_files_keep_apart() {
+    let source = "use std::json ;\nuse std::list::CommaList ;\nstart Doc ;\nDoc -> \"(\" CommaList<Value> \")\" ;\n";
+    let (grammar, _) = load(source);
+    let grammar = grammar.unwrap();
+    let names: Vec<&str> = grammar.productions.iter().map(|p| p.name.as_str()).collect();
+    assert!(names.contains(&"CommaList1"), "{:?}", names);
+    assert!(names.contains(&"StdJsonCommaList1"), "{:?}", names);
+    assert!(!grammar.analyze().has_errors());
+}
+
+#[test]
+fn json_strings_reject_only_c0_controls() {
+    let (grammar, _) = load("extends std::json ;\n");
+    let grammar = grammar.unwrap();
+    let string = |text: &str| gideon::parse(&grammar, Level::Tokens, "Value", text).is_ok();
+    assert!(string("\"a\u{7f}b\u{85}\""));
+    assert!(!string("\"a\u{1f}b\""));
+    assert!(!string("\"a\nb\""));
+}
+
+//A directory of its own holding `files`
+fn directory(files: &[(&str, &str)]) -> PathBuf {
+    static DIRS: AtomicUsize = AtomicUsize::new(0);
+    let n = DIRS.fetch_add(1, Ordering::SeqCst);
+    let root = env::temp_dir().join(format!("gideon-loader-{}-{}", process::id(), n));
+    for &(file, text) in files {
+        let file = root.join(file);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, text).unwrap();
+    }
+    root
+}
+
+//Loads `source` from a directory holding `files`
+fn load_with(files: &[(&str, &str)], source: &str) -> Result<Grammar, FrontendError> {
+    let root = directory(files);
+    let grammar = Loader::new(&root).load_source("test.gideon", source);
+    let _ = fs::remove_dir_all(&root);
+    grammar
+}
+
+const EXPR: &str = "Expr -> Term \"+\" Expr | Term ;\nTerm -> {digit} ;\n{digit} -> [0-9] ;\nUnused -> \"u\" ;\n";
+
+#[test]
+fn use_imports_a_file_or_one_rule() {
+    let files = [("lang/expr.gideon", EXPR)];
+    let grammar = load_with(&files, "use lang::expr ;\nstart S ;\nS -> Expr ;\n");
+    let grammar = grammar.unwrap();
+    assert!(grammar.production("Unused").is_some());
+    assert!(gideon::parse(&grammar, gideon::Level::Tokens, "S", "1+2").is_ok());
+    let grammar = load_with(&files, "use lang::expr::Expr ;\nstart S ;\nS -> Expr ;\n");
+    let grammar = grammar.unwrap();
+    assert!(grammar.production("Term").is_some() && grammar.lexical_rule("digit").is_some());
+    assert!(grammar.production("Unused").is_none());
+}
+
+#[test]
+fn use_failures_are_located() {
+    let (grammar, loader) = load("start S ;\nuse lang::missing ;\nS -> \"s\" ;\n");
+    assert_eq!(grammar.unwrap_err(), FrontendError::UnresolvedPath);
+    assert_eq!(loader.line(), 2);
+    let files = [("a.gideon", "use b ;\nA -> B ;\n"), ("b.gideon", "use a ;\nB -> \"b\" ;\n")];
+    let grammar = load_with(&files, "use a ;\n");
+    assert_eq!(grammar.unwrap_err(), FrontendError::CyclicUse);
+    let files = [("lang/expr.gideon", EXPR)];
+    let grammar = load_with(&files, "use lang::expr ;\nTerm -> \"t\" ;\n");
+    assert_eq!(grammar.unwrap_err(), FrontendError::NameCollision);
+}
+
+#[test]
+fn extends_overrides_and_appends_alternatives() {
+    let grammar = gideon::load(concat!(env!("CARGO_MANIFEST_DIR"), "/language/jsonc.gideon")).unwrap();
+    assert_eq!(grammar.entry_points(), ["Value"]);
+    let input = "{\"a\": [1, NaN, Infinity,], // note\n\"b\": /* none */ null,}";
+    assert!(gideon::parse(&grammar, gideon::Level::Tokens, "Value", input).is_ok());
+    let json = gideon::load(concat!(env!("CARGO_MANIFEST_DIR"), "/language/json.gideon")).unwrap();
+    assert!(gideon::parse(&json, gideon::Level::Tokens, "Value", "[1,]").is_err());
+}
+
+#[test]
+fn inherited_rules_are_redefined_explicitly() {
+    let files = [("lang/expr.gideon", EXPR)];
+    let grammar = load_with(&files, "extends lang::expr ;\noverride Term -> {digit} | \"(\" Expr \")\" ;\n");
+    assert!(gideon::parse(&grammar.unwrap(), gideon::Level::Tokens, "Expr", "1+(2+3)").is_ok());
+    let grammar = load_with(&files, "extends lang::expr ;\nTerm +-> \"x\" ;\n");
+    assert!(gideon::parse(&grammar.unwrap(), gideon::Level::Tokens, "Expr", "1+x").is_ok());
+    let grammar = load_with(&files, "extends lang::expr ;\noverride Factor -> \"f\" ;\n");
+    assert_eq!(grammar.unwrap_err(), FrontendError::UnknownOverride);
+    let grammar = load_with(&files, "extends lang::expr ;\nTerm -> \"t\" ;\n");
+    assert_eq!(grammar.unwrap_err(), FrontendError::NameCollision);
+}
+
+#[test]
+fn files_loaded_before_keep_their_instances_apart() {
+    let a = "List<T> -> T* ;\nA -> List<\"a\"> ;\n";
+    let root = directory(&[("a.gideon", a), ("b.gideon", "use a ;\nB -> List<\"b\"> A ;\n")]);
+    let mut loader = Loader::new(&root);
+    let first = loader.load("a.gideon");
+    let second = loader.load("b.gideon");
+    let _ = fs::remove_dir_all(&root);
+    assert!(first.unwrap().production("List1").is_some());
+    let second = second.unwrap();
+    assert!(second.production("List1").is_some() && second.production("AList1").is_some());
+}
@@ -0,0 +1,210 @@
+extern crate gideon;
+
+use gideon::json::{self, object, Json};
+
+use std::fs;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+const GRAMMAR: &str = "start Expr ;
+skip {ws} ;
+{ws} -> \" \"+ ;
+{num} -> [0-9]+ ;
+Expr -> Expr \"+\" Term | Term ;
+Term -> {num} ;
+";
+
+fn request(id: usize, method: &str, params: Json) -> Json {
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id.into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    object(vec![("jsonrpc", "2.0".into()), ("method", method.into()), ("params", params)])
+}
+
+fn at(uri: &str, line: usize, character: usize) -> Vec<(&'static str, Json)> {
+    vec![
+        ("textDocument", object(vec![("uri", uri.into())])),
+        ("position", object(vec![("line", line.into()), ("character", character.into())])),
+    ]
+}
+
+//Sends `messages` to `gideon lsp` and returns the exit status and the
+//messages it wrote
+fn session(messages: &[Json]) -> (Option<i32>, Vec<Json>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gideon"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    {
+        let stdin = child.stdin.as_mut().unwrap();
+        for message in messages {
+            let body = message.to_string();
+            write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        }
+    }
+    let mut output = String::new();
+    child.stdout.take().unwrap().read_to_string(&mut output).unwrap();
+    let status = child.wait().unwrap();
+    let mut replies = Vec::new();
+    let mut rest = output.as_str();
+    while let Some(start) = rest.find("\r\n\r\n") {
+        let length: usize = rest[..start].trim_start_matches("Content-Length: ").parse().unwrap();
+        let body = &rest[start + 4..start + 4 + length];
+        replies.push(json::parse(body).unwrap());
+        rest = &rest[start + 4 + length..];
+    }
+    (status.code(), replies)
+}
+
+fn result(replies: &[Json], id: usize) -> &Json {
+    let reply = replies.iter().find(|reply| reply.get("id").as_usize() == Some(id));
+    reply.unwrap_or_else(|| panic!("no reply to {}", id)).get("result")
+}
+
+fn range(json: &Json) -> (usize, usize, usize, usize) {
+    let (start, end) = (json.get("start"), json.get("end"));
+    let position = |p: &Json| (p.get("line").as_usize().unwrap(), p.get("character").as_usize().unwrap());
+    let ((a, b), (c, d)) = (position(start), position(end));
+    (a, b, c, d)
+}
+
+#[test]
+fn scripted_session() {
+    let dir = std::env::temp_dir().join(format!("gideon-lsp-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("expr.gideon"), GRAMMAR).unwrap();
+    let uri = format!("file://{}", dir.join("expr.gideon").display());
+    let document = object(vec![
+        ("uri", uri.as_str().into()),
+        ("languageId", "gideon".into()),
+        ("version", 1.into()),
+        ("text", GRAMMAR.into()),
+    ]);
+    let mut rename = at(&uri, 4, 17);
+    rename.push(("newName", "Factor".into()));
+    let mut references = at(&uri, 4, 17);
+    references.push(("context", object(vec![("includeDeclaration", true.into())])));
+    let messages = vec![
+        request(1, "initialize", object(vec![("capabilities", object(Vec::new()))])),
+        notification("initialized", object(Vec::new())),
+        notification("textDocument/didOpen", object(vec![("textDocument", document)])),
+        request(2, "textDocument/definition", object(at(&uri, 4, 17))),
+        request(3, "textDocument/references", object(references)),
+        request(4, "textDocument/rename", object(rename)),
+        request(5, "textDocument/hover", object(at(&uri, 4, 0))),
+        request(6, "textDocument/completion", object(at(&uri, 5, 9))),
+        request(7, "textDocument/documentSymbol", object(at(&uri, 0, 0))),
+        request(8, "textDocument/semanticTokens/full", object(at(&uri, 0, 0))),
+        request(9, "shutdown", Json::Null),
+        request(10, "textDocument/hover", object(at(&uri, 4, 0))),
+        notification("exit", Json::Null),
+    ];
+    let (status, replies) = session(&messages);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(status, Some(0));
+
+    let capabilities = result(&replies, 1).get("capabilities");
+    assert!(!capabilities.get("definitionProvider").is_null());
+    let diagnostics = replies
+        .iter()
+        .find(|reply| reply.get("method").as_str() == Some("textDocument/publishDiagnostics"))
+        .unwrap();
+    assert_eq!(diagnostics.get("params").get("uri").as_str(), Some(uri.as_str()));
+    assert!(diagnostics.get("params").get("diagnostics").as_array().is_empty());
+
+    //Term used in Expr is declared on the last line
+    let definition = result(&replies, 2);
+    assert_eq!(definition.get("uri").as_str(), Some(uri.as_str()));
+    assert_eq!(range(definition.get("range")), (5, 0, 5, 4));
+
+    let references = result(&replies, 3).as_array();
+    let mut references: Vec<_> = references.iter().map(|location| range(location.get("range"))).collect();
+    references.sort();
+    assert_eq!(references, [(4, 17, 4, 21), (4, 24, 4, 28), (5, 0, 5, 4)]);
+
+    let edits = result(&replies, 4).get("changes").get(&uri).as_array();
+    assert_eq!(edits.len(), 3);
+    assert!(edits.iter().all(|edit| edit.get("newText").as_str() == Some("Factor")));
+
+    let hover = result(&replies, 5).get("contents").get("value").as_str().unwrap();
+    assert!(hover.contains("nullable: no"), "{}", hover);
+    assert!(hover.contains("first:    {num}"), "{}", hover);
+
+    //inside braces only lexical rules fit
+    let completion = result(&replies, 6).as_array();
+    let mut labels: Vec<&str> = completion.iter().filter_map(|item| item.get("label").as_str()).collect();
+    labels.sort();
+    assert_eq!(labels, ["num", "ws"]);
+
+    let symbols = result(&replies, 7).as_array();
+    let symbols: Vec<&str> = symbols.iter().filter_map(|symbol| symbol.get("name").as_str()).collect();
+    for name in &["Expr", "Term", "{num}", "{ws}"] {
+        assert!(symbols.contains(name), "{} in {:?}", name, symbols);
+    }
+
+    let data = result(&replies, 8).get("data").as_array();
+    assert!(!data.is_empty() && data.len().is_multiple_of(5));
+
+    assert!(result(&replies, 9).is_null());
+    let refused = replies.iter().find(|reply| reply.get("id").as_usize() == Some(10)).unwrap();
+    assert!(!refused.get("error").is_null());
+}
+
+fn open(uri: &str, text: &str) -> Json {
+    let document = object(vec![
+        ("uri", uri.into()),
+        ("languageId", "gideon".into()),
+        ("version", 1.into()),
+        ("text", text.into()),
+    ]);
+    notification("textDocument/didOpen", object(vec![("textDocument", document)]))
+}
+
+#[cfg(unix)]
+#[test]
+fn workspaces_hold_the_files_used_from_open_documents() {
+    let dir = std::env::temp_dir().join(format!("gideon-lsp-use-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let dir = fs::canonicalize(&dir).unwrap();
+    let main = "use lib ;\nstart S ;\nS -> Item+ ;\n";
+    fs::write(dir.join("main.gideon"), main).unwrap();
+    fs::write(dir.join("lib.gideon"), "Item -> \"a\" | \"b\" ;\n").unwrap();
+    //uses lib but is neither open nor used from an open document
+    fs::write(dir.join("other.gideon"), "use lib ;\nX -> Item ;\n").unwrap();
+    //a directory walk would go round until the path is too long
+    std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+    let uri = |file: &str| format!("file://{}", dir.join(file).display());
+    let mut references = at(&uri("main.gideon"), 2, 5);
+    references.push(("context", object(vec![("includeDeclaration", true.into())])));
+    let messages = vec![
+        request(1, "initialize", object(Vec::new())),
+        open(&uri("main.gideon"), main),
+        request(2, "textDocument/definition", object(at(&uri("main.gideon"), 2, 5))),
+        request(3, "textDocument/references", object(references)),
+        //the unsaved text of lib.gideon replaces the file
+        open(&uri("lib.gideon"), "Extra -> \"c\" ;\nItem -> \"a\" | \"b\" ;\n"),
+        request(4, "textDocument/definition", object(at(&uri("main.gideon"), 2, 5))),
+        request(5, "shutdown", Json::Null),
+        notification("exit", Json::Null),
+    ];
+    let (status, replies) = session(&messages);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(status, Some(0));
+
+    let definition = result(&replies, 2);
+    assert_eq!(definition.get("uri").as_str(), Some(uri("lib.gideon").as_str()));
+    assert_eq!(range(definition.get("range")), (0, 0, 0, 4));
+    let references = result(&replies, 3).as_array();
+    let files: Vec<&str> = references.iter().filter_map(|location| location.get("uri").as_str()).collect();
+    assert_eq!(files.len(), 2);
+    assert!(files.iter().all(|file| !file.ends_with("other.gideon")));
+    assert_eq!(range(result(&replies, 4).get("range")), (1, 0, 1, 4));
+}
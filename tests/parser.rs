@@ -0,0 +1,45 @@
+extern crate gideon;
+
+use gideon::{FrontendError, Level, Parser};
+
+//The first error of `source` and where it is
+fn first_error(source: &str) -> Option<(FrontendError, usize, usize)> {
+    let chars: Vec<char> = source.chars().collect();
+    let parser = Parser::new(&chars);
+    match parser.parse() {
+        Ok(tree) => parser.first_error(&tree),
+        Err(what) => Some((what, parser.line(), parser.offset())),
+    }
+}
+
+#[test]
+fn unterminated_tokens_are_located_at_their_start() {
+    let error = first_error("start S ;\nS -> \"a\" \"bc ;\n");
+    assert_eq!(error, Some((FrontendError::UnterminatedLiteral, 2, 9)));
+    let error = first_error("start S ;\nS -> 'a' |\n  'b ;\n");
+    assert_eq!(error, Some((FrontendError::UnterminatedLiteral, 3, 2)));
+    let error = first_error("start S ;\nS -> \"a\" [bc ;\n");
+    assert_eq!(error, Some((FrontendError::ExpectedCloseBracket, 2, 9)));
+}
+
+#[test]
+fn keywords_name_productions_outside_of_declarations() {
+    let source = "start start ;
+skip {ws} ;
+{ws} -> \" \"+ ;
+start -> skip layout extends<use> ;
+skip -> \"s\" ;
+layout? : u8 -> \"l\" ;
+extends<T> -> T override ;
+override -> {skip} ;
+use -> \"u\" ;
+{skip} -> \"k\" ;
+";
+    assert_eq!(first_error(source), None);
+    let grammar = gideon::lower(source).unwrap();
+    assert_eq!(grammar.entry_points(), ["start"]);
+    assert!(grammar.production("skip").is_some() && grammar.production("override").is_some());
+    assert!(gideon::parse(&grammar, Level::Tokens, "start", "s l u k").is_ok());
+    assert!(gideon::parse(&grammar, Level::Tokens, "start", "s u k").is_ok());
+    assert!(gideon::parse(&grammar, Level::Tokens, "start", "s l u").is_err());
+}
@@ -0,0 +1,137 @@
+extern crate gideon;
+
+use gideon::{FrontendError, Grammar, Level, Scanner, Trivia};
+
+fn lexemes(grammar: &Grammar, input: &str) -> Vec<(String, String)> {
+    let input: Vec<char> = input.chars().collect();
+    let scanner = Scanner::new(grammar, &input);
+    scanner
+        .tokenize()
+        .expect("input")
+        .iter()
+        .map(|lexeme| (lexeme.symbol().to_string(), lexeme.text()))
+        .collect()
+}
+
+fn owned(lexemes: &[(&str, &str)]) -> Vec<(String, String)> {
+    lexemes.iter().map(|&(symbol, text)| (symbol.to_string(), text.to_string())).collect()
+}
+
+#[test]
+fn long_repetitions_scan_in_one_lexeme() {
+    let grammar = gideon::lower(r#"
+        S -> {string}* ;
+        {string} -> "\"" ([^"\\] | "\\" \p{Any})* "\"" ;
+    "#).unwrap();
+    let text = format!("\"{}\"", "a\\\"".repeat(50_000));
+    let scanned = lexemes(&grammar, &text);
+    assert_eq!(scanned.len(), 1);
+    assert_eq!(scanned[0].1.len(), text.len());
+}
+
+const COMMENTED: &str = r##"
+    start S ;
+    skip {ws} | {comment} ;
+    {ws} -> [ \n]+ ;
+    {comment} -> "#" [^\n]* ;
+    {name} -> [a-z]+ ;
+    S -> {name}* ;
+"##;
+
+#[test]
+fn skipped_text_is_dropped() {
+    let grammar = gideon::lower(COMMENTED).unwrap();
+    let scanned = lexemes(&grammar, "a # one\n  bc # two\n");
+    assert_eq!(scanned, owned(&[("{name}", "a"), ("{name}", "bc")]));
+}
+
+#[test]
+fn skipped_text_attaches_to_the_next_lexeme() {
+    let grammar = gideon::lower(COMMENTED).unwrap();
+    let input: Vec<char> = "a # one\n  bc # two\n".chars().collect();
+    let scanner = Scanner::with_trivia(&grammar, &input, Trivia::Attach);
+    let scanned = scanner.tokenize().unwrap();
+    let trivia: Vec<String> = scanned.iter().map(|lexeme| lexeme.trivia().iter().collect()).collect();
+    assert_eq!(trivia, ["", " # one\n  "]);
+    assert_eq!(scanner.trailing_trivia().iter().collect::<String>(), " # two\n");
+    assert_eq!((scanned[1].line(), scanned[1].offset()), (2, 2));
+}
+
+const INTERPOLATED: &str = r#"
+    start S ;
+    skip {ws} ;
+    {ws} -> " "+ ;
+    {name} -> [a-z]+ ;
+    {quote} push Text -> "\"" ;
+    {text} in Text -> [^"$]+ ;
+    {open} in Text push default -> "${" ;
+    {close} pop -> "}" ;
+    {end} in Text pop -> "\"" ;
+    S -> ({name} | {quote} Part* {end})* ;
+    Part -> {text} | {open} {name} {close} ;
+"#;
+
+#[test]
+fn modes_switch_the_active_terminals() {
+    let grammar = gideon::lower(INTERPOLATED).unwrap();
+    let scanned = lexemes(&grammar, "ab \"x ${cd} y\" e");
+    let expected = [
+        ("{name}", "ab"),
+        ("{quote}", "\""),
+        ("{text}", "x "),
+        ("{open}", "${"),
+        ("{name}", "cd"),
+        ("{close}", "}"),
+        ("{text}", " y"),
+        ("{end}", "\""),
+        ("{name}", "e"),
+    ];
+    assert_eq!(scanned, owned(&expected));
+}
+
+#[test]
+fn popping_the_default_mode_fails() {
+    let grammar = gideon::lower(INTERPOLATED).unwrap();
+    let input: Vec<char> = "ab }".chars().collect();
+    let scanner = Scanner::new(&grammar, &input);
+    assert_eq!(scanner.tokenize().unwrap_err(), FrontendError::UnbalancedModePop);
+}
+
+const BLOCKS: &str = r#"
+    start Block ;
+    layout ;
+    {name} -> [a-z]+ ;
+    Block -> ({name} {NEWLINE} ({INDENT} Block {DEDENT})?)* ;
+"#;
+
+#[test]
+fn layout_makes_indentation_tokens() {
+    let grammar = gideon::lower(BLOCKS).unwrap();
+    let scanned = lexemes(&grammar, "a\n  b\n\n  c\n    d\ne\n");
+    let expected = [
+        ("{name}", "a"),
+        ("{NEWLINE}", ""),
+        ("{INDENT}", ""),
+        ("{name}", "b"),
+        ("{NEWLINE}", ""),
+        ("{name}", "c"),
+        ("{NEWLINE}", ""),
+        ("{INDENT}", ""),
+        ("{name}", "d"),
+        ("{NEWLINE}", ""),
+        ("{DEDENT}", ""),
+        ("{DEDENT}", ""),
+        ("{name}", "e"),
+        ("{NEWLINE}", ""),
+    ];
+    assert_eq!(scanned, owned(&expected));
+}
+
+#[test]
+fn layout_blocks_parse() {
+    let grammar = gideon::lower(BLOCKS).unwrap();
+    assert!(!grammar.analyze().has_errors());
+    assert!(gideon::parse(&grammar, Level::Tokens, "Block", "a\n  b\n  c\nd").is_ok());
+    let dedent = gideon::parse(&grammar, Level::Tokens, "Block", "a\n    b\n  c\n");
+    assert_eq!(dedent.unwrap_err(), FrontendError::InconsistentDedent);
+}
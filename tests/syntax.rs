@@ -0,0 +1,75 @@
+extern crate gideon;
+
+use gideon::syntax;
+use gideon::{Parser, SyntaxKind, SyntaxNode, Trivia};
+
+fn parse(source: &str) -> SyntaxNode {
+    let chars: Vec<char> = source.chars().collect();
+    syntax::parse(&chars)
+}
+
+//The tree of `source` holds it byte for byte, in its tokens in order
+fn assert_lossless(source: &str) {
+    let chars: Vec<char> = source.chars().collect();
+    let tree = syntax::parse(&chars);
+    assert_eq!(tree.text(), source);
+    assert_eq!(tree.to_string(), source);
+    assert_eq!(tree.range(), (0, chars.len()));
+    let tokens: String = tree.tokens().iter().map(|token| token.text()).collect();
+    assert_eq!(tokens, source);
+    let parser = Parser::with_trivia(&chars, Trivia::Attach);
+    assert_eq!(syntax::build(&chars, &parser.parse()).to_string(), source);
+}
+
+#[test]
+fn trees_keep_the_source() {
+    let sources = [
+        "start S ;\nskip {ws} ;\n{ws} -> \" \"+ ;\nS -> A* % \",\" | (\"b\")? ;\nA -> \"a\" ;\n",
+        "start S;S->\"a\"|None;",
+        "start S ;\r\n# comment\r\nS -> \"ä\" [α-ω]  \\p{Lu}\t;\r\n",
+        "use std::list ;\nextends base ;\noverride X -> 'x' ;\n",
+        "S : u8 -> n:{num} => { n + 1 } ;\n{num} -> [0-9]+ ;\n",
+        "layout ;\nItem<T>? -> T & !\"x\" ;\n",
+        "{KEY} -> \"k\" ![a-z] ;  # trailing\n",
+        "S -> \"😀\" \"🙂\"..\"🙃\" ;\n",
+    ];
+    for source in &sources {
+        assert_lossless(source);
+        let tree = parse(source);
+        assert!(tree.tokens().iter().all(|token| token.kind() != SyntaxKind::Error), "{:?}", source);
+    }
+}
+
+#[test]
+fn trees_of_broken_input_keep_the_source() {
+    let sources = [
+        "start S ;\nS -> \"a\" ( \"b\" ;\nT -> \"c\" ;\n",
+        "start S ;\nS -> \"unterminated\n",
+        "S -> [abc ;\n",
+        "S -> 'a' |\n  'b ;\n",
+        "-> ;; ) @ $\n",
+        "S -> \"a\" =>",
+        "S -> \"a\" => { unclosed",
+        "start",
+        "",
+        "   \n\t",
+        "# only a comment",
+        "😀 -> \"🙂\" ;\n",
+    ];
+    for source in &sources {
+        assert_lossless(source);
+        let tree = parse(source);
+        assert!(tree.tokens().iter().any(|token| token.kind() == SyntaxKind::Error), "{:?}", source);
+    }
+}
+
+#[test]
+fn comments_ahead_of_a_declaration_belong_to_it() {
+    let tree = parse("start S ;\n# items\nS -> \"a\" ;\n");
+    let nodes = tree.child_nodes();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1].kind(), SyntaxKind::Prod);
+    assert_eq!(nodes[1].text(), "\n# items\nS -> \"a\" ;");
+    let kinds: Vec<SyntaxKind> = nodes[1].tokens().iter().take(3).map(|token| token.kind()).collect();
+    assert_eq!(kinds, [SyntaxKind::Whitespace, SyntaxKind::Comment, SyntaxKind::Whitespace]);
+}
@@ -0,0 +1,65 @@
+extern crate gideon;
+
+use gideon::visitor::walk_prod;
+use gideon::{cst, FrontendError, Parser, Token, Visitor};
+
+//The tokens visited, the productions entered and the errors met
+#[derive(Default)]
+struct Walk {
+    tokens: Vec<String>,
+    prods: Vec<String>,
+    errors: Vec<FrontendError>,
+}
+
+impl<'a> Visitor<'a> for Walk {
+    fn visit_prod(&mut self, prod: &cst::Prod<'a>) {
+        match *prod.name() {
+            Ok(cst::ProdName::Name(Ok(name), _)) => self.prods.push(name.data().text()),
+            Ok(cst::ProdName::LexicalRuleName(_, Ok(name), _)) => {
+                self.prods.push(format!("{{{}}}", name.data().text()))
+            }
+            _ => {}
+        }
+        walk_prod(self, prod)
+    }
+
+    fn visit_token(&mut self, token: Token<'a>) {
+        self.tokens.push(token.data().text());
+    }
+
+    fn visit_error(&mut self, error: FrontendError) {
+        self.errors.push(error);
+    }
+}
+
+fn walk(source: &str) -> Walk {
+    let chars: Vec<char> = source.chars().collect();
+    let parser = Parser::new(&chars);
+    let mut walk = Walk::default();
+    walk.visit_grammar(&parser.parse().unwrap());
+    walk
+}
+
+#[test]
+fn tokens_are_visited_in_source_order() {
+    let walk = walk("use std::list ;\nskip {ws} ;\nS -> a:A* % \",\" | !\"x\" (B | [0-9]) ;\n");
+    let expected = [
+        "use", "std", "::", "list", ";", "skip", "{", "ws", "}", ";", "S", "->", "a", ":", "A", "*", "%", ",", "|",
+        "!", "x", "(", "B", "|", "0-9", ")", ";",
+    ];
+    assert_eq!(walk.tokens, expected);
+    assert!(walk.errors.is_empty());
+}
+
+#[test]
+fn overridden_methods_see_every_production() {
+    let walk = walk("start S ;\n{ws} -> \" \"+ ;\nS -> A ;\noverride A -> \"a\" ;\nextends base ;\n");
+    assert_eq!(walk.prods, ["{ws}", "S", "A"]);
+}
+
+#[test]
+fn missing_tokens_reach_visit_error() {
+    let walk = walk("start S ;\nS -> \"a\" ( \"b\" ;\nT -> \"c\" ;\n");
+    assert_eq!(walk.errors.first(), Some(&FrontendError::ExpectedCloseParen));
+    assert_eq!(walk.prods, ["S"]);
+}